@@ -2,6 +2,12 @@ follow-notification = {$name} followed you
 favourite-notification = {$name} favourited your toot
 reblog-notification = {$name} boosted your toot
 mention-notification = {$name} mentioned you
+follow-request-notification = {$name} has requested to follow you
+poll-notification = A poll has ended
+status-notification = {$name} just posted
+update-notification = {$name} edited a toot
+admin-sign-up-notification = {$name} signed up
+admin-report-notification = {$name} filed a report
 
 invalid-client-name = Invalid client name
 invalid-website = Invalid website
@@ -21,6 +27,9 @@ oauth-consent = {$name} would like to authenticate with your account.
     It has requested the following permissions:
 consent = Consent
 reject = Reject
+oauth-choose-account = Which account should it use?
+oauth-no-accounts = There are no accounts for you on this server
+oauth-invalid-account = Invalid account
 
 scope-read = Full read access to your account
 scope-read-accounts = Read access basic account information
@@ -50,11 +59,51 @@ scope-write-reports = Submit abuse reports
 scope-write-statuses = Post statuses
 scope-follow = Manage relationships with other accounts
 scope-push = Access to push notifications
+scope-admin-read = Read all data on the server as a moderator
+scope-admin-read-accounts = Read sensitive information of all accounts
+scope-admin-read-reports = Read sensitive information of all reports
+scope-admin-write = Modify all data on the server as a moderator
+scope-admin-write-accounts = Perform moderation actions on accounts
+scope-admin-write-reports = Perform moderation actions on reports
 
 error-no-permission = You do not have permission to perform this action
 account-not-found = Account not found
 error-status-not-found = Toot not found
 error-notification-not-found = Notification not found
+error-notification-request-not-found = Notification request not found
+error-invalid-notification-policy = Invalid notification policy
+error-invalid-report-category = Invalid report category
+error-report-comment-too-long = Report comment too long
+error-cannot-report-self = You can't report yourself
+error-invalid-alias = Account aliases must be HTTPS actor URIs of another account
+error-too-many-aliases = Too many account aliases
+error-cannot-move-to-self = You can't move your account to itself
+error-delete-confirmation-mismatch = The username given doesn't match your account
+error-invalid-username = Usernames can only contain letters, numbers and underscores
+error-username-taken = That username is already taken
+error-too-many-accounts = You can't create any more accounts
+error-unsupported-import-type = This type of data can't be imported
+error-invalid-import-mode = Invalid import mode
+error-invalid-import-file = The uploaded file is not a valid CSV file
+error-import-too-large = Too many rows in the uploaded file
+error-import-not-found = Import not found
+error-export-not-found = Export not found
+error-archive-rate-limited = You can only request one archive per week
+error-archive-not-found = Archive not found
+error-archive-not-ready = The archive is still being built
+error-archive-expired = The archive has expired
+error-archive-failed = The archive could not be built
+error-report-not-found = Report not found
+error-not-a-group = This account is not a group
+error-group-post-not-found = Group post not found
+error-group-post-already-reviewed = This group post has already been reviewed
+error-invalid-tag = Invalid hashtag
+error-too-many-featured-tags = You can't feature any more hashtags
+error-featured-tag-not-found = Featured hashtag not found
+error-endorse-not-following = You can only feature accounts you follow
+error-account-suspended = Your account has been suspended
+error-invalid-moderation-action = Invalid moderation action
+error-cannot-moderate-self = You can't perform moderation actions on yourself
 unsupported-media-type = Unsupported media type
 internal-server-error = Internal server error
 failed-to-decode-image = Failed to read image
@@ -0,0 +1,5 @@
+DROP TABLE trending_statuses;
+DROP TABLE trending_tags;
+ALTER TABLE statuses DROP COLUMN trendable;
+ALTER TABLE tags DROP COLUMN trendable;
+DROP TABLE status_tags;
\ No newline at end of file
@@ -0,0 +1,21 @@
+CREATE TABLE status_tags (
+    id UUID PRIMARY KEY,
+    status UUID NOT NULL REFERENCES statuses(id) ON DELETE CASCADE,
+    tag UUID NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    UNIQUE (status, tag)
+);
+
+ALTER TABLE tags ADD COLUMN trendable BOOLEAN NULL DEFAULT NULL;
+ALTER TABLE statuses ADD COLUMN trendable BOOLEAN NULL DEFAULT NULL;
+
+CREATE TABLE trending_tags (
+    tag UUID PRIMARY KEY REFERENCES tags(id) ON DELETE CASCADE,
+    score DOUBLE PRECISION NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE trending_statuses (
+    status UUID PRIMARY KEY REFERENCES statuses(id) ON DELETE CASCADE,
+    score DOUBLE PRECISION NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
\ No newline at end of file
@@ -0,0 +1,4 @@
+DROP TABLE notification_permissions;
+DROP TABLE notification_requests;
+DROP TABLE notification_policies;
+ALTER TABLE notifications DROP COLUMN filtered;
\ No newline at end of file
@@ -0,0 +1,29 @@
+ALTER TABLE notifications ADD COLUMN filtered BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE notification_policies (
+    account UUID PRIMARY KEY REFERENCES accounts(id) ON DELETE CASCADE,
+    for_not_following VARCHAR NOT NULL DEFAULT 'accept',
+    for_not_followers VARCHAR NOT NULL DEFAULT 'accept',
+    for_new_accounts VARCHAR NOT NULL DEFAULT 'accept',
+    for_private_mentions VARCHAR NOT NULL DEFAULT 'filter'
+);
+
+CREATE TABLE notification_requests (
+    id UUID PRIMARY KEY,
+    iid BIGSERIAL,
+    account UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    from_account UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    last_status UUID NULL REFERENCES statuses(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (account, from_account)
+);
+
+CREATE INDEX notification_requests_iid_idx ON notification_requests (iid);
+
+CREATE TABLE notification_permissions (
+    account UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    from_account UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (account, from_account)
+);
\ No newline at end of file
@@ -0,0 +1,3 @@
+ALTER TABLE notifications DROP COLUMN report;
+DROP TABLE reports;
+ALTER TABLE accounts DROP COLUMN moderator;
\ No newline at end of file
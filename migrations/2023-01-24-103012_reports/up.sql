@@ -0,0 +1,19 @@
+ALTER TABLE accounts ADD COLUMN moderator BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE reports (
+    id UUID PRIMARY KEY,
+    iid BIGSERIAL,
+    account UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    target_account UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    status_ids UUID[] NOT NULL DEFAULT '{}',
+    category VARCHAR NOT NULL DEFAULT 'other',
+    comment TEXT NOT NULL DEFAULT '',
+    forwarded BOOLEAN NOT NULL DEFAULT FALSE,
+    url VARCHAR NULL UNIQUE,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX reports_iid_idx ON reports (iid);
+
+ALTER TABLE notifications ADD COLUMN report UUID NULL REFERENCES reports(id) ON DELETE CASCADE;
\ No newline at end of file
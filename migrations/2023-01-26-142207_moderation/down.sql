@@ -0,0 +1,7 @@
+ALTER TABLE reports DROP COLUMN action_taken_by_account;
+ALTER TABLE reports DROP COLUMN action_taken_at;
+ALTER TABLE reports DROP COLUMN assigned_account;
+
+ALTER TABLE accounts DROP COLUMN sensitized_at;
+ALTER TABLE accounts DROP COLUMN silenced_at;
+ALTER TABLE accounts DROP COLUMN suspended_at;
\ No newline at end of file
@@ -0,0 +1,7 @@
+ALTER TABLE accounts ADD COLUMN suspended_at TIMESTAMP NULL;
+ALTER TABLE accounts ADD COLUMN silenced_at TIMESTAMP NULL;
+ALTER TABLE accounts ADD COLUMN sensitized_at TIMESTAMP NULL;
+
+ALTER TABLE reports ADD COLUMN assigned_account UUID NULL REFERENCES accounts(id) ON DELETE SET NULL;
+ALTER TABLE reports ADD COLUMN action_taken_at TIMESTAMP NULL;
+ALTER TABLE reports ADD COLUMN action_taken_by_account UUID NULL REFERENCES accounts(id) ON DELETE SET NULL;
\ No newline at end of file
@@ -0,0 +1,3 @@
+DROP TABLE instance_activity;
+DROP TABLE instance_peers;
+DROP TABLE instance_stats;
\ No newline at end of file
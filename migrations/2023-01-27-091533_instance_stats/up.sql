@@ -0,0 +1,21 @@
+CREATE TABLE instance_stats (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    user_count BIGINT NOT NULL,
+    status_count BIGINT NOT NULL,
+    local_comments BIGINT NOT NULL,
+    domain_count BIGINT NOT NULL,
+    active_month BIGINT NOT NULL,
+    active_half_year BIGINT NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE instance_peers (
+    domain VARCHAR PRIMARY KEY
+);
+
+CREATE TABLE instance_activity (
+    week DATE PRIMARY KEY,
+    statuses BIGINT NOT NULL,
+    logins BIGINT NOT NULL,
+    registrations BIGINT NOT NULL
+);
\ No newline at end of file
@@ -0,0 +1,3 @@
+ALTER TABLE accounts DROP COLUMN moved_at;
+ALTER TABLE accounts DROP COLUMN moved_to;
+ALTER TABLE accounts DROP COLUMN also_known_as;
\ No newline at end of file
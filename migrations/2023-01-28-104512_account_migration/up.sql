@@ -0,0 +1,3 @@
+ALTER TABLE accounts ADD COLUMN also_known_as VARCHAR[] NOT NULL DEFAULT '{}';
+ALTER TABLE accounts ADD COLUMN moved_to UUID NULL REFERENCES accounts(id) ON DELETE SET NULL;
+ALTER TABLE accounts ADD COLUMN moved_at TIMESTAMP NULL;
\ No newline at end of file
@@ -0,0 +1,15 @@
+CREATE TABLE imports (
+    id UUID PRIMARY KEY,
+    iid BIGSERIAL,
+    account UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    import_type VARCHAR NOT NULL,
+    overwrite BOOLEAN NOT NULL DEFAULT FALSE,
+    total_items INTEGER NOT NULL DEFAULT 0,
+    imported_items INTEGER NOT NULL DEFAULT 0,
+    failed_items INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMP NULL
+);
+
+CREATE INDEX imports_iid_idx ON imports (iid);
+CREATE INDEX imports_account_idx ON imports (account);
\ No newline at end of file
@@ -0,0 +1 @@
+DROP TABLE account_archives;
\ No newline at end of file
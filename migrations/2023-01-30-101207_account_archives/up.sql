@@ -0,0 +1,13 @@
+CREATE TABLE account_archives (
+    id UUID PRIMARY KEY,
+    iid BIGSERIAL,
+    account UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    file VARCHAR NULL,
+    size BIGINT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMP NULL,
+    expires_at TIMESTAMP NULL
+);
+
+CREATE INDEX account_archives_iid_idx ON account_archives (iid);
+CREATE INDEX account_archives_account_idx ON account_archives (account);
\ No newline at end of file
@@ -0,0 +1,2 @@
+ALTER TABLE oauth_token DROP COLUMN account_id;
+ALTER TABLE oauth_codes DROP COLUMN account_id;
\ No newline at end of file
@@ -0,0 +1,2 @@
+ALTER TABLE oauth_codes ADD COLUMN account_id UUID NULL REFERENCES accounts(id) ON DELETE CASCADE;
+ALTER TABLE oauth_token ADD COLUMN account_id UUID NULL REFERENCES accounts(id) ON DELETE CASCADE;
\ No newline at end of file
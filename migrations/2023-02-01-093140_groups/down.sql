@@ -0,0 +1,2 @@
+DROP TABLE group_posts;
+ALTER TABLE accounts DROP COLUMN group_review_posts;
\ No newline at end of file
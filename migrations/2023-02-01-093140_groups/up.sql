@@ -0,0 +1,16 @@
+ALTER TABLE accounts ADD COLUMN group_review_posts BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE group_posts (
+    id UUID PRIMARY KEY,
+    iid BIGSERIAL,
+    group_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    status UUID NOT NULL REFERENCES statuses(id) ON DELETE CASCADE,
+    announce UUID NULL REFERENCES statuses(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    reviewed_at TIMESTAMP NULL,
+    approved BOOLEAN NOT NULL DEFAULT FALSE,
+    UNIQUE (group_id, status)
+);
+
+CREATE INDEX group_posts_iid_idx ON group_posts (iid);
+CREATE INDEX group_posts_group_idx ON group_posts (group_id);
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE accounts DROP COLUMN featured_collection_url;
\ No newline at end of file
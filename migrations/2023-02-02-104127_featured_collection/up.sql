@@ -0,0 +1 @@
+ALTER TABLE accounts ADD COLUMN featured_collection_url VARCHAR NULL;
\ No newline at end of file
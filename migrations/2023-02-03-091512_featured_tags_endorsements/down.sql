@@ -0,0 +1,3 @@
+ALTER TABLE accounts DROP COLUMN featured_tags_collection_url;
+DROP TABLE endorsements;
+DROP TABLE featured_tags;
\ No newline at end of file
@@ -0,0 +1,25 @@
+CREATE TABLE featured_tags (
+    id UUID PRIMARY KEY,
+    iid BIGSERIAL,
+    account UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    tag UUID NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (account, tag)
+);
+
+CREATE INDEX featured_tags_iid_idx ON featured_tags (iid);
+CREATE INDEX featured_tags_account_idx ON featured_tags (account);
+
+CREATE TABLE endorsements (
+    id UUID PRIMARY KEY,
+    iid BIGSERIAL,
+    account UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    endorsed UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (account, endorsed)
+);
+
+CREATE INDEX endorsements_iid_idx ON endorsements (iid);
+CREATE INDEX endorsements_account_idx ON endorsements (account);
+
+ALTER TABLE accounts ADD COLUMN featured_tags_collection_url VARCHAR NULL;
\ No newline at end of file
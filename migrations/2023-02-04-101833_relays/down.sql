@@ -0,0 +1 @@
+DROP TABLE relays;
\ No newline at end of file
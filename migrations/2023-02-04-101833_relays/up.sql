@@ -0,0 +1,10 @@
+CREATE TABLE relays (
+    id UUID PRIMARY KEY,
+    iid BIGSERIAL,
+    inbox_url VARCHAR NOT NULL UNIQUE,
+    actor VARCHAR NULL,
+    state VARCHAR NOT NULL DEFAULT 'pending',
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX relays_actor_idx ON relays (actor);
\ No newline at end of file
@@ -0,0 +1 @@
+DROP TABLE delivery_domains;
\ No newline at end of file
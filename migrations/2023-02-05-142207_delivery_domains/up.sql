@@ -0,0 +1,10 @@
+CREATE TABLE delivery_domains (
+    domain VARCHAR PRIMARY KEY,
+    consecutive_failures INTEGER NOT NULL DEFAULT 0,
+    first_failure_at TIMESTAMP NULL,
+    last_failure_at TIMESTAMP NULL,
+    last_success_at TIMESTAMP NULL,
+    unavailable_since TIMESTAMP NULL
+);
+
+CREATE INDEX delivery_domains_unavailable_since_idx ON delivery_domains (unavailable_since);
\ No newline at end of file
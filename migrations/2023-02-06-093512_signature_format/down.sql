@@ -0,0 +1 @@
+ALTER TABLE delivery_domains DROP COLUMN signature_format;
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE delivery_domains ADD COLUMN signature_format VARCHAR NULL;
\ No newline at end of file
@@ -0,0 +1 @@
+DROP TABLE thread_backfills;
\ No newline at end of file
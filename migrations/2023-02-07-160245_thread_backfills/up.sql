@@ -0,0 +1,4 @@
+CREATE TABLE thread_backfills (
+    status_id UUID PRIMARY KEY REFERENCES statuses(id) ON DELETE CASCADE,
+    crawled_at TIMESTAMP NOT NULL
+);
\ No newline at end of file
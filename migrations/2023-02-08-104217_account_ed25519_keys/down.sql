@@ -0,0 +1 @@
+ALTER TABLE accounts DROP COLUMN ed25519_private_key;
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE accounts ADD COLUMN ed25519_private_key VARCHAR NULL;
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE account_archives DROP COLUMN failed_at;
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE account_archives ADD COLUMN failed_at TIMESTAMP NULL;
\ No newline at end of file
@@ -0,0 +1,82 @@
+#[macro_use]
+extern crate log;
+
+use diesel::prelude::*;
+use rocket_sync_db_pools::Poolable;
+
+/// Postgres advisory lock held by the active scheduler, so replicas don't submit the same job twice
+const SCHEDULER_LOCK_ID: i64 = 0x7461_6661_726e_0001;
+const LOCK_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+fn try_lock(c: &diesel::PgConnection) -> QueryResult<bool> {
+    diesel::select(diesel::dsl::sql::<diesel::sql_types::Bool>(
+        &format!("pg_try_advisory_lock({})", SCHEDULER_LOCK_ID)
+    )).get_result(c)
+}
+
+/// Holds the scheduler lock on its own connection, returning only once the connection, and so the lock, is lost
+fn hold_lock(c: diesel::r2d2::PooledConnection<diesel::r2d2::ConnectionManager<diesel::PgConnection>>) {
+    loop {
+        std::thread::sleep(LOCK_CHECK_INTERVAL);
+        if let Err(e) = diesel::sql_query("SELECT 1").execute(&c) {
+            error!("Lost database connection holding the scheduler lock: {}", e);
+            return;
+        }
+    }
+}
+
+macro_rules! schedule {
+    ($beat:expr, $schedules:expr, $name:ident, $task:expr) => {
+        if !$schedules.$name.is_empty() {
+            let schedule = celery::beat::CronSchedule::from_string(&$schedules.$name)
+                .expect(concat!("Invalid schedule for ", stringify!($name)));
+            $beat.schedule_named_task(stringify!($name).to_string(), $task, schedule);
+        }
+    };
+}
+
+#[tokio::main]
+async fn main() {
+    pretty_env_logger::init();
+
+    info!("Tafarn periodic task scheduler starting...");
+
+    let app = tafarn::setup().await;
+    let db_pool = diesel::PgConnection::pool("db", &app.rocket).unwrap();
+
+    let lock_conn = loop {
+        let c = db_pool.get().expect("Unable to get DB pool connection");
+        if try_lock(&c).expect("Unable to take scheduler lock") {
+            break c;
+        }
+        info!("Another scheduler holds the lock, waiting");
+        tokio::time::sleep(LOCK_CHECK_INTERVAL).await;
+    };
+    info!("Took scheduler lock");
+
+    let mut beat = celery::beat!(
+        broker = celery::broker::AMQPBroker { app.amqp_url },
+        tasks = [],
+        task_routes = [],
+        broker_connection_retry = true,
+        broker_connection_timeout = 10,
+        heartbeat = Some(60),
+    ).await.expect("Unable to setup Celery beat");
+
+    let schedules = app.schedule;
+    schedule!(beat, schedules, recompute_trends, tafarn::tasks::trends::recompute_trends::new());
+    schedule!(beat, schedules, update_instance_stats, tafarn::tasks::stats::update_instance_stats::new());
+    schedule!(beat, schedules, delete_expired_archives, tafarn::tasks::archives::delete_expired_archives::new());
+    schedule!(beat, schedules, probe_unavailable_domains, tafarn::tasks::delivery::probe_unavailable_domains::new());
+    schedule!(beat, schedules, refresh_stale_accounts, tafarn::tasks::maintenance::refresh_stale_accounts::new());
+    schedule!(beat, schedules, delete_expired_sessions, tafarn::tasks::maintenance::delete_expired_sessions::new());
+    schedule!(beat, schedules, delete_orphaned_media, tafarn::tasks::maintenance::delete_orphaned_media::new());
+
+    tokio::select! {
+        r = beat.start() => r.unwrap(),
+        _ = tokio::task::spawn_blocking(move || hold_lock(lock_conn)) => {
+            // Another replica may take over now, so stop rather than risk running jobs twice
+            std::process::exit(1);
+        }
+    }
+}
\ No newline at end of file
@@ -40,17 +40,25 @@ async fn main() -> Result<(), rocket::Error> {
         vapid_key: app.vapid_key,
         web_push_client: std::sync::Arc::new(web_push_old::WebPushClient::new()),
         as_key: std::sync::Arc::new(app.as_key),
-        media_path: std::sync::Arc::new(app.media_path.clone())
+        media_path: std::sync::Arc::new(app.media_path.clone()),
+        archive_path: std::sync::Arc::new(app.archive_path),
+        json_ld: app.json_ld,
     });
 
+    if let Ok(db_config) = rocket_sync_db_pools::Config::from("db", &app.rocket) {
+        tafarn::metrics::DB_POOL_MAX_CONNECTIONS.set(&["requests"], db_config.pool_size as i64);
+    }
+
     let _ = app.rocket
         .attach(CORS)
+        .attach(tafarn::metrics::MetricsFairing)
         .attach(tafarn::DbConn::fairing())
         .attach(tafarn::csrf::CSRFFairing)
         .attach(rocket_dyn_templates::Template::custom(|engines| {
             engines.tera.register_function("fl", tafarn::i18n::TeraLocalizer::new());
         }))
         .manage(app.celery_app)
+        .manage(tafarn::views::activity_streams::SignatureReplayCache::default())
         .mount("/static", rocket::fs::FileServer::from("./static"))
         .mount("/media", rocket::fs::FileServer::from(app.media_path))
         .mount("/", rocket::routes![
@@ -61,6 +69,7 @@ async fn main() -> Result<(), rocket::Error> {
             tafarn::views::meta::host_meta,
             tafarn::views::meta::web_finger,
             tafarn::views::meta::well_known_node_info,
+            tafarn::views::meta::metrics,
 
             tafarn::views::nodeinfo::node_info_2_0,
             tafarn::views::nodeinfo::node_info_2_1,
@@ -75,6 +84,8 @@ async fn main() -> Result<(), rocket::Error> {
 
             tafarn::views::instance::instance,
             tafarn::views::instance::instance_v2,
+            tafarn::views::instance::instance_rules,
+            tafarn::views::instance::instance_extended_description,
             tafarn::views::instance::instance_peers,
             tafarn::views::instance::instance_activity,
             tafarn::views::instance::custom_emoji,
@@ -82,6 +93,36 @@ async fn main() -> Result<(), rocket::Error> {
             tafarn::views::accounts::verify_credentials,
             tafarn::views::accounts::user_preferences,
             tafarn::views::accounts::update_credentials,
+            tafarn::views::accounts::set_aliases_form,
+            tafarn::views::accounts::set_aliases_json,
+            tafarn::views::accounts::move_account_form,
+            tafarn::views::accounts::move_account_json,
+            tafarn::views::accounts::delete_account_form,
+            tafarn::views::accounts::delete_account_json,
+            tafarn::views::accounts::owned_accounts_list,
+            tafarn::views::accounts::create_owned_account_form,
+            tafarn::views::accounts::create_owned_account_json,
+            tafarn::views::imports::create_import,
+            tafarn::views::imports::imports,
+            tafarn::views::imports::import,
+            tafarn::views::exports::export,
+            tafarn::views::archives::create_archive,
+            tafarn::views::archives::archives,
+            tafarn::views::archives::download_archive,
+            tafarn::views::groups::group_posts,
+            tafarn::views::groups::approve_group_post,
+            tafarn::views::groups::reject_group_post,
+            tafarn::views::groups::group_settings_form,
+            tafarn::views::groups::group_settings_json,
+            tafarn::views::featured_tags::featured_tags,
+            tafarn::views::featured_tags::account_featured_tags,
+            tafarn::views::featured_tags::create_featured_tag_form,
+            tafarn::views::featured_tags::create_featured_tag_json,
+            tafarn::views::featured_tags::delete_featured_tag,
+            tafarn::views::featured_tags::featured_tag_suggestions,
+            tafarn::views::endorsements::endorsements,
+            tafarn::views::endorsements::endorse_account,
+            tafarn::views::endorsements::unendorse_account,
             tafarn::views::accounts::account,
             tafarn::views::accounts::account_statuses,
             tafarn::views::accounts::account_following,
@@ -132,9 +173,35 @@ async fn main() -> Result<(), rocket::Error> {
             tafarn::views::notifications::notification,
             tafarn::views::notifications::clear_notifications,
             tafarn::views::notifications::dismiss_notification,
+            tafarn::views::notifications::notification_policy,
+            tafarn::views::notifications::update_notification_policy_form,
+            tafarn::views::notifications::update_notification_policy_json,
+            tafarn::views::notifications::notification_requests,
+            tafarn::views::notifications::notification_request,
+            tafarn::views::notifications::accept_notification_request,
+            tafarn::views::notifications::dismiss_notification_request,
+            tafarn::views::reports::create_report_form,
+            tafarn::views::reports::create_report_json,
+            tafarn::views::admin::list_accounts,
+            tafarn::views::admin::get_account,
+            tafarn::views::admin::account_action_form,
+            tafarn::views::admin::account_action_json,
+            tafarn::views::admin::unsuspend_account,
+            tafarn::views::admin::unsilence_account,
+            tafarn::views::admin::unsensitive_account,
+            tafarn::views::admin::list_reports,
+            tafarn::views::admin::report,
+            tafarn::views::admin::assign_report,
+            tafarn::views::admin::unassign_report,
+            tafarn::views::admin::resolve_report,
+            tafarn::views::admin::reopen_report,
 
             tafarn::views::search::search,
 
+            tafarn::views::trends::trends,
+            tafarn::views::trends::trends_tags,
+            tafarn::views::trends::trends_statuses,
+
             tafarn::views::mutes::mutes,
             tafarn::views::mutes::get_mute_account,
             tafarn::views::mutes::mute_account,
@@ -182,9 +249,16 @@ async fn main() -> Result<(), rocket::Error> {
             tafarn::views::activity_streams::post_inbox,
             tafarn::views::activity_streams::get_outbox,
             tafarn::views::activity_streams::post_outbox,
+            tafarn::views::activity_streams::get_followers,
+            tafarn::views::activity_streams::get_followers_page,
+            tafarn::views::activity_streams::get_featured,
+            tafarn::views::activity_streams::get_featured_tags,
             tafarn::views::activity_streams::get_shared_inbox,
             tafarn::views::activity_streams::post_shared_inbox,
             tafarn::views::activity_streams::system_actor,
+            tafarn::views::activity_streams::get_system_inbox,
+            tafarn::views::activity_streams::post_system_inbox,
+            tafarn::views::activity_streams::relay_follow,
             tafarn::views::activity_streams::status,
             tafarn::views::activity_streams::status_activity,
             tafarn::views::activity_streams::like,
@@ -1,4 +1,5 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use rocket_sync_db_pools::Poolable;
 
 #[derive(Parser)]
 #[command(author, version)]
@@ -20,6 +21,74 @@ enum Commands {
         #[arg(short, long, help = "Disable fetching the profile's graph (followers and following)")]
         no_graph: bool,
     },
+    #[command(about = "Recomputes trending tags and statuses")]
+    RecomputeTrends,
+    #[command(about = "Approves or rejects a hashtag for inclusion in trends")]
+    ReviewTag {
+        name: String,
+        #[arg(value_enum)]
+        decision: ReviewDecision,
+    },
+    #[command(about = "Approves or rejects a status for inclusion in trends")]
+    ReviewStatus {
+        #[arg(help = "Status ID as returned by the API")]
+        id: i64,
+        #[arg(value_enum)]
+        decision: ReviewDecision,
+    },
+    #[command(about = "Manages subscriptions to ActivityPub relays")]
+    Relay {
+        #[command(subcommand)]
+        command: RelayCommands,
+    },
+    #[command(about = "Shows and resets the delivery state of remote domains")]
+    Delivery {
+        #[command(subcommand)]
+        command: DeliveryCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum RelayCommands {
+    #[command(about = "Subscribes to a relay")]
+    Add {
+        #[arg(help = "Inbox URL of the relay")]
+        inbox: String,
+    },
+    #[command(about = "Unsubscribes from a relay")]
+    Remove {
+        #[arg(help = "Inbox URL of the relay")]
+        inbox: String,
+    },
+    #[command(about = "Lists relay subscriptions")]
+    List,
+}
+
+#[derive(Subcommand)]
+enum DeliveryCommands {
+    #[command(about = "Lists domains deliveries are failing to")]
+    Status,
+    #[command(about = "Clears the failures of a domain, resuming deliveries to it")]
+    Reset {
+        domain: String,
+    },
+}
+
+#[derive(Clone, ValueEnum)]
+enum ReviewDecision {
+    Approve,
+    Reject,
+    Reset,
+}
+
+impl ReviewDecision {
+    fn trendable(&self) -> Option<bool> {
+        match self {
+            ReviewDecision::Approve => Some(true),
+            ReviewDecision::Reject => Some(false),
+            ReviewDecision::Reset => None,
+        }
+    }
 }
 
 #[tokio::main]
@@ -40,5 +109,55 @@ async fn main() {
             app.celery_app.send_task(tafarn::tasks::accounts::update_accounts::new(no_graph)).await.unwrap();
             println!("Update of all profiles requested");
         }
+        Commands::RecomputeTrends => {
+            app.celery_app.send_task(tafarn::tasks::trends::recompute_trends::new()).await.unwrap();
+            println!("Trend recomputation requested");
+        }
+        Commands::ReviewTag { name, decision } => {
+            app.celery_app.send_task(tafarn::tasks::trends::review_tag::new(name.clone(), decision.trendable())).await.unwrap();
+            println!("Review of #{} requested", name);
+        }
+        Commands::ReviewStatus { id, decision } => {
+            app.celery_app.send_task(tafarn::tasks::trends::review_status::new(id, decision.trendable())).await.unwrap();
+            println!("Review of status {} requested", id);
+        }
+        Commands::Relay { command: RelayCommands::Add { inbox } } => {
+            app.celery_app.send_task(tafarn::tasks::relays::subscribe_relay::new(inbox.clone())).await.unwrap();
+            println!("Subscription to {} requested", inbox);
+        }
+        Commands::Relay { command: RelayCommands::Remove { inbox } } => {
+            app.celery_app.send_task(tafarn::tasks::relays::unsubscribe_relay::new(inbox.clone())).await.unwrap();
+            println!("Unsubscription from {} requested", inbox);
+        }
+        Commands::Relay { command: RelayCommands::List } => {
+            let db_pool = diesel::PgConnection::pool("db", &app.rocket).unwrap();
+            let c = db_pool.get().unwrap();
+            let relays = tafarn::tasks::relays::list_relays(&c).unwrap();
+            for relay in relays {
+                println!("{}\t{}", relay.inbox_url, relay.state);
+            }
+        }
+        Commands::Delivery { command: DeliveryCommands::Status } => {
+            let db_pool = diesel::PgConnection::pool("db", &app.rocket).unwrap();
+            let c = db_pool.get().unwrap();
+            let domains = tafarn::tasks::delivery::list_delivery_domains(&c).unwrap();
+            let fmt_date = |d: Option<chrono::NaiveDateTime>| d.map(|d| d.to_string()).unwrap_or_else(|| "-".to_string());
+            println!("DOMAIN\tFAILURES\tLAST SUCCESS\tUNAVAILABLE SINCE");
+            for domain in domains {
+                println!(
+                    "{}\t{}\t{}\t{}", domain.domain, domain.consecutive_failures,
+                    fmt_date(domain.last_success_at), fmt_date(domain.unavailable_since)
+                );
+            }
+        }
+        Commands::Delivery { command: DeliveryCommands::Reset { domain } } => {
+            let db_pool = diesel::PgConnection::pool("db", &app.rocket).unwrap();
+            let c = db_pool.get().unwrap();
+            if tafarn::tasks::delivery::reset_delivery_domain(&c, &domain).unwrap() {
+                println!("Delivery state of {} reset", domain);
+            } else {
+                println!("No delivery state recorded for {}", domain);
+            }
+        }
     }
 }
\ No newline at end of file
@@ -5,7 +5,7 @@ use rocket_sync_db_pools::Poolable;
 
 #[tokio::main]
 async fn main() {
-    pretty_env_logger::init();
+    tafarn::metrics::init_task_logger();
 
     info!("Tafarn task runner starting...");
 
@@ -20,8 +20,14 @@ async fn main() {
         vapid_key: app.vapid_key,
         web_push_client: std::sync::Arc::new(web_push_old::WebPushClient::new()),
         as_key: std::sync::Arc::new(app.as_key),
-        media_path: std::sync::Arc::new(app.media_path)
+        media_path: std::sync::Arc::new(app.media_path),
+        archive_path: std::sync::Arc::new(app.archive_path),
+        json_ld: app.json_ld,
     });
 
+    if app.metrics.enabled {
+        tokio::spawn(tafarn::metrics::serve(app.metrics.tasks_address, app.metrics.bearer_token));
+    }
+
     celery_app.consume().await.unwrap();
 }
\ No newline at end of file
@@ -0,0 +1,454 @@
+//! Just enough JSON-LD processing to bring objects using prefixed terms, alternative contexts or keyword aliases into
+//! the compacted form our ActivityStreams types deserialize from. Only the contexts bundled here are understood,
+//! remote contexts are never fetched, and terms they'd define are left as they are.
+
+use std::collections::{HashMap, HashSet};
+
+const AS_NS: &str = "https://www.w3.org/ns/activitystreams#";
+const SEC_NS: &str = "https://w3id.org/security#";
+const PUBLIC: &str = "https://www.w3.org/ns/activitystreams#Public";
+
+/// Vocabularies whose terms compact to the bare names used by our types
+const KNOWN_NAMESPACES: [&str; 9] = [
+    "https://www.w3.org/ns/activitystreams#",
+    "http://www.w3.org/ns/activitystreams#",
+    "https://w3id.org/security#",
+    "http://www.w3.org/ns/ldp#",
+    "http://joinmastodon.org/ns#",
+    "http://schema.org#",
+    "http://schema.org/",
+    "https://schema.org#",
+    "https://schema.org/",
+];
+
+/// ActivityStreams properties whose values are IRIs rather than strings
+const AS_ID_TERMS: [&str; 37] = [
+    "actor", "anyOf", "attachment", "attributedTo", "audience", "bcc", "bto", "cc", "context", "current",
+    "describes", "first", "following", "followers", "generator", "icon", "image", "inReplyTo", "instrument",
+    "items", "last", "liked", "likes", "location", "next", "object", "oneOf", "orderedItems", "origin", "outbox",
+    "partOf", "prev", "preview", "replies", "result", "tag", "target",
+];
+
+const AS_ID_TERMS_EXTRA: [&str; 7] = ["to", "url", "href", "sharedInbox", "endpoints", "movedTo", "alsoKnownAs"];
+
+#[derive(Clone)]
+struct Term {
+    iri: String,
+    id_typed: bool,
+}
+
+#[derive(Clone, Default)]
+struct Context {
+    terms: HashMap<String, Term>,
+    vocab: Option<String>,
+}
+
+fn is_activitystreams_url(url: &str) -> bool {
+    let url = url.trim_end_matches('#').trim_end_matches(".jsonld");
+    url == "https://www.w3.org/ns/activitystreams" || url == "http://www.w3.org/ns/activitystreams"
+}
+
+impl Context {
+    fn define(&mut self, term: &str, iri: &str, id_typed: bool) {
+        self.terms.insert(term.to_string(), Term {
+            iri: iri.to_string(),
+            id_typed,
+        });
+    }
+
+    fn load_bundled(&mut self, url: &str) -> bool {
+        if is_activitystreams_url(url) {
+            self.define("as", AS_NS, false);
+            self.define("ldp", "http://www.w3.org/ns/ldp#", false);
+            self.define("vcard", "http://www.w3.org/2006/vcard/ns#", false);
+            self.define("xsd", "http://www.w3.org/2001/XMLSchema#", false);
+            self.define("id", "@id", false);
+            self.define("type", "@type", false);
+            for term in AS_ID_TERMS.iter().chain(AS_ID_TERMS_EXTRA.iter()) {
+                self.define(term, &format!("{}{}", AS_NS, term), true);
+            }
+            self.define("inbox", "http://www.w3.org/ns/ldp#inbox", true);
+            return true;
+        }
+
+        match url {
+            "https://w3id.org/security/v1" => {
+                self.define("sec", SEC_NS, false);
+                self.define("id", "@id", false);
+                self.define("type", "@type", false);
+                self.define("publicKey", &format!("{}publicKey", SEC_NS), true);
+                self.define("owner", &format!("{}owner", SEC_NS), true);
+                self.define("publicKeyPem", &format!("{}publicKeyPem", SEC_NS), false);
+                true
+            }
+            "https://w3id.org/security/data-integrity/v1" |
+            "https://w3id.org/security/data-integrity/v2" |
+            "https://w3id.org/security/multikey/v1" => {
+                self.define("sec", SEC_NS, false);
+                self.define("id", "@id", false);
+                self.define("type", "@type", false);
+                self.define("assertionMethod", &format!("{}assertionMethod", SEC_NS), true);
+                self.define("controller", &format!("{}controller", SEC_NS), true);
+                self.define("publicKeyMultibase", &format!("{}publicKeyMultibase", SEC_NS), false);
+                true
+            }
+            _ => false
+        }
+    }
+
+    fn process(&mut self, context: &serde_json::Value) {
+        match context {
+            serde_json::Value::String(url) if !self.load_bundled(url) => {
+                debug!("Ignoring unknown JSON-LD context {}", url);
+            }
+            serde_json::Value::Array(contexts) => {
+                for c in contexts {
+                    self.process(c);
+                }
+            }
+            serde_json::Value::Object(definitions) => {
+                for (term, definition) in definitions {
+                    if term == "@vocab" {
+                        self.vocab = definition.as_str().and_then(|v| self.expand_iri(v, false));
+                        continue;
+                    }
+                    if term.starts_with('@') {
+                        continue;
+                    }
+                    match definition {
+                        serde_json::Value::String(iri) => self.define(term, iri, false),
+                        serde_json::Value::Object(d) => {
+                            let id_typed = matches!(d.get("@type").and_then(|t| t.as_str()), Some("@id") | Some("@vocab"));
+                            let iri = d.get("@id").and_then(|i| i.as_str()).unwrap_or(term);
+                            self.define(term, iri, id_typed);
+                        }
+                        serde_json::Value::Null => {
+                            self.terms.remove(term);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Expands a term or compact IRI, treating bare words as terms when `vocab` is set
+    fn expand_iri(&self, value: &str, vocab: bool) -> Option<String> {
+        self.expand_iri_inner(value, vocab, 0)
+    }
+
+    fn expand_iri_inner(&self, value: &str, vocab: bool, depth: usize) -> Option<String> {
+        // Definitions can refer to each other, but not forever
+        if depth > 8 || value.starts_with('@') {
+            return Some(value.to_string());
+        }
+
+        if vocab {
+            if let Some(term) = self.terms.get(value) {
+                if term.iri == value {
+                    return Some(value.to_string());
+                }
+                return self.expand_iri_inner(&term.iri, false, depth + 1);
+            }
+        }
+
+        if let Some((prefix, suffix)) = value.split_once(':') {
+            if suffix.starts_with("//") {
+                return Some(value.to_string());
+            }
+            return Some(match self.terms.get(prefix) {
+                Some(term) => format!("{}{}", self.expand_iri_inner(&term.iri, false, depth + 1)?, suffix),
+                None => value.to_string()
+            });
+        }
+
+        match &self.vocab {
+            Some(v) if vocab => Some(format!("{}{}", v, value)),
+            _ => None
+        }
+    }
+
+    fn is_id_typed(&self, key: &str, iri: &str) -> bool {
+        if let Some(term) = self.terms.get(key) {
+            return term.id_typed;
+        }
+        match iri.strip_prefix(AS_NS) {
+            Some(name) => AS_ID_TERMS.contains(&name) || AS_ID_TERMS_EXTRA.contains(&name),
+            None => false
+        }
+    }
+}
+
+fn compact_iri(iri: &str) -> Option<String> {
+    match iri {
+        "@id" => return Some("id".to_string()),
+        "@type" => return Some("type".to_string()),
+        _ => {}
+    }
+    KNOWN_NAMESPACES.iter()
+        .filter_map(|ns| iri.strip_prefix(ns))
+        .find(|name| !name.is_empty())
+        .map(|name| name.to_string())
+}
+
+/// Compacts a term against our vocabularies, leaving it as written if it isn't from one of them
+fn compact_term(term: &str, context: &Context) -> (String, Option<String>) {
+    match context.expand_iri(term, true) {
+        Some(iri) => (compact_iri(&iri).unwrap_or_else(|| term.to_string()), Some(iri)),
+        None => (term.to_string(), None)
+    }
+}
+
+/// Returns if a document's `@context` includes the ActivityStreams vocabulary in any of its usual spellings
+pub fn has_activitystreams_context(value: &serde_json::Value) -> bool {
+    fn check(context: &serde_json::Value) -> bool {
+        match context {
+            serde_json::Value::String(url) => is_activitystreams_url(url),
+            serde_json::Value::Array(contexts) => contexts.iter().any(check),
+            serde_json::Value::Object(definitions) => {
+                definitions.get("@vocab").and_then(|v| v.as_str()) == Some(AS_NS) ||
+                    definitions.get("as").and_then(|v| v.as_str()) == Some(AS_NS)
+            }
+            _ => false
+        }
+    }
+
+    value.get("@context").map(check).unwrap_or(false)
+}
+
+/// Rewrites a document into the compact form our types expect.
+///
+/// In lenient mode a missing `@context` is taken to be ActivityStreams and a bare `Public` audience is understood,
+/// both of which some implementations get wrong.
+pub fn normalise(value: serde_json::Value, lenient: bool) -> serde_json::Value {
+    let mut context = Context::default();
+    if lenient && value.get("@context").is_none() {
+        context.load_bundled("https://www.w3.org/ns/activitystreams");
+    }
+    normalise_node(value, &context, lenient)
+}
+
+fn normalise_node(value: serde_json::Value, context: &Context, lenient: bool) -> serde_json::Value {
+    match value {
+        serde_json::Value::Array(values) => serde_json::Value::Array(
+            values.into_iter().map(|v| normalise_node(v, context, lenient)).collect()
+        ),
+        serde_json::Value::Object(object) => normalise_object(object, context, lenient),
+        v => v
+    }
+}
+
+fn normalise_object(
+    mut object: serde_json::Map<String, serde_json::Value>, context: &Context, lenient: bool,
+) -> serde_json::Value {
+    let local_context;
+    let context = match object.remove("@context") {
+        Some(c) => {
+            let mut ctx = context.clone();
+            ctx.process(&c);
+            local_context = ctx;
+            &local_context
+        }
+        None => context
+    };
+
+    if let Some(value) = object.remove("@value") {
+        return value;
+    }
+    if let Some(list) = object.remove("@list").or_else(|| object.remove("@set")) {
+        return normalise_node(list, context, lenient);
+    }
+
+    let mut out = serde_json::Map::new();
+    let mut bare = HashSet::new();
+    for (key, value) in object {
+        let (name, iri) = compact_term(&key, context);
+        let id_typed = match iri {
+            Some(iri) => context.is_id_typed(&key, &iri),
+            None => false
+        };
+
+        let value = if name == "type" {
+            normalise_type(value, context)
+        } else if id_typed {
+            normalise_id_value(value, context, lenient)
+        } else {
+            normalise_node(value, context, lenient)
+        };
+
+        // A bare term wins over a prefixed or expanded spelling of the same property, whichever comes first
+        if key == name {
+            if bare.insert(name.clone()) {
+                out.insert(name, value);
+            }
+        } else if !out.contains_key(&name) {
+            out.insert(name, value);
+        }
+    }
+
+    // An object with nothing but an ID is just a reference to it
+    if out.len() == 1 {
+        if let Some(serde_json::Value::String(id)) = out.get("id") {
+            return serde_json::Value::String(id.clone());
+        }
+    }
+
+    serde_json::Value::Object(out)
+}
+
+fn normalise_type(value: serde_json::Value, context: &Context) -> serde_json::Value {
+    let compact = |t: &str| compact_term(t, context).0;
+    match value {
+        serde_json::Value::String(t) => serde_json::Value::String(compact(&t)),
+        // Our types are tagged by a single type, so prefer one from a vocabulary we know
+        serde_json::Value::Array(types) => {
+            let types: Vec<String> = types.iter().filter_map(|t| t.as_str()).map(compact).collect();
+            match types.iter().find(|t| !t.contains(':')).or_else(|| types.first()) {
+                Some(t) => serde_json::Value::String(t.clone()),
+                None => serde_json::Value::Array(vec![])
+            }
+        }
+        v => v
+    }
+}
+
+fn normalise_id_value(value: serde_json::Value, context: &Context, lenient: bool) -> serde_json::Value {
+    match value {
+        serde_json::Value::String(s) => {
+            if lenient && s == "Public" {
+                return serde_json::Value::String(PUBLIC.to_string());
+            }
+            serde_json::Value::String(if s.contains(':') {
+                context.expand_iri(&s, false).unwrap_or(s)
+            } else {
+                s
+            })
+        }
+        serde_json::Value::Array(values) => serde_json::Value::Array(
+            values.into_iter().map(|v| normalise_id_value(v, context, lenient)).collect()
+        ),
+        v => normalise_node(v, context, lenient)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn bare_term_wins_over_prefixed() {
+        // Keys are visited in sorted order, so "as:content" is seen before "content"
+        let out = normalise(json!({
+            "@context": "https://www.w3.org/ns/activitystreams",
+            "as:content": "prefixed",
+            "content": "bare",
+        }), false);
+        assert_eq!(out["content"], "bare");
+    }
+
+    #[test]
+    fn bare_term_wins_over_expanded() {
+        let out = normalise(json!({
+            "@context": "https://www.w3.org/ns/activitystreams",
+            "https://www.w3.org/ns/activitystreams#name": "expanded",
+            "name": "bare",
+        }), false);
+        assert_eq!(out["name"], "bare");
+    }
+
+    #[test]
+    fn prefixed_term_used_without_bare() {
+        let out = normalise(json!({
+            "@context": "https://www.w3.org/ns/activitystreams",
+            "as:content": "prefixed",
+        }), false);
+        assert_eq!(out["content"], "prefixed");
+    }
+
+    #[test]
+    fn activitystreams_context() {
+        for url in [
+            "https://www.w3.org/ns/activitystreams",
+            "http://www.w3.org/ns/activitystreams",
+            "https://www.w3.org/ns/activitystreams#",
+            "https://www.w3.org/ns/activitystreams.jsonld",
+        ] {
+            let out = normalise(json!({
+                "@context": url,
+                "as:type": "as:Note",
+                "as:to": "as:Public",
+                "as:inReplyTo": {"@id": "https://example.com/1"},
+                "ldp:inbox": "https://example.com/inbox",
+            }), false);
+            assert_eq!(out["type"], "Note", "{}", url);
+            assert_eq!(out["to"], PUBLIC, "{}", url);
+            assert_eq!(out["inReplyTo"], "https://example.com/1", "{}", url);
+            assert_eq!(out["inbox"], "https://example.com/inbox", "{}", url);
+        }
+    }
+
+    #[test]
+    fn security_v1_context() {
+        let out = normalise(json!({
+            "@context": ["https://www.w3.org/ns/activitystreams", "https://w3id.org/security/v1"],
+            "id": "https://example.com/users/a",
+            "sec:publicKey": {
+                "@id": "https://example.com/users/a#main-key",
+                "sec:owner": "https://example.com/users/a",
+                "sec:publicKeyPem": "-----BEGIN PUBLIC KEY-----",
+            },
+        }), false);
+        assert_eq!(out["publicKey"]["id"], "https://example.com/users/a#main-key");
+        assert_eq!(out["publicKey"]["owner"], "https://example.com/users/a");
+        assert_eq!(out["publicKey"]["publicKeyPem"], "-----BEGIN PUBLIC KEY-----");
+    }
+
+    #[test]
+    fn data_integrity_and_multikey_contexts() {
+        for url in [
+            "https://w3id.org/security/data-integrity/v1",
+            "https://w3id.org/security/data-integrity/v2",
+            "https://w3id.org/security/multikey/v1",
+        ] {
+            let out = normalise(json!({
+                "@context": ["https://www.w3.org/ns/activitystreams", url],
+                "id": "https://example.com/users/a",
+                "sec:assertionMethod": [{
+                    "@id": "https://example.com/users/a#ed25519-key",
+                    "@type": "sec:Multikey",
+                    "sec:controller": "https://example.com/users/a",
+                    "sec:publicKeyMultibase": "z6Mk",
+                }],
+            }), false);
+            let key = &out["assertionMethod"][0];
+            assert_eq!(key["id"], "https://example.com/users/a#ed25519-key", "{}", url);
+            assert_eq!(key["type"], "Multikey", "{}", url);
+            assert_eq!(key["controller"], "https://example.com/users/a", "{}", url);
+            assert_eq!(key["publicKeyMultibase"], "z6Mk", "{}", url);
+        }
+    }
+
+    #[test]
+    fn unknown_context_left_alone() {
+        let out = normalise(json!({
+            "@context": ["https://www.w3.org/ns/activitystreams", "https://example.com/context.jsonld"],
+            "ex:thing": "value",
+        }), false);
+        assert_eq!(out["ex:thing"], "value");
+    }
+
+    #[test]
+    fn lenient_defaults() {
+        let out = normalise(json!({"type": "Note", "to": "Public"}), true);
+        assert_eq!(out["to"], PUBLIC);
+
+        let out = normalise(json!({
+            "@context": "https://www.w3.org/ns/activitystreams",
+            "type": "Note",
+            "to": "Public",
+        }), false);
+        assert_eq!(out["to"], "Public");
+    }
+}
@@ -24,6 +24,8 @@ pub mod i18n;
 pub mod views;
 pub mod csrf;
 pub mod tasks;
+pub mod json_ld;
+pub mod metrics;
 
 #[database("db")]
 pub struct DbConn(diesel::PgConnection);
@@ -34,7 +36,13 @@ pub async fn db_run<
     T: 'static + Send,
     F: 'static + FnOnce(&mut diesel::PgConnection) -> diesel::result::QueryResult<T> + Send
 >(db: &DbConn, localizer: &i18n::Localizer, func: F) -> Result<T, views::Error> {
-    Ok(match db.run(func).await {
+    metrics::DB_QUERIES_IN_FLIGHT.add(&[], 1);
+    let start = std::time::Instant::now();
+    let result = db.run(func).await;
+    metrics::DB_QUERY_DURATION.observe(&[], start.elapsed().as_secs_f64());
+    metrics::DB_QUERIES_IN_FLIGHT.add(&[], -1);
+
+    Ok(match result {
         Ok(r) => r,
         Err(e) => {
             warn!("DB error: {}", e);
@@ -158,6 +166,9 @@ lazy_static! {
     };
 
     pub static ref WEBFINGER_RE: regex::Regex = regex::Regex::new("@?(?P<acct>(?P<user>.+)@(?P<domain>.+))").unwrap();
+    pub static ref USERNAME_RE: regex::Regex = regex::Regex::new("^[a-zA-Z0-9_]{1,30}$").unwrap();
+    pub static ref HASHTAG_RE: regex::Regex = regex::Regex::new(r"(?:^|[^\w/&])#(?P<tag>\w+)").unwrap();
+    pub static ref URL_RE: regex::Regex = regex::Regex::new(r"https?://\S+").unwrap();
 
     pub static ref LANGUAGE_LOADER: i18n_embed::fluent::FluentLanguageLoader = {
         use i18n_embed::LanguageLoader;
@@ -185,6 +196,130 @@ pub struct Config {
     vapid_key: std::path::PathBuf,
     as_key: std::path::PathBuf,
     media_path: std::path::PathBuf,
+    #[serde(default = "default_archive_path")]
+    archive_path: std::path::PathBuf,
+    #[serde(default)]
+    trends_require_review: bool,
+    #[serde(default)]
+    instance: InstanceConfig,
+    #[serde(default)]
+    signature_replay_cache: bool,
+    #[serde(default)]
+    authorized_fetch: bool,
+    #[serde(default)]
+    blocked_domains: Vec<String>,
+    #[serde(default)]
+    json_ld: JsonLdConfig,
+    #[serde(default)]
+    schedule: ScheduleConfig,
+    #[serde(default)]
+    metrics: MetricsConfig,
+}
+
+fn default_archive_path() -> std::path::PathBuf {
+    std::path::PathBuf::from("./archives")
+}
+
+#[derive(Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct JsonLdConfig {
+    pub enabled: bool,
+    pub lenient_domains: Vec<String>,
+}
+
+impl JsonLdConfig {
+    pub fn is_lenient(&self, domain: &str) -> bool {
+        domain_in_list(&self.lenient_domains, domain)
+    }
+}
+
+/// Returns if a domain, or a domain it's a subdomain of, is in a list from the config
+pub fn domain_in_list(domains: &[String], domain: &str) -> bool {
+    let domain = domain.to_ascii_lowercase();
+    domains.iter().any(|d| {
+        let d = d.to_ascii_lowercase();
+        domain == d || domain.ends_with(&format!(".{}", d))
+    })
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    /// Where the task runner serves its metrics, as it has no HTTP server of its own
+    pub tasks_address: String,
+    /// Label delivery and fetch metrics with the remote domain, which grows with the number of known servers
+    pub domain_labels: bool,
+    /// Token scrapers must send as `Authorization: Bearer <token>`, metrics are refused without one set
+    pub bearer_token: Option<String>,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        MetricsConfig {
+            enabled: false,
+            tasks_address: "127.0.0.1:9925".to_string(),
+            domain_labels: false,
+            bearer_token: None,
+        }
+    }
+}
+
+/// Cron expressions, in UTC, for the jobs submitted by the beat scheduler; an empty string disables a job
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct ScheduleConfig {
+    pub recompute_trends: String,
+    pub update_instance_stats: String,
+    pub delete_expired_archives: String,
+    pub probe_unavailable_domains: String,
+    pub refresh_stale_accounts: String,
+    pub delete_expired_sessions: String,
+    pub delete_orphaned_media: String,
+}
+
+impl Default for ScheduleConfig {
+    fn default() -> Self {
+        ScheduleConfig {
+            recompute_trends: "*/15 * * * *".to_string(),
+            update_instance_stats: "0 * * * *".to_string(),
+            delete_expired_archives: "30 * * * *".to_string(),
+            probe_unavailable_domains: "0 */6 * * *".to_string(),
+            refresh_stale_accounts: "45 * * * *".to_string(),
+            delete_expired_sessions: "*/30 * * * *".to_string(),
+            delete_orphaned_media: "15 3 * * *".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct InstanceConfig {
+    pub title: String,
+    pub short_description: String,
+    pub description: String,
+    pub extended_description: String,
+    pub contact_email: String,
+    pub contact_account: Option<String>,
+    pub thumbnail: Option<String>,
+    pub languages: Vec<String>,
+    pub rules: Vec<String>,
+}
+
+impl Default for InstanceConfig {
+    fn default() -> Self {
+        InstanceConfig {
+            title: "Tafarn".to_string(),
+            short_description: String::new(),
+            description: String::new(),
+            extended_description: String::new(),
+            contact_email: String::new(),
+            contact_account: None,
+            thumbnail: None,
+            languages: vec!["en".to_string()],
+            rules: vec![],
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -194,6 +329,8 @@ pub struct OIDCConfig {
     client_secret: String,
     #[serde(default)]
     required_role: Option<String>,
+    #[serde(default)]
+    moderator_role: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -207,15 +344,29 @@ pub struct AppConfig {
     pub web_push_signature: web_push::PartialVapidSignatureBuilder,
     pub as_key: openssl::pkey::PKey<openssl::pkey::Private>,
     pub media_path: PathBuf,
+    pub archive_path: PathBuf,
+    pub trends_require_review: bool,
+    pub instance: InstanceConfig,
+    pub loaded_at: chrono::DateTime<chrono::Utc>,
+    pub signature_replay_cache: bool,
+    pub authorized_fetch: bool,
+    pub blocked_domains: Vec<String>,
+    pub json_ld: JsonLdConfig,
+    pub metrics: MetricsConfig,
 }
 
 pub struct App {
     pub rocket: rocket::Rocket<rocket::Build>,
     pub celery_app: CeleryApp,
+    pub amqp_url: String,
     pub uri: String,
     pub vapid_key: Vec<u8>,
     pub as_key: openssl::pkey::PKey<openssl::pkey::Private>,
     pub media_path: PathBuf,
+    pub archive_path: PathBuf,
+    pub json_ld: JsonLdConfig,
+    pub schedule: ScheduleConfig,
+    pub metrics: MetricsConfig,
 }
 
 pub fn gen_media_path(root: &std::path::Path, ext: &str) -> (String, PathBuf) {
@@ -236,6 +387,8 @@ pub async fn setup() -> App {
     let as_key_bytes = std::fs::read(config.as_key).expect("Unable to read ActivityStreams key");
     let as_key = openssl::pkey::PKey::private_key_from_pem(as_key_bytes.as_slice()).expect("Unable to parse ActivityStreams key");
 
+    metrics::set_domain_labels(config.metrics.domain_labels);
+
     let celery_app = celery::app!(
         broker = AMQPBroker { config.celery.amqp_url.clone() },
         tasks = [
@@ -246,9 +399,13 @@ pub async fn setup() -> App {
             tasks::accounts::deliver_account_update,
             tasks::accounts::delete_account,
             tasks::accounts::delete_account_by_id,
+            tasks::accounts::delete_local_account,
+            tasks::accounts::move_account,
+            tasks::accounts::process_move,
 
             tasks::inbox::process_activity,
             tasks::delivery::deliver_object,
+            tasks::delivery::forward_activity,
 
             tasks::relationships::process_follow,
             tasks::relationships::process_undo_follow,
@@ -267,6 +424,8 @@ pub async fn setup() -> App {
             tasks::statuses::delete_status_by_id,
             tasks::statuses::undo_announce,
             tasks::statuses::undo_like,
+            tasks::statuses::process_add,
+            tasks::statuses::process_remove,
             tasks::statuses::insert_into_timelines,
             tasks::statuses::deliver_status,
             tasks::statuses::deliver_status_delete,
@@ -274,7 +433,31 @@ pub async fn setup() -> App {
             tasks::statuses::deliver_undo_boost,
             tasks::statuses::deliver_like,
             tasks::statuses::deliver_undo_like,
+            tasks::statuses::deliver_pin,
+            tasks::statuses::deliver_unpin,
+            tasks::accounts::deliver_featured_tag,
             tasks::statuses::get_replies,
+            tasks::statuses::backfill_thread,
+
+            tasks::trends::recompute_trends,
+            tasks::trends::review_tag,
+            tasks::trends::review_status,
+            tasks::stats::update_instance_stats,
+            tasks::imports::process_import,
+            tasks::archives::build_archive,
+            tasks::archives::delete_expired_archives,
+            tasks::groups::process_group_post,
+            tasks::groups::announce_group_post,
+            tasks::relays::subscribe_relay,
+            tasks::relays::unsubscribe_relay,
+            tasks::relays::ingest_relayed_status,
+            tasks::reports::process_report,
+            tasks::reports::process_flag,
+            tasks::delivery::deliver_system_object,
+            tasks::delivery::probe_unavailable_domains,
+            tasks::maintenance::refresh_stale_accounts,
+            tasks::maintenance::delete_expired_sessions,
+            tasks::maintenance::delete_orphaned_media,
         ],
         task_routes = [],
         prefetch_count = 5,
@@ -294,6 +477,7 @@ pub async fn setup() -> App {
         &config.oidc.client_id,
         &config.oidc.client_secret,
         config.oidc.required_role.as_deref(),
+        config.oidc.moderator_role.as_deref(),
     ).await.expect("Unable to setup OIDC app");
 
     info!("Applying database migrations");
@@ -309,10 +493,24 @@ pub async fn setup() -> App {
             web_push_signature,
             as_key: as_key.clone(),
             media_path: config.media_path.clone(),
+            archive_path: config.archive_path.clone(),
+            trends_require_review: config.trends_require_review,
+            instance: config.instance,
+            loaded_at: chrono::Utc::now(),
+            signature_replay_cache: config.signature_replay_cache,
+            authorized_fetch: config.authorized_fetch,
+            blocked_domains: config.blocked_domains,
+            json_ld: config.json_ld.clone(),
+            metrics: config.metrics.clone(),
         }).manage(oidc_app),
         celery_app,
+        amqp_url: config.celery.amqp_url,
         vapid_key: vapid_key_bytes,
         as_key,
         media_path: config.media_path,
+        archive_path: config.archive_path,
+        json_ld: config.json_ld,
+        schedule: config.schedule,
+        metrics: config.metrics,
     }
 }
\ No newline at end of file
@@ -0,0 +1,495 @@
+//! Prometheus metrics, collected in process and rendered in the text exposition format.
+//!
+//! Labels are kept to values from a small fixed set, such as route templates, task names and status codes.
+//! The remote domain is only added to delivery and fetch metrics when `domain_labels` is enabled.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+const DURATION_BUCKETS: [f64; 14] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0,
+];
+const MAX_REQUEST_SIZE: usize = 8192;
+
+static DOMAIN_LABELS: AtomicBool = AtomicBool::new(false);
+
+pub struct Counter {
+    name: &'static str,
+    help: &'static str,
+    labels: &'static [&'static str],
+    values: std::sync::Mutex<BTreeMap<Vec<String>, u64>>,
+}
+
+pub struct Gauge {
+    name: &'static str,
+    help: &'static str,
+    labels: &'static [&'static str],
+    values: std::sync::Mutex<BTreeMap<Vec<String>, i64>>,
+}
+
+#[derive(Default)]
+struct HistogramValue {
+    buckets: [u64; DURATION_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+pub struct Histogram {
+    name: &'static str,
+    help: &'static str,
+    labels: &'static [&'static str],
+    values: std::sync::Mutex<BTreeMap<Vec<String>, HistogramValue>>,
+}
+
+fn label_values(labels: &[&str]) -> Vec<String> {
+    labels.iter().map(|l| l.to_string()).collect()
+}
+
+/// Formats a label set, leaving out empty values as Prometheus treats them as absent anyway
+fn format_labels(names: &[&str], values: &[String], extra: Option<(&str, &str)>) -> String {
+    let labels = names.iter().zip(values.iter())
+        .map(|(n, v)| (*n, v.as_str()))
+        .chain(extra)
+        .filter(|(_, v)| !v.is_empty())
+        .map(|(n, v)| format!(
+            "{}=\"{}\"", n, v.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+        ))
+        .collect::<Vec<_>>();
+    if labels.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", labels.join(","))
+    }
+}
+
+impl Counter {
+    fn new(name: &'static str, help: &'static str, labels: &'static [&'static str]) -> Self {
+        Counter {
+            name,
+            help,
+            labels,
+            values: std::sync::Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn inc(&self, labels: &[&str]) {
+        *self.values.lock().unwrap().entry(label_values(labels)).or_insert(0) += 1;
+    }
+
+    fn render(&self, out: &mut String) {
+        let values = self.values.lock().unwrap();
+        if values.is_empty() {
+            return;
+        }
+        let _ = writeln!(out, "# HELP {} {}\n# TYPE {} counter", self.name, self.help, self.name);
+        for (labels, value) in values.iter() {
+            let _ = writeln!(out, "{}{} {}", self.name, format_labels(self.labels, labels, None), value);
+        }
+    }
+}
+
+impl Gauge {
+    fn new(name: &'static str, help: &'static str, labels: &'static [&'static str]) -> Self {
+        Gauge {
+            name,
+            help,
+            labels,
+            values: std::sync::Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn set(&self, labels: &[&str], value: i64) {
+        self.values.lock().unwrap().insert(label_values(labels), value);
+    }
+
+    pub fn add(&self, labels: &[&str], value: i64) {
+        *self.values.lock().unwrap().entry(label_values(labels)).or_insert(0) += value;
+    }
+
+    fn render(&self, out: &mut String) {
+        let values = self.values.lock().unwrap();
+        if values.is_empty() {
+            return;
+        }
+        let _ = writeln!(out, "# HELP {} {}\n# TYPE {} gauge", self.name, self.help, self.name);
+        for (labels, value) in values.iter() {
+            let _ = writeln!(out, "{}{} {}", self.name, format_labels(self.labels, labels, None), value);
+        }
+    }
+}
+
+impl Histogram {
+    fn new(name: &'static str, help: &'static str, labels: &'static [&'static str]) -> Self {
+        Histogram {
+            name,
+            help,
+            labels,
+            values: std::sync::Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn observe(&self, labels: &[&str], value: f64) {
+        let mut values = self.values.lock().unwrap();
+        let histogram = values.entry(label_values(labels)).or_default();
+        for (bucket, bound) in histogram.buckets.iter_mut().zip(DURATION_BUCKETS.iter()) {
+            if value <= *bound {
+                *bucket += 1;
+            }
+        }
+        histogram.count += 1;
+        histogram.sum += value;
+    }
+
+    fn render(&self, out: &mut String) {
+        let values = self.values.lock().unwrap();
+        if values.is_empty() {
+            return;
+        }
+        let _ = writeln!(out, "# HELP {} {}\n# TYPE {} histogram", self.name, self.help, self.name);
+        for (labels, value) in values.iter() {
+            for (bucket, bound) in value.buckets.iter().zip(DURATION_BUCKETS.iter()) {
+                let _ = writeln!(
+                    out, "{}_bucket{} {}", self.name,
+                    format_labels(self.labels, labels, Some(("le", &bound.to_string()))), bucket
+                );
+            }
+            let _ = writeln!(
+                out, "{}_bucket{} {}", self.name, format_labels(self.labels, labels, Some(("le", "+Inf"))), value.count
+            );
+            let _ = writeln!(out, "{}_sum{} {}", self.name, format_labels(self.labels, labels, None), value.sum);
+            let _ = writeln!(out, "{}_count{} {}", self.name, format_labels(self.labels, labels, None), value.count);
+        }
+    }
+}
+
+lazy_static! {
+    pub static ref HTTP_REQUESTS: Counter = Counter::new(
+        "tafarn_http_requests_total", "HTTP requests handled, by route template", &["method", "route", "status"]
+    );
+    pub static ref HTTP_REQUEST_DURATION: Histogram = Histogram::new(
+        "tafarn_http_request_duration_seconds", "Time taken to handle HTTP requests", &["method", "route"]
+    );
+    pub static ref DB_POOL_MAX_CONNECTIONS: Gauge = Gauge::new(
+        "tafarn_db_pool_max_connections", "Size of each database connection pool", &["pool"]
+    );
+    pub static ref DB_POOL_CONNECTIONS: Gauge = Gauge::new(
+        "tafarn_db_pool_connections", "Open database connections, by pool and state", &["pool", "state"]
+    );
+    pub static ref DB_QUERIES_IN_FLIGHT: Gauge = Gauge::new(
+        "tafarn_db_queries_in_flight", "Database operations currently running for HTTP requests", &[]
+    );
+    pub static ref DB_QUERY_DURATION: Histogram = Histogram::new(
+        "tafarn_db_query_duration_seconds", "Time taken by database operations for HTTP requests", &[]
+    );
+    pub static ref INBOX_REQUESTS: Counter = Counter::new(
+        "tafarn_inbox_requests_total", "Activities POSTed to inboxes, by outcome", &["inbox", "outcome"]
+    );
+    pub static ref TASKS: Counter = Counter::new(
+        "tafarn_tasks_total", "Task executions, by outcome", &["task", "outcome"]
+    );
+    pub static ref TASK_DURATION: Histogram = Histogram::new(
+        "tafarn_task_duration_seconds", "Time taken by tasks that succeeded or timed out", &["task"]
+    );
+    pub static ref TASK_RETRIES: Counter = Counter::new(
+        "tafarn_task_retries_total", "Task executions scheduled for retry", &["task"]
+    );
+    pub static ref TASK_RETRIES_EXCEEDED: Counter = Counter::new(
+        "tafarn_task_retries_exceeded_total", "Tasks given up on after using all their retries", &["task"]
+    );
+    pub static ref DELIVERIES: Counter = Counter::new(
+        "tafarn_deliveries_total", "Deliveries to remote inboxes, by response status", &["status", "domain"]
+    );
+    pub static ref FETCH_DURATION: Histogram = Histogram::new(
+        "tafarn_fetch_duration_seconds", "Time taken to fetch remote objects, including retries", &["outcome", "domain"]
+    );
+}
+
+pub fn set_domain_labels(enabled: bool) {
+    DOMAIN_LABELS.store(enabled, Ordering::Relaxed);
+}
+
+/// The domain label for a remote URL, empty unless per-domain labels are enabled
+pub fn domain_label(url: &str) -> String {
+    if !DOMAIN_LABELS.load(Ordering::Relaxed) {
+        return String::new();
+    }
+    reqwest::Url::parse(url).ok()
+        .and_then(|u| u.host_str().map(|h| h.to_string()))
+        .unwrap_or_default()
+}
+
+pub fn render() -> String {
+    if let Some(config) = crate::tasks::CONFIG.read().unwrap().as_ref() {
+        let state = config.db.state();
+        DB_POOL_MAX_CONNECTIONS.set(&["tasks"], config.db.max_size() as i64);
+        DB_POOL_CONNECTIONS.set(&["tasks", "idle"], state.idle_connections as i64);
+        DB_POOL_CONNECTIONS.set(&["tasks", "active"], (state.connections - state.idle_connections) as i64);
+    }
+
+    let mut out = String::new();
+    HTTP_REQUESTS.render(&mut out);
+    HTTP_REQUEST_DURATION.render(&mut out);
+    DB_POOL_MAX_CONNECTIONS.render(&mut out);
+    DB_POOL_CONNECTIONS.render(&mut out);
+    DB_QUERIES_IN_FLIGHT.render(&mut out);
+    DB_QUERY_DURATION.render(&mut out);
+    INBOX_REQUESTS.render(&mut out);
+    TASKS.render(&mut out);
+    TASK_DURATION.render(&mut out);
+    TASK_RETRIES.render(&mut out);
+    TASK_RETRIES_EXCEEDED.render(&mut out);
+    DELIVERIES.render(&mut out);
+    FETCH_DURATION.render(&mut out);
+    out
+}
+
+/// A task event parsed from one of the lines Celery's tracer logs
+#[derive(Debug, PartialEq)]
+enum TaskLogEvent<'a> {
+    Finished { task: &'a str, outcome: &'static str, duration: Option<f64> },
+    Retrying { task: &'a str },
+    RetriesExceeded { task: &'a str },
+}
+
+/// Parses a `celery::app::trace` log line, the tests below pin the formats of the Celery version in use.
+///
+/// Celery's `on_success` and `on_failure` hooks would have to be added to every task and aren't told how long it
+/// ran, whereas its tracer logs every outcome along with the task name and duration.
+fn parse_task_log(message: &str) -> Option<TaskLogEvent<'_>> {
+    let (task, event) = message.strip_prefix("Task ")
+        .and_then(|m| m.split_once('['))
+        .and_then(|(task, rest)| rest.split_once("] ").map(|(_, event)| (task, event)))?;
+    let duration = |s: &str| s.trim_end_matches('s').parse::<f64>().ok();
+    let finished = |outcome, duration| Some(TaskLogEvent::Finished { task, outcome, duration });
+
+    if let Some(rest) = event.strip_prefix("succeeded in ") {
+        finished("succeeded", rest.split_once(':').and_then(|(d, _)| duration(d)))
+    } else if event.starts_with("failed with expected error") {
+        finished("expected_error", None)
+    } else if event.starts_with("failed with unexpected error") {
+        finished("unexpected_error", None)
+    } else if let Some(rest) = event.strip_prefix("timed out after ") {
+        finished("timeout", duration(rest))
+    } else if event.starts_with("triggered retry") {
+        finished("retry", None)
+    } else if event.starts_with("expired") {
+        finished("expired", None)
+    } else if event.starts_with("retrying") {
+        Some(TaskLogEvent::Retrying { task })
+    } else if event.starts_with("retries exceeded") {
+        Some(TaskLogEvent::RetriesExceeded { task })
+    } else {
+        None
+    }
+}
+
+fn record_task_log(message: &str) {
+    match parse_task_log(message) {
+        Some(TaskLogEvent::Finished { task, outcome, duration }) => {
+            TASKS.inc(&[task, outcome]);
+            if let Some(d) = duration {
+                TASK_DURATION.observe(&[task], d);
+            }
+        }
+        Some(TaskLogEvent::Retrying { task }) => TASK_RETRIES.inc(&[task]),
+        Some(TaskLogEvent::RetriesExceeded { task }) => TASK_RETRIES_EXCEEDED.inc(&[task]),
+        None => {}
+    }
+}
+
+struct TaskLogger {
+    inner: Box<dyn log::Log>,
+}
+
+impl log::Log for TaskLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.target() == "celery::app::trace" || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if record.target() == "celery::app::trace" {
+            record_task_log(&record.args().to_string());
+        }
+        self.inner.log(record);
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Sets up logging as `pretty_env_logger::init` does, but also collecting task metrics from Celery's log lines
+pub fn init_task_logger() {
+    let mut builder = pretty_env_logger::formatted_builder();
+    if let Ok(s) = std::env::var("RUST_LOG") {
+        builder.parse_filters(&s);
+    }
+    let logger = builder.build();
+    let level = std::cmp::max(logger.filter(), log::LevelFilter::Info);
+
+    log::set_boxed_logger(Box::new(TaskLogger {
+        inner: Box::new(logger),
+    })).expect("Unable to set logger");
+    log::set_max_level(level);
+}
+
+/// Checks the token a scraper sent against the configured one, refusing everything if none is configured
+pub fn check_token(expected: Option<&str>, given: Option<&str>) -> bool {
+    match (expected, given) {
+        (Some(e), Some(g)) => e.len() == g.len() && openssl::memcmp::eq(e.as_bytes(), g.as_bytes()),
+        _ => false
+    }
+}
+
+fn request_token(request: &[u8]) -> Option<String> {
+    String::from_utf8_lossy(request).lines()
+        .filter_map(|l| l.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("authorization"))
+        .and_then(|(_, value)| value.trim().strip_prefix("Bearer ").map(|t| t.trim().to_string()))
+}
+
+async fn handle_connection(mut stream: tokio::net::TcpStream, token: Option<String>) -> std::io::Result<()> {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 || request.len() + n > MAX_REQUEST_SIZE {
+            return Ok(());
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+
+    let response = if !request.starts_with(b"GET /metrics ") {
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+    } else if !check_token(token.as_deref(), request_token(&request).as_deref()) {
+        "HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+    } else {
+        let body = render();
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(), body
+        )
+    };
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Serves `/metrics` on its own listener, for processes without a Rocket server
+pub async fn serve(address: String, token: Option<String>) {
+    let listener = match tokio::net::TcpListener::bind(&address).await {
+        Ok(l) => l,
+        Err(e) => {
+            error!("Unable to listen for metrics on {}: {}", address, e);
+            return;
+        }
+    };
+    info!("Serving metrics on {}", address);
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let token = token.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_connection(stream, token).await {
+                        debug!("Error serving metrics: {}", e);
+                    }
+                });
+            }
+            Err(e) => warn!("Unable to accept metrics connection: {}", e),
+        }
+    }
+}
+
+struct RequestStart(std::time::Instant);
+
+/// Counts and times every request, labelled by the template of the route that handled it
+pub struct MetricsFairing;
+
+#[rocket::async_trait]
+impl rocket::fairing::Fairing for MetricsFairing {
+    fn info(&self) -> rocket::fairing::Info {
+        rocket::fairing::Info {
+            name: "Metrics",
+            kind: rocket::fairing::Kind::Request | rocket::fairing::Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut rocket::Request<'_>, _data: &mut rocket::Data<'_>) {
+        request.local_cache(|| RequestStart(std::time::Instant::now()));
+    }
+
+    async fn on_response<'r>(&self, request: &'r rocket::Request<'_>, response: &mut rocket::Response<'r>) {
+        let start = request.local_cache(|| RequestStart(std::time::Instant::now()));
+        let method = request.method().as_str();
+        let route = request.route().map(|r| r.uri.as_str()).unwrap_or("unmatched");
+        HTTP_REQUESTS.inc(&[method, route, &response.status().code.to_string()]);
+        HTTP_REQUEST_DURATION.observe(&[method, route], start.0.elapsed().as_secs_f64());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "5b2f43c4-0a5e-4b0e-9d7e-2f1b1c9d6b1e";
+
+    // Each line is built with the format string celery::app::trace uses for it
+
+    #[test]
+    fn task_succeeded() {
+        let line = format!("Task {}[{}] succeeded in {}s: {:?}", "deliver_object", ID, 0.25f32, ());
+        assert_eq!(parse_task_log(&line), Some(TaskLogEvent::Finished {
+            task: "deliver_object", outcome: "succeeded", duration: Some(0.25),
+        }));
+    }
+
+    #[test]
+    fn task_failed() {
+        let line = format!("Task {}[{}] failed with expected error: {}", "deliver_object", ID, "Unable to: deliver");
+        assert_eq!(parse_task_log(&line), Some(TaskLogEvent::Finished {
+            task: "deliver_object", outcome: "expected_error", duration: None,
+        }));
+        let line = format!("Task {}[{}] failed with unexpected error: {}", "deliver_object", ID, "oops");
+        assert_eq!(parse_task_log(&line), Some(TaskLogEvent::Finished {
+            task: "deliver_object", outcome: "unexpected_error", duration: None,
+        }));
+    }
+
+    #[test]
+    fn task_timed_out() {
+        let line = format!("Task {}[{}] timed out after {}s", "build_archive", ID, 300.5f32);
+        assert_eq!(parse_task_log(&line), Some(TaskLogEvent::Finished {
+            task: "build_archive", outcome: "timeout", duration: Some(300.5),
+        }));
+    }
+
+    #[test]
+    fn task_retry_and_expiry() {
+        let line = format!("Task {}[{}] triggered retry", "deliver_object", ID);
+        assert_eq!(parse_task_log(&line), Some(TaskLogEvent::Finished {
+            task: "deliver_object", outcome: "retry", duration: None,
+        }));
+        let line = format!("Task {}[{}] expired, discarding", "deliver_object", ID);
+        assert_eq!(parse_task_log(&line), Some(TaskLogEvent::Finished {
+            task: "deliver_object", outcome: "expired", duration: None,
+        }));
+    }
+
+    #[test]
+    fn task_retrying() {
+        let line = format!("Task {}[{}] retrying ({} / {})", "deliver_object", ID, 3, 25);
+        assert_eq!(parse_task_log(&line), Some(TaskLogEvent::Retrying { task: "deliver_object" }));
+        let line = format!("Task {}[{}] retrying ({} / inf)", "deliver_object", ID, 3);
+        assert_eq!(parse_task_log(&line), Some(TaskLogEvent::Retrying { task: "deliver_object" }));
+        let line = format!("Task {}[{}] retries exceeded", "deliver_object", ID);
+        assert_eq!(parse_task_log(&line), Some(TaskLogEvent::RetriesExceeded { task: "deliver_object" }));
+    }
+
+    #[test]
+    fn task_received_ignored() {
+        assert_eq!(parse_task_log(&format!("Task {}[{}] received", "deliver_object", ID)), None);
+        assert_eq!(parse_task_log("Executing task with 300 second time limit"), None);
+    }
+}
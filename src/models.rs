@@ -51,6 +51,7 @@ pub struct OAuthCodes {
     pub redirect_uri: String,
     pub client_id: uuid::Uuid,
     pub user_id: String,
+    pub account_id: Option<uuid::Uuid>,
 }
 
 #[derive(Insertable, Queryable, AsChangeset, Serialize, Deserialize, Clone, Debug)]
@@ -67,7 +68,8 @@ pub struct OAuthToken {
     pub time: chrono::NaiveDateTime,
     pub client_id: uuid::Uuid,
     pub user_id: String,
-    pub revoked: bool
+    pub revoked: bool,
+    pub account_id: Option<uuid::Uuid>,
 }
 
 #[derive(Insertable, Queryable, AsChangeset, Serialize, Deserialize, Clone, Debug)]
@@ -112,6 +114,28 @@ pub struct Account {
     pub header_remote_url: Option<String>,
     pub follower_collection_url: Option<String>,
     pub deleted_at: Option<chrono::NaiveDateTime>,
+    #[serde(default)]
+    pub moderator: bool,
+    #[serde(default)]
+    pub suspended_at: Option<chrono::NaiveDateTime>,
+    #[serde(default)]
+    pub silenced_at: Option<chrono::NaiveDateTime>,
+    #[serde(default)]
+    pub sensitized_at: Option<chrono::NaiveDateTime>,
+    #[serde(default)]
+    pub also_known_as: Vec<String>,
+    #[serde(default)]
+    pub moved_to: Option<uuid::Uuid>,
+    #[serde(default)]
+    pub moved_at: Option<chrono::NaiveDateTime>,
+    #[serde(default)]
+    pub group_review_posts: bool,
+    #[serde(default)]
+    pub featured_collection_url: Option<String>,
+    #[serde(default)]
+    pub featured_tags_collection_url: Option<String>,
+    #[serde(default)]
+    pub ed25519_private_key: Option<String>,
 }
 
 #[derive(Insertable, Clone, Debug)]
@@ -147,6 +171,9 @@ pub struct NewAccount {
     pub header_content_type: Option<String>,
     pub header_remote_url: Option<String>,
     pub follower_collection_url: Option<String>,
+    pub also_known_as: Vec<String>,
+    pub featured_collection_url: Option<String>,
+    pub featured_tags_collection_url: Option<String>,
 }
 
 impl Account {
@@ -166,9 +193,29 @@ impl Account {
         }
     }
 
+    pub fn featured_collection(&self, uri: &str) -> Option<String> {
+        if self.local {
+            Some(format!("https://{}/as/users/{}/featured", uri, self.id))
+        } else {
+            self.featured_collection_url.clone()
+        }
+    }
+
+    pub fn featured_tags_collection(&self, uri: &str) -> Option<String> {
+        if self.local {
+            Some(format!("https://{}/as/users/{}/featured_tags", uri, self.id))
+        } else {
+            self.featured_tags_collection_url.clone()
+        }
+    }
+
     pub fn key_id(&self, uri: &str) -> String {
         format!("https://{}/as/users/{}#key", uri, self.id)
     }
+
+    pub fn ed25519_key_id(&self, uri: &str) -> String {
+        format!("https://{}/as/users/{}#ed25519-key", uri, self.id)
+    }
 }
 
 #[derive(Insertable, Queryable, Identifiable, AsChangeset, Serialize, Deserialize, Clone, Debug)]
@@ -248,6 +295,8 @@ pub struct Notification {
     pub cause: uuid::Uuid,
     pub status: Option<uuid::Uuid>,
     pub created_at: chrono::NaiveDateTime,
+    pub filtered: bool,
+    pub report: Option<uuid::Uuid>,
 }
 
 #[derive(Insertable, Clone, Debug)]
@@ -258,9 +307,98 @@ pub struct NewNotification {
     pub account: uuid::Uuid,
     pub cause: uuid::Uuid,
     pub status: Option<uuid::Uuid>,
+    pub report: Option<uuid::Uuid>,
     pub created_at: chrono::NaiveDateTime,
 }
 
+#[derive(Insertable, Queryable, Identifiable, AsChangeset, Serialize, Deserialize, Clone, Debug)]
+#[table_name="notification_policies"]
+#[primary_key(account)]
+pub struct NotificationPolicy {
+    pub account: uuid::Uuid,
+    pub for_not_following: String,
+    pub for_not_followers: String,
+    pub for_new_accounts: String,
+    pub for_private_mentions: String,
+}
+
+impl NotificationPolicy {
+    pub fn default_for(account: uuid::Uuid) -> Self {
+        NotificationPolicy {
+            account,
+            for_not_following: "accept".to_string(),
+            for_not_followers: "accept".to_string(),
+            for_new_accounts: "accept".to_string(),
+            for_private_mentions: "filter".to_string(),
+        }
+    }
+}
+
+#[derive(Queryable, Identifiable, Serialize, Deserialize, Clone, Debug)]
+#[table_name="notification_requests"]
+pub struct NotificationRequest {
+    pub id: uuid::Uuid,
+    pub iid: i64,
+    pub account: uuid::Uuid,
+    pub from_account: uuid::Uuid,
+    pub last_status: Option<uuid::Uuid>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable, Clone, Debug)]
+#[table_name="notification_requests"]
+pub struct NewNotificationRequest {
+    pub id: uuid::Uuid,
+    pub account: uuid::Uuid,
+    pub from_account: uuid::Uuid,
+    pub last_status: Option<uuid::Uuid>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable, Queryable, Serialize, Deserialize, Clone, Debug)]
+#[table_name="notification_permissions"]
+pub struct NotificationPermission {
+    pub account: uuid::Uuid,
+    pub from_account: uuid::Uuid,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable, Queryable, Identifiable, AsChangeset, Serialize, Deserialize, Clone, Debug)]
+#[table_name="reports"]
+pub struct Report {
+    pub id: uuid::Uuid,
+    pub iid: i64,
+    pub account: uuid::Uuid,
+    pub target_account: uuid::Uuid,
+    pub status_ids: Vec<uuid::Uuid>,
+    pub category: String,
+    pub comment: String,
+    pub forwarded: bool,
+    pub url: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    pub assigned_account: Option<uuid::Uuid>,
+    pub action_taken_at: Option<chrono::NaiveDateTime>,
+    pub action_taken_by_account: Option<uuid::Uuid>,
+}
+
+#[derive(Insertable, Clone, Debug)]
+#[table_name="reports"]
+pub struct NewReport {
+    pub id: uuid::Uuid,
+    pub account: uuid::Uuid,
+    pub target_account: uuid::Uuid,
+    pub status_ids: Vec<uuid::Uuid>,
+    pub category: String,
+    pub comment: String,
+    pub forwarded: bool,
+    pub url: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
 #[derive(Insertable, Queryable, Identifiable, AsChangeset, Serialize, Deserialize, Clone, Debug)]
 #[table_name="media"]
 pub struct Media {
@@ -308,6 +446,7 @@ pub struct Status {
     pub visible: bool,
     pub text_source: Option<String>,
     pub spoiler_text_source: Option<String>,
+    pub trendable: Option<bool>,
 }
 
 impl Status {
@@ -500,5 +639,220 @@ pub struct StatusMention {
 #[table_name="tags"]
 pub struct Tag {
     pub id: uuid::Uuid,
-    pub name: String
+    pub name: String,
+    pub trendable: Option<bool>,
+}
+
+#[derive(Insertable)]
+#[table_name="tags"]
+pub struct NewTag {
+    pub id: uuid::Uuid,
+    pub name: String,
+}
+
+#[derive(Insertable, Queryable, Serialize, Deserialize, Clone, Debug)]
+#[table_name="status_tags"]
+pub struct StatusTag {
+    pub id: uuid::Uuid,
+    pub status: uuid::Uuid,
+    pub tag: uuid::Uuid,
+}
+
+#[derive(Insertable, Queryable, Serialize, Deserialize, Clone, Debug)]
+#[table_name="trending_tags"]
+pub struct TrendingTag {
+    pub tag: uuid::Uuid,
+    pub score: f64,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable, Queryable, Serialize, Deserialize, Clone, Debug)]
+#[table_name="trending_statuses"]
+pub struct TrendingStatus {
+    pub status: uuid::Uuid,
+    pub score: f64,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable, Queryable, Serialize, Deserialize, Clone, Debug)]
+#[table_name="instance_stats"]
+pub struct InstanceStats {
+    pub id: bool,
+    pub user_count: i64,
+    pub status_count: i64,
+    pub local_comments: i64,
+    pub domain_count: i64,
+    pub active_month: i64,
+    pub active_half_year: i64,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable, Queryable, Serialize, Deserialize, Clone, Debug)]
+#[table_name="instance_peers"]
+pub struct InstancePeer {
+    pub domain: String,
+}
+
+#[derive(Insertable, Queryable, Serialize, Deserialize, Clone, Debug)]
+#[table_name="instance_activity"]
+pub struct InstanceActivity {
+    pub week: chrono::NaiveDate,
+    pub statuses: i64,
+    pub logins: i64,
+    pub registrations: i64,
+}
+
+#[derive(Queryable, Identifiable, AsChangeset, Serialize, Deserialize, Clone, Debug)]
+#[table_name="imports"]
+pub struct Import {
+    pub id: uuid::Uuid,
+    pub iid: i64,
+    pub account: uuid::Uuid,
+    pub import_type: String,
+    pub overwrite: bool,
+    pub total_items: i32,
+    pub imported_items: i32,
+    pub failed_items: i32,
+    pub created_at: chrono::NaiveDateTime,
+    pub finished_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Insertable, Clone, Debug)]
+#[table_name="imports"]
+pub struct NewImport {
+    pub id: uuid::Uuid,
+    pub account: uuid::Uuid,
+    pub import_type: String,
+    pub overwrite: bool,
+    pub total_items: i32,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Identifiable, AsChangeset, Serialize, Deserialize, Clone, Debug)]
+#[table_name="account_archives"]
+pub struct AccountArchive {
+    pub id: uuid::Uuid,
+    pub iid: i64,
+    pub account: uuid::Uuid,
+    pub file: Option<String>,
+    pub size: Option<i64>,
+    pub created_at: chrono::NaiveDateTime,
+    pub finished_at: Option<chrono::NaiveDateTime>,
+    pub expires_at: Option<chrono::NaiveDateTime>,
+    pub failed_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Insertable, Clone, Debug)]
+#[table_name="account_archives"]
+pub struct NewAccountArchive {
+    pub id: uuid::Uuid,
+    pub account: uuid::Uuid,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Identifiable, AsChangeset, Serialize, Deserialize, Clone, Debug)]
+#[table_name="group_posts"]
+pub struct GroupPost {
+    pub id: uuid::Uuid,
+    pub iid: i64,
+    pub group_id: uuid::Uuid,
+    pub status: uuid::Uuid,
+    pub announce: Option<uuid::Uuid>,
+    pub created_at: chrono::NaiveDateTime,
+    pub reviewed_at: Option<chrono::NaiveDateTime>,
+    pub approved: bool,
+}
+
+#[derive(Insertable, Clone, Debug)]
+#[table_name="group_posts"]
+pub struct NewGroupPost {
+    pub id: uuid::Uuid,
+    pub group_id: uuid::Uuid,
+    pub status: uuid::Uuid,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Identifiable, Serialize, Deserialize, Clone, Debug)]
+#[table_name="featured_tags"]
+pub struct FeaturedTag {
+    pub id: uuid::Uuid,
+    pub iid: i64,
+    pub account: uuid::Uuid,
+    pub tag: uuid::Uuid,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable, Clone, Debug)]
+#[table_name="featured_tags"]
+pub struct NewFeaturedTag {
+    pub id: uuid::Uuid,
+    pub account: uuid::Uuid,
+    pub tag: uuid::Uuid,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Identifiable, Serialize, Deserialize, Clone, Debug)]
+#[table_name="endorsements"]
+pub struct Endorsement {
+    pub id: uuid::Uuid,
+    pub iid: i64,
+    pub account: uuid::Uuid,
+    pub endorsed: uuid::Uuid,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable, Clone, Debug)]
+#[table_name="endorsements"]
+pub struct NewEndorsement {
+    pub id: uuid::Uuid,
+    pub account: uuid::Uuid,
+    pub endorsed: uuid::Uuid,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Identifiable, Serialize, Deserialize, Clone, Debug)]
+#[table_name="relays"]
+pub struct Relay {
+    pub id: uuid::Uuid,
+    pub iid: i64,
+    pub inbox_url: String,
+    pub actor: Option<String>,
+    pub state: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable, Clone, Debug)]
+#[table_name="relays"]
+pub struct NewRelay {
+    pub id: uuid::Uuid,
+    pub inbox_url: String,
+    pub state: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+impl Relay {
+    pub fn follow_id(&self, uri: &str) -> String {
+        format!("https://{}/as/relay/{}", uri, self.id)
+    }
+}
+
+#[derive(Insertable, Queryable, Identifiable, Serialize, Deserialize, Clone, Debug)]
+#[table_name="delivery_domains"]
+#[primary_key(domain)]
+pub struct DeliveryDomain {
+    pub domain: String,
+    pub consecutive_failures: i32,
+    pub first_failure_at: Option<chrono::NaiveDateTime>,
+    pub last_failure_at: Option<chrono::NaiveDateTime>,
+    pub last_success_at: Option<chrono::NaiveDateTime>,
+    pub unavailable_since: Option<chrono::NaiveDateTime>,
+    pub signature_format: Option<String>,
+}
+
+#[derive(Insertable, Queryable, Identifiable, Clone, Debug)]
+#[table_name="thread_backfills"]
+#[primary_key(status_id)]
+pub struct ThreadBackfill {
+    pub status_id: uuid::Uuid,
+    pub crawled_at: chrono::NaiveDateTime,
 }
\ No newline at end of file
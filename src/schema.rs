@@ -48,6 +48,7 @@ table! {
         redirect_uri -> Varchar,
         client_id -> Uuid,
         user_id -> Varchar,
+        account_id -> Nullable<Uuid>,
     }
 }
 
@@ -65,6 +66,7 @@ table! {
         client_id -> Uuid,
         user_id -> Varchar,
         revoked -> Bool,
+        account_id -> Nullable<Uuid>,
     }
 }
 
@@ -109,6 +111,17 @@ table! {
         header_remote_url -> Nullable<Varchar>,
         follower_collection_url -> Nullable<Varchar>,
         deleted_at -> Nullable<Timestamp>,
+        moderator -> Bool,
+        suspended_at -> Nullable<Timestamp>,
+        silenced_at -> Nullable<Timestamp>,
+        sensitized_at -> Nullable<Timestamp>,
+        also_known_as -> Array<Varchar>,
+        moved_to -> Nullable<Uuid>,
+        moved_at -> Nullable<Timestamp>,
+        group_review_posts -> Bool,
+        featured_collection_url -> Nullable<Varchar>,
+        featured_tags_collection_url -> Nullable<Varchar>,
+        ed25519_private_key -> Nullable<Varchar>,
     }
 }
 
@@ -175,6 +188,57 @@ table! {
         cause -> Uuid,
         status -> Nullable<Uuid>,
         created_at -> Timestamp,
+        filtered -> Bool,
+        report -> Nullable<Uuid>,
+    }
+}
+
+table! {
+    notification_policies (account) {
+        account -> Uuid,
+        for_not_following -> Varchar,
+        for_not_followers -> Varchar,
+        for_new_accounts -> Varchar,
+        for_private_mentions -> Varchar,
+    }
+}
+
+table! {
+    notification_requests (id) {
+        id -> Uuid,
+        iid -> Int8,
+        account -> Uuid,
+        from_account -> Uuid,
+        last_status -> Nullable<Uuid>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    notification_permissions (account, from_account) {
+        account -> Uuid,
+        from_account -> Uuid,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    reports (id) {
+        id -> Uuid,
+        iid -> Int8,
+        account -> Uuid,
+        target_account -> Uuid,
+        status_ids -> Array<Uuid>,
+        category -> Varchar,
+        comment -> Text,
+        forwarded -> Bool,
+        url -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        assigned_account -> Nullable<Uuid>,
+        action_taken_at -> Nullable<Timestamp>,
+        action_taken_by_account -> Nullable<Uuid>,
     }
 }
 
@@ -224,6 +288,7 @@ table! {
         visible -> Bool,
         text_source -> Nullable<Varchar>,
         spoiler_text_source -> Nullable<Varchar>,
+        trendable -> Nullable<Bool>,
     }
 }
 
@@ -319,6 +384,151 @@ table! {
     tags (id) {
         id -> Uuid,
         name -> Varchar,
+        trendable -> Nullable<Bool>,
+    }
+}
+
+table! {
+    status_tags (id) {
+        id -> Uuid,
+        status -> Uuid,
+        tag -> Uuid,
+    }
+}
+
+table! {
+    trending_tags (tag) {
+        tag -> Uuid,
+        score -> Float8,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    trending_statuses (status) {
+        status -> Uuid,
+        score -> Float8,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    instance_stats (id) {
+        id -> Bool,
+        user_count -> Int8,
+        status_count -> Int8,
+        local_comments -> Int8,
+        domain_count -> Int8,
+        active_month -> Int8,
+        active_half_year -> Int8,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    instance_peers (domain) {
+        domain -> Varchar,
+    }
+}
+
+table! {
+    instance_activity (week) {
+        week -> Date,
+        statuses -> Int8,
+        logins -> Int8,
+        registrations -> Int8,
+    }
+}
+
+table! {
+    imports (id) {
+        id -> Uuid,
+        iid -> Int8,
+        account -> Uuid,
+        import_type -> Varchar,
+        overwrite -> Bool,
+        total_items -> Integer,
+        imported_items -> Integer,
+        failed_items -> Integer,
+        created_at -> Timestamp,
+        finished_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    account_archives (id) {
+        id -> Uuid,
+        iid -> Int8,
+        account -> Uuid,
+        file -> Nullable<Varchar>,
+        size -> Nullable<Int8>,
+        created_at -> Timestamp,
+        finished_at -> Nullable<Timestamp>,
+        expires_at -> Nullable<Timestamp>,
+        failed_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    group_posts (id) {
+        id -> Uuid,
+        iid -> Int8,
+        group_id -> Uuid,
+        status -> Uuid,
+        announce -> Nullable<Uuid>,
+        created_at -> Timestamp,
+        reviewed_at -> Nullable<Timestamp>,
+        approved -> Bool,
+    }
+}
+
+table! {
+    featured_tags (id) {
+        id -> Uuid,
+        iid -> Int8,
+        account -> Uuid,
+        tag -> Uuid,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    endorsements (id) {
+        id -> Uuid,
+        iid -> Int8,
+        account -> Uuid,
+        endorsed -> Uuid,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    delivery_domains (domain) {
+        domain -> Varchar,
+        consecutive_failures -> Int4,
+        first_failure_at -> Nullable<Timestamp>,
+        last_failure_at -> Nullable<Timestamp>,
+        last_success_at -> Nullable<Timestamp>,
+        unavailable_since -> Nullable<Timestamp>,
+        signature_format -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    thread_backfills (status_id) {
+        status_id -> Uuid,
+        crawled_at -> Timestamp,
+    }
+}
+
+table! {
+    relays (id) {
+        id -> Uuid,
+        iid -> Int8,
+        inbox_url -> Varchar,
+        actor -> Nullable<Varchar>,
+        state -> Varchar,
+        created_at -> Timestamp,
     }
 }
 
@@ -364,5 +574,23 @@ allow_tables_to_appear_in_same_query!(
     account_notes,
     media_attachments,
     status_mentions,
-    tags
+    tags,
+    status_tags,
+    trending_tags,
+    trending_statuses,
+    notification_policies,
+    notification_requests,
+    notification_permissions,
+    reports,
+    instance_stats,
+    instance_peers,
+    instance_activity,
+    imports,
+    account_archives,
+    group_posts,
+    featured_tags,
+    endorsements,
+    relays,
+    delivery_domains,
+    thread_backfills
 );
\ No newline at end of file
@@ -5,7 +5,7 @@ use chrono::{DateTime, TimeZone, Utc};
 use diesel::prelude::*;
 use itertools::Itertools;
 use futures::stream::StreamExt;
-use super::{resolve_url, resolve_object, fetch_object};
+use super::{resolve_url, resolve_object, resolve_object_or_link, fetch_object};
 
 async fn fetch_image(img: &activity_streams::ReferenceOrObject<activity_streams::ImageOrLink>) -> Option<(String, String, String)> {
     let config = super::config();
@@ -66,6 +66,26 @@ async fn fetch_image(img: &activity_streams::ReferenceOrObject<activity_streams:
     }
 }
 
+/// Looks up an account by actor ID without fetching it from the network
+fn find_known_account(actor_id: &str) -> TaskResult<Option<models::Account>> {
+    let config = super::config();
+    let local_regex = regex::Regex::new(&format!("^https://{}/as/users/(?P<id>[^/#]+)$", config.uri)).unwrap();
+
+    tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = config.db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        if let Some(id) = local_regex.captures(actor_id)
+            .and_then(|c| uuid::Uuid::parse_str(c.name("id").unwrap().as_str()).ok()) {
+            crate::schema::accounts::dsl::accounts.find(id)
+                .filter(crate::schema::accounts::dsl::local.eq(true))
+                .get_result(&c).optional().with_expected_err(|| "Unable to fetch account")
+        } else {
+            crate::schema::accounts::dsl::accounts.filter(
+                crate::schema::accounts::dsl::actor.eq(actor_id)
+            ).get_result(&c).optional().with_expected_err(|| "Unable to fetch account")
+        }
+    })
+}
+
 async fn _update_account(
     object: activity_streams::Object, new_account: bool, follow_graph: bool,
 ) -> TaskResult<Option<models::Account>> {
@@ -129,6 +149,10 @@ async fn _update_account(
                     existing_account.shared_inbox_url = shared_inbox;
                     existing_account.follower_collection_url = a.followers.clone();
                     existing_account.discoverable = a.discoverable.or(existing_account.discoverable);
+                    existing_account.also_known_as = a.also_known_as.as_slice().to_vec();
+                    existing_account.featured_collection_url = a.featured.clone();
+                    existing_account.featured_tags_collection_url = a.featured_tags.clone();
+                    existing_account.updated_at = Utc::now().naive_utc();
 
                     if let Some((file, url, format)) = avatar {
                         existing_account.avatar_file = Some(file);
@@ -182,6 +206,9 @@ async fn _update_account(
                         header_content_type: None,
                         header_remote_url: None,
                         follower_collection_url: a.followers.clone(),
+                        also_known_as: a.also_known_as.as_slice().to_vec(),
+                        featured_collection_url: a.featured.clone(),
+                        featured_tags_collection_url: a.featured_tags.clone(),
                     };
 
                     if let Some((file, url, format)) = avatar {
@@ -204,6 +231,25 @@ async fn _update_account(
                 }
             };
 
+            let moved_to = match &a.moved_to {
+                Some(moved_to) => find_known_account(moved_to)?
+                    .map(|m| m.id).filter(|m| *m != new_account.id),
+                None => None
+            };
+            let new_account = if moved_to != new_account.moved_to {
+                tokio::task::block_in_place(|| -> TaskResult<_> {
+                    let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+                    diesel::update(crate::schema::accounts::dsl::accounts.find(new_account.id))
+                        .set((
+                            crate::schema::accounts::dsl::moved_to.eq(moved_to),
+                            crate::schema::accounts::dsl::moved_at.eq(moved_to.map(|_| Utc::now().naive_utc())),
+                        ))
+                        .get_result::<models::Account>(&c).with_expected_err(|| "Unable to update account")
+                })?
+            } else {
+                new_account
+            };
+
             for key in a.public_key.as_slice().into_iter() {
                 let key = match resolve_object(key.clone()).await {
                     Some(k) => k,
@@ -247,6 +293,45 @@ async fn _update_account(
                 })?;
             }
 
+            for key in a.assertion_method.as_slice() {
+                let key = match resolve_object(key.clone()).await {
+                    Some(k) => k,
+                    None => {
+                        warn!("Unable to resolve assertion method on {}: {:?}", object.id_or_default(), key);
+                        continue;
+                    }
+                };
+                let key_id = match &key.id {
+                    Some(i) => i.clone(),
+                    None => continue
+                };
+                if key.key_type.as_deref() != Some("Multikey") ||
+                    key.controller.is_none() || key.controller != new_account.actor {
+                    continue;
+                }
+                let pkey = match key.ed25519_key() {
+                    Some(k) => k,
+                    None => {
+                        warn!("Unsupported assertion method key \"{}\" on {}", key_id, object.id_or_default());
+                        continue;
+                    }
+                };
+                let key = models::PublicKey {
+                    id: uuid::Uuid::new_v4(),
+                    key_id,
+                    user_id: new_account.id,
+                    key: String::from_utf8(
+                        pkey.public_key_to_pem().with_unexpected_err(|| "Unable to serialize public key")?
+                    ).unwrap(),
+                };
+                tokio::task::block_in_place(|| -> TaskResult<_> {
+                    let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+                    diesel::insert_into(crate::schema::public_keys::dsl::public_keys)
+                        .values(&key)
+                        .execute(&c).with_expected_err(|| "Unable to insert public key")
+                })?;
+            }
+
             let mut pvs = vec![];
             for attachment in a.common.attachment.as_slice().into_iter() {
                 let attachment = match match resolve_object(attachment.clone()).await {
@@ -298,7 +383,10 @@ async fn _update_account(
             })?;
 
             super::config().celery.send_task(
-                update_account_relations::new(new_account.clone(), a.followers.clone(), a.following.clone(), follow_graph)
+                update_account_relations::new(
+                    new_account.clone(), a.followers.clone(), a.following.clone(), a.featured.clone(),
+                    a.featured_tags.clone(), follow_graph
+                )
             ).await.with_expected_err(|| "Unable to send task")?;
 
             Ok(Some(new_account))
@@ -336,7 +424,8 @@ impl<T> From<Option<T>> for NonMatchingOption<T> {
 
 #[celery::task]
 pub async fn update_account_relations(
-    account: models::Account, followers: Option<String>, following: Option<String>, follow_graph: bool
+    account: models::Account, followers: Option<String>, following: Option<String>, featured: Option<String>,
+    featured_tags: Option<String>, follow_graph: bool
 ) -> TaskResult<()> {
     let mut account = account;
     let db = super::config().db.clone();
@@ -492,6 +581,240 @@ pub async fn update_account_relations(
         })?;
     }
 
+    if let Some(featured) = featured {
+        if let Err(e) = update_featured(&account, &featured).await {
+            warn!("Unable to update featured collection of \"{}\": {}", account.id, e);
+        }
+    }
+    if let Some(featured_tags) = featured_tags {
+        if let Err(e) = update_featured_tags(&account, &featured_tags).await {
+            warn!("Unable to update featured tags of \"{}\": {}", account.id, e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Most pinned statuses to fetch from a remote featured collection
+const MAX_FEATURED: usize = 20;
+
+/// Replaces the pins of a remote account with the contents of its featured collection
+async fn update_featured(account: &models::Account, featured: &str) -> TaskResult<()> {
+    let db = super::config().db.clone();
+
+    let collection = match fetch_object(featured).await {
+        Some(c) => c,
+        None => return Err(TaskError::ExpectedError(format!("Unable to fetch featured collection {}", featured)))
+    };
+    let items = super::collection::fetch_entire_collection(collection)?
+        .take(MAX_FEATURED).collect::<Vec<_>>().await;
+
+    let mut pinned = vec![];
+    for item in items {
+        let item_id = item.id_or_default().to_string();
+        match super::statuses::get_status(item).await {
+            Ok(status) => if status.account_id == account.id {
+                pinned.push(status.id);
+            } else {
+                warn!("Featured status \"{}\" is not owned by account \"{}\"", status.id, account.id);
+            },
+            Err(e) => {
+                warn!("Unable to fetch featured status \"{}\": {}", item_id, e);
+            }
+        }
+    }
+
+    tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        c.transaction::<_, diesel::result::Error, _>(|| {
+            diesel::delete(crate::schema::pins::dsl::pins.filter(
+                crate::schema::pins::dsl::account.eq(account.id)
+            )).execute(&c)?;
+            diesel::insert_into(crate::schema::pins::dsl::pins)
+                .values(pinned.into_iter().map(|s| models::NewPin {
+                    id: uuid::Uuid::new_v4(),
+                    account: account.id,
+                    status: s,
+                }).collect::<Vec<_>>())
+                .execute(&c)
+        }).with_expected_err(|| "Unable to update pins")
+    })?;
+
+    Ok(())
+}
+
+/// Inserts the named tags, returning their IDs
+fn get_tag_ids(c: &diesel::PgConnection, names: &[String]) -> QueryResult<Vec<uuid::Uuid>> {
+    diesel::insert_into(crate::schema::tags::table)
+        .values(names.iter().map(|n| models::NewTag {
+            id: uuid::Uuid::new_v4(),
+            name: n.clone(),
+        }).collect::<Vec<_>>())
+        .on_conflict(crate::schema::tags::dsl::name)
+        .do_nothing()
+        .execute(c)?;
+
+    crate::schema::tags::dsl::tags.filter(
+        crate::schema::tags::dsl::name.eq_any(names)
+    ).select(crate::schema::tags::dsl::id).get_results(c)
+}
+
+fn hashtag_name(object: &activity_streams::Object) -> Option<String> {
+    match object {
+        activity_streams::Object::Hashtag(l) => l.name.as_deref()
+            .map(super::statuses::normalise_hashtag)
+            .filter(|n| !n.is_empty()),
+        _ => None
+    }
+}
+
+/// Replaces the featured tags of a remote account with the contents of its featured tags collection
+async fn update_featured_tags(account: &models::Account, featured_tags: &str) -> TaskResult<()> {
+    let db = super::config().db.clone();
+
+    let collection = match fetch_object(featured_tags).await {
+        Some(c) => c,
+        None => return Err(TaskError::ExpectedError(format!("Unable to fetch featured tags collection {}", featured_tags)))
+    };
+    let items = super::collection::fetch_entire_collection(collection)?
+        .take(crate::views::featured_tags::MAX_FEATURED_TAGS).collect::<Vec<_>>().await;
+
+    let mut names = vec![];
+    for item in items {
+        if let Some(name) = super::resolve_object_or_link(item).await.as_ref().and_then(hashtag_name) {
+            names.push(name);
+        }
+    }
+    names.sort();
+    names.dedup();
+
+    tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        c.transaction::<_, diesel::result::Error, _>(|| {
+            diesel::delete(crate::schema::featured_tags::dsl::featured_tags.filter(
+                crate::schema::featured_tags::dsl::account.eq(account.id)
+            )).execute(&c)?;
+            if names.is_empty() {
+                return Ok(0);
+            }
+            let tag_ids = get_tag_ids(&c, &names)?;
+            diesel::insert_into(crate::schema::featured_tags::dsl::featured_tags)
+                .values(tag_ids.into_iter().map(|t| models::NewFeaturedTag {
+                    id: uuid::Uuid::new_v4(),
+                    account: account.id,
+                    tag: t,
+                    created_at: Utc::now().naive_utc(),
+                }).collect::<Vec<_>>())
+                .execute(&c)
+        }).with_expected_err(|| "Unable to update featured tags")
+    })?;
+
+    Ok(())
+}
+
+/// Handles an `Add` or `Remove` targeting a remote account's featured tags collection
+pub async fn process_featured_tag(
+    activity: activity_streams::ActivityCommon, account: models::Account, remove: bool,
+) -> TaskResult<()> {
+    let db = super::config().db.clone();
+    let activity_id = activity.id_or_default().to_string();
+
+    let name = match activity.object {
+        Some(o) => match resolve_object_or_link(o).await.as_ref().and_then(hashtag_name) {
+            Some(n) => n,
+            None => {
+                warn!("Activity \"{}\" does not have a hashtag object", activity_id);
+                return Ok(());
+            }
+        },
+        None => {
+            warn!("Activity \"{}\" has no object", activity_id);
+            return Ok(());
+        }
+    };
+
+    tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        c.transaction::<_, diesel::result::Error, _>(|| {
+            let tag_id = get_tag_ids(&c, &[name])?;
+            if remove {
+                diesel::delete(crate::schema::featured_tags::dsl::featured_tags.filter(
+                    crate::schema::featured_tags::dsl::account.eq(account.id)
+                ).filter(
+                    crate::schema::featured_tags::dsl::tag.eq_any(tag_id)
+                )).execute(&c)
+            } else {
+                diesel::insert_into(crate::schema::featured_tags::dsl::featured_tags)
+                    .values(tag_id.into_iter().map(|t| models::NewFeaturedTag {
+                        id: uuid::Uuid::new_v4(),
+                        account: account.id,
+                        tag: t,
+                        created_at: Utc::now().naive_utc(),
+                    }).collect::<Vec<_>>())
+                    .on_conflict_do_nothing()
+                    .execute(&c)
+            }
+        }).with_expected_err(|| "Unable to update featured tags")
+    })?;
+
+    Ok(())
+}
+
+pub fn as_render_hashtag(name: &str) -> activity_streams::Object {
+    let config = super::config();
+
+    activity_streams::Object::Hashtag(activity_streams::Link {
+        href: Some(format!("https://{}/tags/{}", config.uri, name)),
+        name: Some(format!("#{}", name)),
+        ..Default::default()
+    })
+}
+
+#[celery::task]
+pub async fn deliver_featured_tag(
+    account: models::Account, name: String, remove: bool,
+) -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+
+    let followers = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        crate::schema::accounts::dsl::accounts.filter(
+            crate::schema::accounts::dsl::id.eq_any(
+                crate::schema::following::dsl::following.filter(
+                    crate::schema::following::dsl::followee.eq(account.id)
+                ).filter(
+                    crate::schema::following::dsl::pending.eq(false)
+                ).select(crate::schema::following::dsl::follower)
+            )
+        ).get_results::<models::Account>(&c).with_expected_err(|| "Unable to get followers")
+    })?;
+
+    let activity = activity_streams::ActivityCommon {
+        common: activity_streams::ObjectCommon {
+            id: Some(format!("https://{}/as/transient/{}", config.uri, uuid::Uuid::new_v4())),
+            to: activity_streams::Pluralisable::Object(
+                activity_streams::ReferenceOrObject::Reference("https://www.w3.org/ns/activitystreams#Public".to_string())
+            ),
+            ..Default::default()
+        },
+        actor: Some(activity_streams::ReferenceOrObject::Reference(account.actor_id(&config.uri))),
+        object: Some(activity_streams::ReferenceOrObject::Object(Box::new(
+            activity_streams::ObjectOrLink::Object(as_render_hashtag(&name))
+        ))),
+        target: account.featured_tags_collection(&config.uri).map(activity_streams::ReferenceOrObject::Reference),
+        result: None,
+        origin: None,
+        instrument: None,
+    };
+    let activity = if remove {
+        activity_streams::Object::Remove(activity)
+    } else {
+        activity_streams::Object::Add(activity)
+    };
+
+    super::delivery::deliver_dedupe_inboxes(activity, followers, account).await?;
+
     Ok(())
 }
 
@@ -608,19 +931,153 @@ pub async fn find_account(
     }
 }
 
+/// Resolves the account that made an HTTP signature, fetching the key's owner if we haven't seen the key before
+pub async fn find_signature_account(signature: &activity_streams::Signature) -> TaskResult<Option<models::Account>> {
+    let db = super::config().db;
+
+    let get_key = || tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        crate::schema::public_keys::dsl::public_keys.filter(
+            crate::schema::public_keys::dsl::key_id.eq(&signature.key_id)
+        ).get_result::<models::PublicKey>(&c).optional().with_expected_err(|| "Unable to fetch public key")
+    });
+
+    let public_key = match get_key()? {
+        Some(k) => k,
+        None => {
+            let owner = signature.key_id.split('#').next().unwrap_or_default().to_string();
+            if find_account(activity_streams::ReferenceOrObject::Reference(owner), false).await?.is_none() {
+                return Ok(None);
+            }
+            match get_key()? {
+                Some(k) => k,
+                None => {
+                    warn!("Unknown public key \"{}\"", signature.key_id);
+                    return Ok(None);
+                }
+            }
+        }
+    };
+
+    let pkey = openssl::pkey::PKey::public_key_from_pem(public_key.key.as_bytes())
+        .with_unexpected_err(|| "Unable to parse public key")?;
+    if !signature.verify(&pkey) {
+        warn!("Signature verification failed with key \"{}\"", signature.key_id);
+        return Ok(None);
+    }
+
+    tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        crate::schema::accounts::dsl::accounts.find(public_key.user_id)
+            .get_result(&c).optional().with_expected_err(|| "Unable to fetch account")
+    })
+}
+
+/// Resolves a `user@domain` address, using WebFinger for remote accounts
+pub async fn find_account_by_acct(acct: &str) -> TaskResult<Option<models::Account>> {
+    let config = super::config();
+    let db = config.db.clone();
+
+    let cap = match crate::WEBFINGER_RE.captures(acct) {
+        Some(c) => c,
+        None => return Ok(None)
+    };
+    let user = cap.name("user").unwrap().as_str().to_string();
+    let domain = cap.name("domain").unwrap().as_str();
+
+    if domain == config.uri {
+        return tokio::task::block_in_place(|| -> TaskResult<_> {
+            let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+            crate::schema::accounts::dsl::accounts.filter(
+                crate::schema::accounts::dsl::username.eq(user)
+            ).filter(
+                crate::schema::accounts::dsl::local.eq(true)
+            ).get_result(&c).optional().with_expected_err(|| "Unable to fetch account")
+        });
+    }
+
+    let url = format!("https://{}/.well-known/webfinger?resource=acct:{}", domain, cap.name("acct").unwrap().as_str());
+    let jrd = match crate::AS_CLIENT.get(&url).send().await {
+        Ok(r) => match r.json::<crate::views::meta::JRD>().await {
+            Ok(j) => j,
+            Err(e) => {
+                warn!("Unable to parse WebFinger response for {}: {}", acct, e);
+                return Ok(None);
+            }
+        },
+        Err(e) => {
+            warn!("Unable to fetch WebFinger for {}: {}", acct, e);
+            return Ok(None);
+        }
+    };
+
+    match jrd.links.into_iter()
+        .filter(|l| l.rel == "self")
+        .find(|l| l.type_.as_deref() == Some("application/activity+json"))
+        .and_then(|l| l.href) {
+        Some(actor) => find_account(activity_streams::ReferenceOrObject::Reference(actor), false).await,
+        None => Ok(None)
+    }
+}
+
+/// The Ed25519 key a local account signs with, generated the first time it's needed
+pub fn ed25519_private_key(account: &models::Account) -> TaskResult<Option<openssl::pkey::PKey<openssl::pkey::Private>>> {
+    if !account.local {
+        return Ok(None);
+    }
+
+    let key = match &account.ed25519_private_key {
+        Some(k) => k.clone(),
+        None => {
+            let db = super::config().db;
+            let new_key = openssl::pkey::PKey::generate_ed25519()
+                .and_then(|k| k.private_key_to_pem_pkcs8())
+                .with_unexpected_err(|| "Unable to generate Ed25519 key")?;
+            let new_key = String::from_utf8(new_key).unwrap();
+
+            // The account passed in may be out of date, so keep whichever key was stored first
+            tokio::task::block_in_place(|| -> TaskResult<_> {
+                let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+                diesel::update(crate::schema::accounts::dsl::accounts.find(account.id).filter(
+                    crate::schema::accounts::dsl::ed25519_private_key.is_null()
+                )).set(crate::schema::accounts::dsl::ed25519_private_key.eq(&new_key))
+                    .execute(&c).with_expected_err(|| "Unable to update account")?;
+                crate::schema::accounts::dsl::accounts.find(account.id)
+                    .select(crate::schema::accounts::dsl::ed25519_private_key)
+                    .get_result::<Option<String>>(&c).with_expected_err(|| "Unable to fetch account")
+            })?.unwrap_or(new_key)
+        }
+    };
+
+    openssl::pkey::PKey::private_key_from_pem(key.as_bytes())
+        .map(Some).with_unexpected_err(|| "Unable to parse account Ed25519 key")
+}
+
 pub fn render_account(account: &models::Account) -> TaskResult<activity_streams::Object> {
     let config = super::config();
 
     let pkey = account.private_key.as_ref()
         .map(|k| openssl::pkey::PKey::private_key_from_pem(k.as_bytes()))
         .transpose().with_unexpected_err(|| "Unable to parse account private key")?;
+    let assertion_method = ed25519_private_key(account)?.and_then(|k| activity_streams::Multikey::from_ed25519_key(
+        account.ed25519_key_id(&config.uri), account.actor_id(&config.uri), &k
+    ));
+
+    let moved_to = match account.moved_to {
+        Some(moved_to) => tokio::task::block_in_place(|| -> TaskResult<_> {
+            let c = config.db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+            crate::schema::accounts::dsl::accounts.find(moved_to)
+                .get_result::<models::Account>(&c).optional().with_expected_err(|| "Unable to fetch account")
+        })?.map(|a| a.actor_id(&config.uri)),
+        None => None
+    };
 
     let actor = activity_streams::Actor {
         preferred_username: Some(account.username.clone()),
         inbox: format!("https://{}/as/users/{}/inbox", config.uri, account.id),
         outbox: format!("https://{}/as/users/{}/outbox", config.uri, account.id),
         following: None,
-        followers: None,
+        followers: Some(account.follower_collection(&config.uri)),
         liked: None,
         manually_approves_followers: Some(account.locked),
         endpoints: Some(activity_streams::ReferenceOrObject::Object(Box::new(activity_streams::Endpoints {
@@ -638,6 +1095,16 @@ pub fn render_account(account: &models::Account) -> TaskResult<activity_streams:
             )
         },
         discoverable: account.discoverable,
+        also_known_as: activity_streams::Pluralisable::List(account.also_known_as.clone()),
+        moved_to,
+        featured: account.featured_collection(&config.uri),
+        featured_tags: account.featured_tags_collection(&config.uri),
+        assertion_method: match assertion_method {
+            Some(key) => activity_streams::Pluralisable::Object(
+                activity_streams::ReferenceOrObject::Object(Box::new(key))
+            ),
+            None => activity_streams::Pluralisable::None,
+        },
         common: activity_streams::ObjectCommon {
             id: Some(account.actor_id(&config.uri)),
             name: Some(account.display_name.clone()),
@@ -773,4 +1240,295 @@ pub async fn delete_account(tombstone: activity_streams::Tombstone, account: mod
 #[celery::task]
 pub async fn delete_account_by_id(id: String, account: models::Account) -> TaskResult<()> {
     _delete_account_by_id(id.as_str(), account, Utc::now()).await
-}
\ No newline at end of file
+}
+
+/// Removes the content of a local account that has been tombstoned and tells every known server about it
+#[celery::task]
+pub async fn delete_local_account(account: models::Account) -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+
+    if !account.local {
+        warn!("Account \"{}\" is not local, not deleting", account.id);
+        return Ok(());
+    }
+
+    let now = Utc::now().naive_utc();
+    let mut files = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        c.transaction::<_, diesel::result::Error, _>(|| {
+            let media = crate::schema::media::dsl::media.filter(
+                crate::schema::media::dsl::id.eq_any(
+                    crate::schema::media_attachments::dsl::media_attachments.filter(
+                        crate::schema::media_attachments::dsl::status.eq_any(
+                            crate::schema::statuses::dsl::statuses.filter(
+                                crate::schema::statuses::dsl::account_id.eq(account.id)
+                            ).select(crate::schema::statuses::dsl::id)
+                        )
+                    ).select(crate::schema::media_attachments::dsl::media)
+                )
+            ).get_results::<models::Media>(&c)?;
+            diesel::delete(crate::schema::media::dsl::media.filter(
+                crate::schema::media::dsl::id.eq_any(media.iter().map(|m| m.id).collect::<Vec<_>>())
+            )).execute(&c)?;
+
+            diesel::update(crate::schema::statuses::dsl::statuses.filter(
+                crate::schema::statuses::dsl::account_id.eq(account.id)
+            ).filter(
+                crate::schema::statuses::dsl::deleted_at.is_null()
+            )).set(crate::schema::statuses::dsl::deleted_at.eq(now)).execute(&c)?;
+
+            diesel::delete(crate::schema::likes::dsl::likes.filter(
+                crate::schema::likes::dsl::account.eq(account.id)
+            )).execute(&c)?;
+            diesel::delete(crate::schema::bookmarks::dsl::bookmarks.filter(
+                crate::schema::bookmarks::dsl::account.eq(account.id)
+            )).execute(&c)?;
+            diesel::delete(crate::schema::pins::dsl::pins.filter(
+                crate::schema::pins::dsl::account.eq(account.id)
+            )).execute(&c)?;
+            diesel::delete(crate::schema::following::dsl::following.filter(
+                crate::schema::following::dsl::follower.eq(account.id)
+                    .or(crate::schema::following::dsl::followee.eq(account.id))
+            )).execute(&c)?;
+            diesel::delete(crate::schema::account_fields::dsl::account_fields.filter(
+                crate::schema::account_fields::dsl::account_id.eq(account.id)
+            )).execute(&c)?;
+
+            diesel::update(crate::schema::accounts::dsl::accounts.find(account.id))
+                .set((
+                    crate::schema::accounts::dsl::display_name.eq(""),
+                    crate::schema::accounts::dsl::bio.eq(""),
+                    crate::schema::accounts::dsl::avatar_file.eq(None::<String>),
+                    crate::schema::accounts::dsl::avatar_content_type.eq(None::<String>),
+                    crate::schema::accounts::dsl::header_file.eq(None::<String>),
+                    crate::schema::accounts::dsl::header_content_type.eq(None::<String>),
+                    crate::schema::accounts::dsl::also_known_as.eq(Vec::<String>::new()),
+                    crate::schema::accounts::dsl::updated_at.eq(now),
+                ))
+                .execute(&c)?;
+
+            Ok(media.into_iter().flat_map(|m| vec![m.file, m.preview_file]).flatten().collect::<Vec<_>>())
+        }).with_expected_err(|| "Unable to delete account content")
+    })?;
+    files.extend(account.avatar_file.iter().cloned());
+    files.extend(account.header_file.iter().cloned());
+
+    for file in files {
+        if let Err(e) = std::fs::remove_file(config.media_path.join(&file)) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Unable to delete media file \"{}\": {}", file, e);
+            }
+        }
+    }
+
+    let known_accounts = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        crate::schema::accounts::dsl::accounts.filter(
+            crate::schema::accounts::dsl::local.eq(false)
+        ).filter(
+            crate::schema::accounts::dsl::deleted_at.is_null()
+        ).get_results::<models::Account>(&c).with_expected_err(|| "Unable to get accounts")
+    })?;
+
+    let actor_id = account.actor_id(&config.uri);
+    let activity = activity_streams::Object::Delete(activity_streams::ActivityCommon {
+        common: activity_streams::ObjectCommon {
+            id: Some(format!("https://{}/as/transient/{}", config.uri, uuid::Uuid::new_v4())),
+            to: activity_streams::Pluralisable::Object(
+                activity_streams::ReferenceOrObject::Reference("https://www.w3.org/ns/activitystreams#Public".to_string())
+            ),
+            ..Default::default()
+        },
+        actor: Some(activity_streams::ReferenceOrObject::Reference(actor_id.clone())),
+        object: Some(activity_streams::ReferenceOrObject::Reference(actor_id)),
+        target: None,
+        result: None,
+        origin: None,
+        instrument: None,
+    });
+
+    super::delivery::deliver_dedupe_inboxes(activity, known_accounts, account.clone()).await?;
+
+    info!("Local account \"{}\" deleted", account.id);
+
+    Ok(())
+}
+
+/// Resolves the target of a move, refreshing remote accounts so their aliases are current
+async fn resolve_move_target(target: &str) -> TaskResult<models::Account> {
+    match find_known_account(target)? {
+        Some(a) if a.local => Ok(a),
+        _ => update_account_from_url(target.to_string(), false).await
+    }
+}
+
+fn has_alias(target: &models::Account, account: &models::Account) -> bool {
+    let config = super::config();
+    let actor_id = account.actor_id(&config.uri);
+    target.also_known_as.iter().any(|a| a == &actor_id)
+}
+
+/// Unfollows `old` and follows `new` on behalf of every local follower of `old`
+async fn migrate_followers(old: &models::Account, new: &models::Account) -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+
+    let (followings, followers, already_following) = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        let followings = crate::schema::following::dsl::following.filter(
+            crate::schema::following::dsl::followee.eq(old.id)
+        ).get_results::<models::Following>(&c).with_expected_err(|| "Unable to get followers")?;
+        let followers = crate::schema::accounts::dsl::accounts.filter(
+            crate::schema::accounts::dsl::id.eq_any(followings.iter().map(|f| f.follower).collect::<Vec<_>>())
+        ).filter(
+            crate::schema::accounts::dsl::local.eq(true)
+        ).get_results::<models::Account>(&c).with_expected_err(|| "Unable to get followers")?;
+        let already_following = crate::schema::following::dsl::following.filter(
+            crate::schema::following::dsl::followee.eq(new.id)
+        ).select(crate::schema::following::dsl::follower)
+            .get_results::<uuid::Uuid>(&c).with_expected_err(|| "Unable to get followers")?;
+        Ok((followings, followers, already_following))
+    })?;
+
+    for follower in followers {
+        let following = match followings.iter().find(|f| f.follower == follower.id) {
+            Some(f) => f.clone(),
+            None => continue
+        };
+
+        config.celery.send_task(
+            super::relationships::unfollow_account::new(following.clone(), follower.clone(), old.clone())
+        ).await.with_expected_err(|| "Unable to send task")?;
+
+        if follower.id == new.id || already_following.contains(&follower.id) {
+            continue;
+        }
+
+        let following_id = uuid::Uuid::new_v4();
+        let created = Utc::now();
+        tokio::task::block_in_place(|| -> TaskResult<_> {
+            let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+            diesel::insert_into(crate::schema::following::dsl::following).values(
+                models::NewFollowing {
+                    id: following_id,
+                    follower: follower.id,
+                    followee: new.id,
+                    created_at: created.naive_utc(),
+                    pending: true,
+                    reblogs: following.reblogs,
+                    notify: following.notify,
+                }
+            ).execute(&c).with_expected_err(|| "Unable to insert following")
+        })?;
+
+        config.celery.send_task(
+            super::relationships::follow_account::new(following_id, follower, new.clone(), created)
+        ).await.with_expected_err(|| "Unable to send task")?;
+    }
+
+    Ok(())
+}
+
+#[celery::task]
+pub async fn move_account(account: models::Account, target: String) -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+
+    let target_account = resolve_move_target(&target).await?;
+    if target_account.id == account.id {
+        warn!("Account \"{}\" cannot move to itself", account.id);
+        return Ok(());
+    }
+    if !has_alias(&target_account, &account) {
+        warn!("Account \"{}\" is not an alias of \"{}\", not moving", account.id, target);
+        return Ok(());
+    }
+
+    let account = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        diesel::update(crate::schema::accounts::dsl::accounts.find(account.id))
+            .set((
+                crate::schema::accounts::dsl::moved_to.eq(target_account.id),
+                crate::schema::accounts::dsl::moved_at.eq(Utc::now().naive_utc()),
+            ))
+            .get_result::<models::Account>(&c).with_expected_err(|| "Unable to update account")
+    })?;
+
+    config.celery.send_task(deliver_account_update::new(account.clone()))
+        .await.with_expected_err(|| "Unable to send task")?;
+
+    let followers = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        crate::schema::accounts::dsl::accounts.filter(
+            crate::schema::accounts::dsl::id.eq_any(
+                crate::schema::following::dsl::following.filter(
+                    crate::schema::following::dsl::followee.eq(account.id)
+                ).filter(
+                    crate::schema::following::dsl::pending.eq(false)
+                ).select(crate::schema::following::dsl::follower)
+            )
+        ).get_results::<models::Account>(&c).with_expected_err(|| "Unable to get followers")
+    })?;
+
+    let actor_id = account.actor_id(&config.uri);
+    let activity = activity_streams::Object::Move(activity_streams::ActivityCommon {
+        common: activity_streams::ObjectCommon {
+            id: Some(format!("https://{}/as/transient/{}", config.uri, uuid::Uuid::new_v4())),
+            to: activity_streams::Pluralisable::Object(
+                activity_streams::ReferenceOrObject::Reference("https://www.w3.org/ns/activitystreams#Public".to_string())
+            ),
+            ..Default::default()
+        },
+        actor: Some(activity_streams::ReferenceOrObject::Reference(actor_id.clone())),
+        object: Some(activity_streams::ReferenceOrObject::Reference(actor_id)),
+        target: Some(activity_streams::ReferenceOrObject::Reference(target_account.actor_id(&config.uri))),
+        result: None,
+        origin: None,
+        instrument: None,
+    });
+
+    super::delivery::deliver_dedupe_inboxes(activity, followers, account.clone()).await?;
+
+    migrate_followers(&account, &target_account).await
+}
+
+#[celery::task]
+pub async fn process_move(activity: activity_streams::ActivityCommon, account: models::Account) -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+
+    if activity.object.as_ref().and_then(|o| o.id()) != Some(account.actor_id(&config.uri).as_str()) {
+        warn!("Move activity \"{}\" does not move its actor", activity.id_or_default());
+        return Ok(());
+    }
+    let target = match activity.target.as_ref().and_then(|t| t.id()) {
+        Some(t) => t.to_string(),
+        None => {
+            warn!("Move activity \"{}\" has no target", activity.id_or_default());
+            return Ok(());
+        }
+    };
+
+    let target_account = resolve_move_target(&target).await?;
+    if target_account.id == account.id {
+        warn!("Move activity \"{}\" targets its own actor", activity.id_or_default());
+        return Ok(());
+    }
+    if !has_alias(&target_account, &account) {
+        warn!("Move activity \"{}\" target \"{}\" does not list the actor as an alias", activity.id_or_default(), target);
+        return Ok(());
+    }
+
+    let account = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        diesel::update(crate::schema::accounts::dsl::accounts.find(account.id))
+            .set((
+                crate::schema::accounts::dsl::moved_to.eq(target_account.id),
+                crate::schema::accounts::dsl::moved_at.eq(Utc::now().naive_utc()),
+            ))
+            .get_result::<models::Account>(&c).with_expected_err(|| "Unable to update account")
+    })?;
+
+    migrate_followers(&account, &target_account).await
+}
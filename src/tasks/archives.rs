@@ -0,0 +1,243 @@
+use crate::models;
+use crate::views::activity_streams;
+use celery::prelude::*;
+use chrono::prelude::*;
+use diesel::prelude::*;
+use std::io::Write;
+
+/// How long a finished archive can be downloaded for
+pub const ARCHIVE_VALIDITY_DAYS: i64 = 7;
+/// Minimum time between two archive requests by the same account
+pub const ARCHIVE_INTERVAL_DAYS: i64 = 7;
+
+fn render_collection(id: String, items: Vec<String>) -> activity_streams::Object {
+    activity_streams::Object::OrderedCollection(activity_streams::Collection {
+        common: activity_streams::ObjectCommon {
+            id: Some(id),
+            ..Default::default()
+        },
+        total_items: Some(items.len() as u64),
+        current: None,
+        first: None,
+        last: None,
+        items: Some(items.into_iter().map(activity_streams::ReferenceOrObject::Reference).collect()),
+    })
+}
+
+async fn render_outbox(account: &models::Account) -> TaskResult<activity_streams::Object> {
+    let config = super::config();
+    let db = config.db.clone();
+
+    let (statuses, boosted) = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        let statuses = crate::schema::statuses::dsl::statuses.filter(
+            crate::schema::statuses::dsl::account_id.eq(account.id)
+        ).filter(
+            crate::schema::statuses::dsl::deleted_at.is_null()
+        ).order_by(crate::schema::statuses::dsl::created_at.asc())
+            .get_results::<models::Status>(&c).with_expected_err(|| "Unable to fetch statuses")?;
+        let boosted = crate::schema::statuses::dsl::statuses.filter(
+            crate::schema::statuses::dsl::id.eq_any(statuses.iter().filter_map(|s| s.boost_of_id).collect::<Vec<_>>())
+        ).get_results::<models::Status>(&c).with_expected_err(|| "Unable to fetch statuses")?;
+        Ok((statuses, boosted))
+    })?;
+
+    let mut items = vec![];
+    for status in &statuses {
+        let aud = super::statuses::make_audiences(status, false).await?;
+        if let Some(boost_of_id) = status.boost_of_id {
+            if let Some(boosted_status) = boosted.iter().find(|s| s.id == boost_of_id) {
+                items.push(super::statuses::as_render_boost(status, boosted_status, account, &aud));
+            }
+        } else if status.boost_of_url.is_none() {
+            items.push(super::statuses::as_render_status_activity(status, account, &aud)?);
+        }
+    }
+
+    Ok(activity_streams::Object::OrderedCollection(activity_streams::Collection {
+        common: activity_streams::ObjectCommon {
+            id: Some("outbox.json".to_string()),
+            ..Default::default()
+        },
+        total_items: Some(items.len() as u64),
+        current: None,
+        first: None,
+        last: None,
+        items: Some(items.into_iter().map(|i| activity_streams::ReferenceOrObject::Object(Box::new(
+            activity_streams::ObjectOrLink::Object(i)
+        ))).collect()),
+    }))
+}
+
+fn write_archive(
+    path: &std::path::Path, files: Vec<(String, Vec<u8>)>, media: Vec<String>,
+) -> Result<u64, String> {
+    let config = super::config();
+
+    let file = std::fs::File::create(path).map_err(|e| e.to_string())?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::FileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    for (name, data) in files {
+        zip.start_file(name, options).map_err(|e| e.to_string())?;
+        zip.write_all(&data).map_err(|e| e.to_string())?;
+    }
+    for name in media {
+        let data = match std::fs::read(config.media_path.join(&name)) {
+            Ok(d) => d,
+            Err(e) => {
+                warn!("Unable to read media file \"{}\" for archive: {}", name, e);
+                continue;
+            }
+        };
+        zip.start_file(format!("media/{}", name), options).map_err(|e| e.to_string())?;
+        zip.write_all(&data).map_err(|e| e.to_string())?;
+    }
+
+    let file = zip.finish().map_err(|e| e.to_string())?;
+    Ok(file.metadata().map_err(|e| e.to_string())?.len())
+}
+
+#[celery::task]
+pub async fn build_archive(archive: models::AccountArchive) -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+    let archive_id = archive.id;
+
+    // A failed archive isn't retried, so its state is final and the user is free to request another
+    if let Err(e) = _build_archive(archive).await {
+        warn!("Unable to build archive \"{}\": {:?}", archive_id, e);
+        tokio::task::block_in_place(|| -> TaskResult<_> {
+            let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+            diesel::update(crate::schema::account_archives::dsl::account_archives.find(archive_id))
+                .set(crate::schema::account_archives::dsl::failed_at.eq(Utc::now().naive_utc()))
+                .execute(&c).with_expected_err(|| "Unable to update archive")
+        })?;
+    }
+
+    Ok(())
+}
+
+async fn _build_archive(archive: models::AccountArchive) -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+
+    let (account, likes, bookmarks, media) = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        let account = crate::schema::accounts::dsl::accounts.find(archive.account)
+            .get_result::<models::Account>(&c).with_expected_err(|| "Unable to fetch account")?;
+        let likes = crate::schema::likes::dsl::likes.filter(
+            crate::schema::likes::dsl::account.eq(account.id)
+        ).left_outer_join(
+            crate::schema::statuses::table.on(
+                crate::schema::likes::dsl::status.eq(crate::schema::statuses::dsl::id.nullable())
+            )
+        ).order_by(crate::schema::likes::dsl::iid.asc())
+            .get_results::<(models::Like, Option<models::Status>)>(&c).with_expected_err(|| "Unable to fetch likes")?;
+        let bookmarks = crate::schema::bookmarks::dsl::bookmarks.filter(
+            crate::schema::bookmarks::dsl::account.eq(account.id)
+        ).inner_join(
+            crate::schema::statuses::table.on(
+                crate::schema::bookmarks::dsl::status.eq(crate::schema::statuses::dsl::id)
+            )
+        ).order_by(crate::schema::bookmarks::dsl::iid.asc())
+            .select(crate::schema::statuses::all_columns)
+            .get_results::<models::Status>(&c).with_expected_err(|| "Unable to fetch bookmarks")?;
+        let media = crate::schema::media_attachments::dsl::media_attachments.filter(
+            crate::schema::media_attachments::dsl::status.eq_any(
+                crate::schema::statuses::dsl::statuses.filter(
+                    crate::schema::statuses::dsl::account_id.eq(account.id)
+                ).select(crate::schema::statuses::dsl::id)
+            )
+        ).inner_join(
+            crate::schema::media::table.on(
+                crate::schema::media::dsl::id.eq(crate::schema::media_attachments::dsl::media)
+            )
+        ).select(crate::schema::media::dsl::file)
+            .get_results::<Option<String>>(&c).with_expected_err(|| "Unable to fetch media")?;
+        Ok((account, likes, bookmarks, media))
+    })?;
+
+    let actor = super::accounts::render_account(&account)?;
+    let outbox = render_outbox(&account).await?;
+    let likes = render_collection("likes.json".to_string(), likes.into_iter().filter_map(|(l, s)| {
+        s.map(|s| s.url(&config.uri)).or(l.status_url)
+    }).collect());
+    let bookmarks = render_collection(
+        "bookmarks.json".to_string(), bookmarks.into_iter().map(|s| s.url(&config.uri)).collect()
+    );
+
+    let mut media = media.into_iter().flatten().collect::<Vec<_>>();
+    media.extend(account.avatar_file.iter().cloned());
+    media.extend(account.header_file.iter().cloned());
+    media.sort();
+    media.dedup();
+
+    let files = vec![
+        ("actor.json".to_string(), actor.to_json().into_bytes()),
+        ("outbox.json".to_string(), outbox.to_json().into_bytes()),
+        ("likes.json".to_string(), likes.to_json().into_bytes()),
+        ("bookmarks.json".to_string(), bookmarks.to_json().into_bytes()),
+    ];
+
+    std::fs::create_dir_all(config.archive_path.as_ref())
+        .with_unexpected_err(|| "Unable to create archive directory")?;
+    let file_name = format!("{}.zip", archive.id);
+    let path = config.archive_path.join(&file_name);
+    let size = tokio::task::block_in_place(|| write_archive(&path, files, media))
+        .map_err(|e| TaskError::UnexpectedError(format!("Unable to write archive: {}", e)))?;
+
+    let now = Utc::now().naive_utc();
+    tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        diesel::update(crate::schema::account_archives::dsl::account_archives.find(archive.id))
+            .set((
+                crate::schema::account_archives::dsl::file.eq(file_name),
+                crate::schema::account_archives::dsl::size.eq(size as i64),
+                crate::schema::account_archives::dsl::finished_at.eq(now),
+                crate::schema::account_archives::dsl::expires_at.eq(now + chrono::Duration::days(ARCHIVE_VALIDITY_DAYS)),
+            ))
+            .execute(&c).with_expected_err(|| "Unable to update archive")
+    })?;
+
+    info!("Archive \"{}\" for account \"{}\" built, {} bytes", archive.id, account.id, size);
+
+    Ok(())
+}
+
+#[celery::task]
+pub async fn delete_expired_archives() -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+    let now = Utc::now().naive_utc();
+
+    let archives = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        crate::schema::account_archives::dsl::account_archives.filter(
+            crate::schema::account_archives::dsl::expires_at.lt(now)
+        ).filter(
+            crate::schema::account_archives::dsl::file.is_not_null()
+        ).get_results::<models::AccountArchive>(&c).with_expected_err(|| "Unable to fetch archives")
+    })?;
+
+    for archive in archives {
+        if let Some(file) = &archive.file {
+            if let Err(e) = std::fs::remove_file(config.archive_path.join(file)) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    warn!("Unable to delete archive file \"{}\": {}", file, e);
+                    continue;
+                }
+            }
+        }
+
+        tokio::task::block_in_place(|| -> TaskResult<_> {
+            let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+            diesel::update(crate::schema::account_archives::dsl::account_archives.find(archive.id))
+                .set(crate::schema::account_archives::dsl::file.eq(None::<String>))
+                .execute(&c).with_expected_err(|| "Unable to update archive")
+        })?;
+    }
+
+    Ok(())
+}
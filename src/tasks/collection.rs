@@ -8,9 +8,15 @@ pub struct CollectionStream {
     next_page: Option<activity_streams::ReferenceOrObject<activity_streams::CollectionPageOrLink>>,
     resolve_fut: Option<futures::future::BoxFuture<'static, Option<activity_streams::CollectionPageOrLink>>>,
     fetch_link_fut: Option<futures::future::BoxFuture<'static, Option<activity_streams::CollectionPage>>>,
+    pages_fetched: usize,
 }
 
 impl CollectionStream {
+    /// How many pages have been fetched from the remote server so far
+    pub fn pages_fetched(&self) -> usize {
+        self.pages_fetched
+    }
+
     fn poll_fetch_link(
         mut self: std::pin::Pin<&mut Self>, cx: &mut futures::task::Context<'_>
     ) -> futures::task::Poll<Option<activity_streams::ReferenceOrObject<activity_streams::ObjectOrLink>>> {
@@ -45,6 +51,7 @@ impl CollectionStream {
                                     println!("Fetching {}", l);
                                     let fut = fetch_object(l);
                                     self.fetch_link_fut = Some(Box::pin(fut));
+                                    self.pages_fetched += 1;
                                     return self.poll_fetch_link(cx);
                                 } else {
                                     None
@@ -90,8 +97,11 @@ impl futures::stream::Stream for CollectionStream {
             } else if self.resolve_fut.is_some() {
                 self.poll_resolve_object(cx)
             } else {
-                if let Some(obj) = &self.next_page {
-                    self.resolve_fut = Some(Box::pin(resolve_object(obj.clone())));
+                if let Some(obj) = self.next_page.clone() {
+                    if let activity_streams::ReferenceOrObject::Reference(_) = &obj {
+                        self.pages_fetched += 1;
+                    }
+                    self.resolve_fut = Some(Box::pin(resolve_object(obj)));
                     self.poll_resolve_object(cx)
                 } else {
                     futures::task::Poll::Ready(None)
@@ -119,6 +129,7 @@ pub fn fetch_entire_collection(
                     collection: c,
                     resolve_fut: None,
                     fetch_link_fut: None,
+                    pages_fetched: 0,
                 })
             } else if let Some(first) = &c.first {
                 Ok(CollectionStream {
@@ -127,6 +138,7 @@ pub fn fetch_entire_collection(
                     collection: c,
                     resolve_fut: None,
                     fetch_link_fut: None,
+                    pages_fetched: 0,
                 })
             } else {
                 Ok(CollectionStream {
@@ -135,6 +147,7 @@ pub fn fetch_entire_collection(
                     collection: c,
                     resolve_fut: None,
                     fetch_link_fut: None,
+                    pages_fetched: 0,
                 })
             }
         }
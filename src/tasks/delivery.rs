@@ -1,39 +1,133 @@
 use crate::models;
 use crate::views::activity_streams;
 use celery::prelude::*;
+use diesel::prelude::*;
 use itertools::Itertools;
+use crate::views::activity_streams::ObjectID;
+use chrono::prelude::*;
 
+/// How long deliveries to a domain have to keep failing before it is considered unavailable
+pub const UNAVAILABLE_AFTER_DAYS: i64 = 7;
+
+
+fn is_account_suspended(account: &models::Account) -> TaskResult<bool> {
+    let db = super::config().db;
+
+    Ok(tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        crate::schema::accounts::dsl::accounts.find(account.id)
+            .select(crate::schema::accounts::dsl::suspended_at)
+            .get_result::<Option<chrono::NaiveDateTime>>(&c).with_expected_err(|| "Unable to get account")
+    })?.is_some())
+}
+
+fn account_private_key(account: &models::Account) -> TaskResult<Option<openssl::pkey::PKey<openssl::pkey::Private>>> {
+    account.private_key.as_ref().map(|k| match openssl::pkey::PKey::private_key_from_pem(k.as_bytes()) {
+        Ok(pkey) => Ok(pkey),
+        Err(_) => Err(TaskError::UnexpectedError("Invalid private key".to_string())),
+    }).transpose()
+}
 
 #[celery::task]
 pub async fn deliver_object(object: activity_streams::Object, inbox: String, account: models::Account) -> TaskResult<()> {
     let config = super::config();
+
+    if is_account_suspended(&account)? {
+        info!("Not delivering \"{}\" to \"{}\" from suspended account \"{}\"", object.id_or_default(), inbox, account.id);
+        return Ok(());
+    }
+
+    let pkey = account_private_key(&account)?;
+    let ed25519_key = super::accounts::ed25519_private_key(&account)?;
+    _deliver_object(object, inbox, pkey.as_deref().map(|k| super::SigningKeys {
+        rsa: (k, account.key_id(&config.uri)),
+        ed25519: ed25519_key.as_deref().map(|e| (e, account.ed25519_key_id(&config.uri))),
+    })).await
+}
+
+/// Passes on an activity received from elsewhere exactly as it was sent, signing the request as the forwarding account
+#[celery::task]
+pub async fn forward_activity(activity_id: String, body: String, inbox: String, account: models::Account) -> TaskResult<()> {
+    let config = super::config();
+
+    if is_account_suspended(&account)? {
+        info!("Not forwarding \"{}\" to \"{}\" from suspended account \"{}\"", activity_id, inbox, account.id);
+        return Ok(());
+    }
+
+    let pkey = account_private_key(&account)?;
+    let ed25519_key = super::accounts::ed25519_private_key(&account)?;
+    _deliver_body(&activity_id, body, inbox, pkey.as_deref().map(|k| super::SigningKeys {
+        rsa: (k, account.key_id(&config.uri)),
+        ed25519: ed25519_key.as_deref().map(|e| (e, account.ed25519_key_id(&config.uri))),
+    })).await
+}
+
+#[celery::task]
+pub async fn deliver_system_object(object: activity_streams::Object, inbox: String) -> TaskResult<()> {
+    let config = super::config();
+    _deliver_object(object, inbox, Some(super::SigningKeys {
+        rsa: (&config.as_key, format!("https://{}/as/system#key", config.uri)),
+        ed25519: None,
+    })).await
+}
+
+async fn _deliver_object(
+    object: activity_streams::Object, inbox: String, key: Option<super::SigningKeys<'_>>,
+) -> TaskResult<()> {
+    _deliver_body(object.id_or_default(), object.to_json(), inbox, key).await
+}
+
+async fn _deliver_body(
+    id: &str, body: String, inbox: String, key: Option<super::SigningKeys<'_>>,
+) -> TaskResult<()> {
     let url = reqwest::Url::parse(&inbox).with_unexpected_err(|| "Invalid inbox URL")?;
     let host = url.host_str().map(|h| h.to_string()).ok_or(TaskError::UnexpectedError("Invalid inbox URL".to_string()))?;
 
-    let body = object.to_json();
+    if is_domain_unavailable(&host)? {
+        info!("Not delivering \"{}\" to \"{}\" as the domain is unavailable", id, inbox);
+        crate::metrics::DELIVERIES.inc(&["skipped", &crate::metrics::domain_label(&inbox)]);
+        return Ok(());
+    }
+
     let body_hash = openssl::hash::hash(openssl::hash::MessageDigest::sha256(), body.as_bytes())
         .with_unexpected_err(|| "Unable to hash body")?;
     let date = chrono::Utc::now().naive_utc().format("%a, %d %h %Y %H:%M:%S GMT").to_string();
-    let mut req = crate::AS_CLIENT.post(url)
+    let req = crate::AS_CLIENT.post(url)
         .body(body)
-        .header("Host", host)
+        .header("Host", host.clone())
         .header("Date", date)
         .header("Digest", format!("SHA-256={}", base64::encode(body_hash)))
+        .header("Content-Digest", format!("sha-256=:{}:", base64::encode(body_hash)))
         .header("Content-Type", "application/ld+json; profile=\"https://www.w3.org/ns/activitystreams\"")
         .build().with_unexpected_err(|| "Unable to build request")?;
 
-    if let Some(pkey) = account.private_key.as_ref().map(|k| match openssl::pkey::PKey::private_key_from_pem(k.as_bytes()) {
-        Ok(pkey) => Ok(pkey),
-        Err(_) => Err(TaskError::UnexpectedError("Invalid private key".to_string())),
-    }).transpose()? {
-        super::sign_request(&mut req, &pkey,  account.key_id(&config.uri))
-            .map_err(|e| TaskError::UnexpectedError(e))?;
+    let r = match key {
+        Some(keys) => super::execute_signed(req, keys).await,
+        None => crate::AS_CLIENT.execute(req).await.map_err(super::SignedRequestError::Request),
+    };
+    let r = match r {
+        Ok(r) => r,
+        Err(super::SignedRequestError::Signing(e)) => return Err(TaskError::UnexpectedError(e)),
+        Err(super::SignedRequestError::Request(e)) => {
+            crate::metrics::DELIVERIES.inc(&["error", &crate::metrics::domain_label(&inbox)]);
+            record_delivery_failure(&host)?;
+            return Err(e).with_expected_err(|| "Unable to send request");
+        }
+    };
+
+    let status = r.status();
+    crate::metrics::DELIVERIES.inc(&[status.as_str(), &crate::metrics::domain_label(&inbox)]);
+    if status.is_server_error() || status == reqwest::StatusCode::REQUEST_TIMEOUT ||
+        status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        record_delivery_failure(&host)?;
+        let text = r.text().await.with_expected_err(|| "Unable to read response")?;
+        return Err(TaskError::ExpectedError(format!("Delivery failed ({}): {}", status, text)));
     }
 
-    let r = crate::AS_CLIENT.execute(req).await
-        .with_expected_err(|| "Unable to send request")?;
+    // Any other response means the server is up, even if it didn't like what we sent
+    record_delivery_success(&host)?;
 
-    let status = r.status();
     if !status.is_success() {
         let text = r.text().await.with_expected_err(|| "Unable to read response")?;
         return Err(TaskError::UnexpectedError(format!("Delivery failed ({}): {}", status, text)));
@@ -42,13 +136,232 @@ pub async fn deliver_object(object: activity_streams::Object, inbox: String, acc
     Ok(())
 }
 
+pub fn is_domain_unavailable(domain: &str) -> TaskResult<bool> {
+    let db = super::config().db;
+
+    tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        Ok(crate::schema::delivery_domains::dsl::delivery_domains.filter(
+            crate::schema::delivery_domains::dsl::domain.eq(domain)
+        ).filter(
+            crate::schema::delivery_domains::dsl::unavailable_since.is_not_null()
+        ).count().get_result::<i64>(&c).with_expected_err(|| "Unable to fetch delivery domain")? > 0)
+    })
+}
+
+fn record_delivery_failure(domain: &str) -> TaskResult<()> {
+    let db = super::config().db;
+    let now = Utc::now().naive_utc();
+
+    let became_unavailable = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        c.transaction::<_, diesel::result::Error, _>(|| {
+            let existing = crate::schema::delivery_domains::dsl::delivery_domains
+                .find(domain)
+                .for_update()
+                .get_result::<models::DeliveryDomain>(&c).optional()?;
+
+            let existing = match existing {
+                Some(d) => d,
+                None => {
+                    diesel::insert_into(crate::schema::delivery_domains::table)
+                        .values(models::DeliveryDomain {
+                            domain: domain.to_string(),
+                            consecutive_failures: 1,
+                            first_failure_at: Some(now),
+                            last_failure_at: Some(now),
+                            last_success_at: None,
+                            unavailable_since: None,
+                            signature_format: None,
+                        })
+                        .on_conflict_do_nothing()
+                        .execute(&c)?;
+                    return Ok(false);
+                }
+            };
+
+            let first_failure_at = existing.first_failure_at.unwrap_or(now);
+            let unavailable_since = match existing.unavailable_since {
+                Some(u) => Some(u),
+                None if now - first_failure_at >= chrono::Duration::days(UNAVAILABLE_AFTER_DAYS) => Some(now),
+                None => None
+            };
+
+            diesel::update(crate::schema::delivery_domains::dsl::delivery_domains.find(domain))
+                .set((
+                    crate::schema::delivery_domains::dsl::consecutive_failures.eq(existing.consecutive_failures + 1),
+                    crate::schema::delivery_domains::dsl::first_failure_at.eq(first_failure_at),
+                    crate::schema::delivery_domains::dsl::last_failure_at.eq(now),
+                    crate::schema::delivery_domains::dsl::unavailable_since.eq(unavailable_since),
+                ))
+                .execute(&c)?;
+
+            Ok(existing.unavailable_since.is_none() && unavailable_since.is_some())
+        }).with_expected_err(|| "Unable to record delivery failure")
+    })?;
+
+    if became_unavailable {
+        warn!("Marking domain \"{}\" as unavailable after {} days of failed deliveries", domain, UNAVAILABLE_AFTER_DAYS);
+    }
+
+    Ok(())
+}
+
+/// Resets the failure count of a domain, called on successful delivery or any other authenticated contact
+pub fn record_delivery_success(domain: &str) -> TaskResult<()> {
+    let db = super::config().db;
+    let now = Utc::now().naive_utc();
+
+    let was_unavailable = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        c.transaction::<_, diesel::result::Error, _>(|| {
+            let was_unavailable = crate::schema::delivery_domains::dsl::delivery_domains
+                .find(domain)
+                .select(crate::schema::delivery_domains::dsl::unavailable_since)
+                .get_result::<Option<NaiveDateTime>>(&c).optional()?
+                .flatten().is_some();
+
+            diesel::insert_into(crate::schema::delivery_domains::table)
+                .values(models::DeliveryDomain {
+                    domain: domain.to_string(),
+                    consecutive_failures: 0,
+                    first_failure_at: None,
+                    last_failure_at: None,
+                    last_success_at: Some(now),
+                    unavailable_since: None,
+                    signature_format: None,
+                })
+                .on_conflict(crate::schema::delivery_domains::dsl::domain)
+                .do_update()
+                .set((
+                    crate::schema::delivery_domains::dsl::consecutive_failures.eq(0),
+                    crate::schema::delivery_domains::dsl::first_failure_at.eq(None::<NaiveDateTime>),
+                    crate::schema::delivery_domains::dsl::last_success_at.eq(now),
+                    crate::schema::delivery_domains::dsl::unavailable_since.eq(None::<NaiveDateTime>),
+                ))
+                .execute(&c)?;
+
+            Ok(was_unavailable)
+        }).with_expected_err(|| "Unable to record delivery success")
+    })?;
+
+    if was_unavailable {
+        info!("Domain \"{}\" is available again", domain);
+    }
+
+    Ok(())
+}
+
+/// Returns the HTTP Signature format a domain is known to accept
+pub fn signature_format(domain: &str) -> TaskResult<Option<super::SignatureFormat>> {
+    let db = super::config().db;
+
+    let format = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        crate::schema::delivery_domains::dsl::delivery_domains
+            .find(domain)
+            .select(crate::schema::delivery_domains::dsl::signature_format)
+            .get_result::<Option<String>>(&c).optional().with_expected_err(|| "Unable to fetch delivery domain")
+    })?;
+
+    Ok(format.flatten().and_then(|f| super::SignatureFormat::from_name(&f)))
+}
+
+pub fn remember_signature_format(domain: &str, format: super::SignatureFormat) -> TaskResult<()> {
+    let db = super::config().db;
+
+    tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        diesel::insert_into(crate::schema::delivery_domains::table)
+            .values(models::DeliveryDomain {
+                domain: domain.to_string(),
+                consecutive_failures: 0,
+                first_failure_at: None,
+                last_failure_at: None,
+                last_success_at: None,
+                unavailable_since: None,
+                signature_format: Some(format.as_str().to_string()),
+            })
+            .on_conflict(crate::schema::delivery_domains::dsl::domain)
+            .do_update()
+            .set(crate::schema::delivery_domains::dsl::signature_format.eq(format.as_str()))
+            .execute(&c).with_expected_err(|| "Unable to update delivery domain")
+    })?;
+
+    Ok(())
+}
+
+pub fn list_delivery_domains(c: &diesel::PgConnection) -> QueryResult<Vec<models::DeliveryDomain>> {
+    crate::schema::delivery_domains::dsl::delivery_domains.filter(
+        crate::schema::delivery_domains::dsl::consecutive_failures.gt(0)
+    ).order_by(crate::schema::delivery_domains::dsl::domain.asc())
+        .get_results(c)
+}
+
+pub fn reset_delivery_domain(c: &diesel::PgConnection, domain: &str) -> QueryResult<bool> {
+    Ok(diesel::delete(crate::schema::delivery_domains::dsl::delivery_domains.find(domain))
+        .execute(c)? > 0)
+}
+
+/// Checks whether unavailable domains have come back, so deliveries to them can resume
+#[celery::task]
+pub async fn probe_unavailable_domains() -> TaskResult<()> {
+    let db = super::config().db;
+
+    let domains = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        crate::schema::delivery_domains::dsl::delivery_domains.filter(
+            crate::schema::delivery_domains::dsl::unavailable_since.is_not_null()
+        ).select(crate::schema::delivery_domains::dsl::domain)
+            .get_results::<String>(&c).with_expected_err(|| "Unable to fetch delivery domains")
+    })?;
+
+    for domain in domains {
+        let available = match crate::AS_CLIENT.get(format!("https://{}/.well-known/nodeinfo", domain)).send().await {
+            Ok(r) => !r.status().is_server_error(),
+            Err(_) => false
+        };
+        if available {
+            record_delivery_success(&domain)?;
+        }
+    }
+
+    Ok(())
+}
+
 pub async fn deliver_dedupe_inboxes(
     object: activity_streams::Object, audience: Vec<models::Account>, account: models::Account
 ) -> TaskResult<()> {
     let config = super::config();
+
+    for inbox in dedupe_inboxes(audience, object.id_or_default())? {
+        config.celery.send_task(
+            deliver_object::new(object.clone(), inbox, account.clone())
+        ).await.with_expected_err(|| "Unable to submit delivery task")?;
+    }
+
+    Ok(())
+}
+
+/// Forwards a received activity's original body to an audience, per ActivityPub's inbox forwarding
+pub async fn forward_dedupe_inboxes(
+    activity_id: String, body: String, audience: Vec<models::Account>, account: models::Account
+) -> TaskResult<()> {
+    let config = super::config();
+
+    for inbox in dedupe_inboxes(audience, &activity_id)? {
+        config.celery.send_task(
+            forward_activity::new(activity_id.clone(), body.clone(), inbox, account.clone())
+        ).await.with_expected_err(|| "Unable to submit delivery task")?;
+    }
+
+    Ok(())
+}
+
+fn dedupe_inboxes(audience: Vec<models::Account>, id: &str) -> TaskResult<Vec<String>> {
     let mut inboxes = vec![];
     for a in audience {
-        if !a.local {
+        if !a.local && a.suspended_at.is_none() {
             if let Some(inbox) = a.shared_inbox_url {
                 inboxes.push(inbox);
             } else if let Some(inbox) = a.inbox_url {
@@ -57,12 +370,17 @@ pub async fn deliver_dedupe_inboxes(
         }
     }
 
-    let inboxes: Vec<_> = inboxes.into_iter().unique().collect();
-    for inbox in inboxes {
-        config.celery.send_task(
-            deliver_object::new(object.clone(), inbox, account.clone())
-        ).await.with_expected_err(|| "Unable to submit delivery task")?;
+    let mut out = vec![];
+    for inbox in inboxes.into_iter().unique() {
+        let domain = reqwest::Url::parse(&inbox).ok().and_then(|u| u.host_str().map(|h| h.to_string()));
+        if let Some(domain) = domain {
+            if is_domain_unavailable(&domain)? {
+                info!("Skipping delivery of \"{}\" to unavailable domain \"{}\"", id, domain);
+                continue;
+            }
+        }
+        out.push(inbox);
     }
 
-    Ok(())
+    Ok(out)
 }
\ No newline at end of file
@@ -0,0 +1,196 @@
+use crate::models;
+use celery::prelude::*;
+use chrono::prelude::*;
+use diesel::prelude::*;
+
+/// Hands a new status to every local group it is addressed to or mentions
+pub async fn dispatch_to_groups(status: &models::Status) -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+
+    if status.boost_of_id.is_some() || status.boost_of_url.is_some() {
+        return Ok(());
+    }
+
+    let groups = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        crate::schema::accounts::dsl::accounts.filter(
+            crate::schema::accounts::dsl::id.eq_any(
+                crate::schema::status_mentions::dsl::status_mentions.filter(
+                    crate::schema::status_mentions::dsl::status.eq(status.id)
+                ).select(crate::schema::status_mentions::dsl::account)
+            ).or(crate::schema::accounts::dsl::id.nullable().eq_any(
+                crate::schema::status_audiences::dsl::status_audiences.filter(
+                    crate::schema::status_audiences::dsl::status_id.eq(status.id)
+                ).select(crate::schema::status_audiences::dsl::account)
+            ))
+        ).filter(
+            crate::schema::accounts::dsl::local.eq(true)
+        ).filter(
+            crate::schema::accounts::dsl::group.eq(true)
+        ).filter(
+            crate::schema::accounts::dsl::deleted_at.is_null()
+        ).filter(
+            crate::schema::accounts::dsl::suspended_at.is_null()
+        ).filter(
+            crate::schema::accounts::dsl::id.ne(status.account_id)
+        ).get_results::<models::Account>(&c).with_expected_err(|| "Unable to fetch groups")
+    })?;
+
+    for group in groups {
+        config.celery.send_task(process_group_post::new(status.clone(), group))
+            .await.with_expected_err(|| "Unable to send task")?;
+    }
+
+    Ok(())
+}
+
+#[celery::task]
+pub async fn process_group_post(status: models::Status, group: models::Account) -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+
+    if !status.visible || status.deleted_at.is_some() {
+        info!("Status \"{}\" is not public, not posting to group \"{}\"", status.id, group.id);
+        return Ok(());
+    }
+
+    let post = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        let is_member = crate::schema::following::dsl::following.filter(
+            crate::schema::following::dsl::follower.eq(status.account_id)
+        ).filter(
+            crate::schema::following::dsl::followee.eq(group.id)
+        ).filter(
+            crate::schema::following::dsl::pending.eq(false)
+        ).count().get_result::<i64>(&c).with_expected_err(|| "Unable to fetch membership")? > 0;
+        if !is_member {
+            return Ok(None);
+        }
+
+        diesel::insert_into(crate::schema::group_posts::table)
+            .values(models::NewGroupPost {
+                id: uuid::Uuid::new_v4(),
+                group_id: group.id,
+                status: status.id,
+                created_at: Utc::now().naive_utc(),
+            })
+            .on_conflict_do_nothing()
+            .get_result::<models::GroupPost>(&c).optional().with_expected_err(|| "Unable to insert group post")
+    })?;
+
+    let post = match post {
+        Some(p) => p,
+        None => {
+            info!("Status \"{}\" is not from a member of group \"{}\" or was already posted", status.id, group.id);
+            return Ok(());
+        }
+    };
+
+    if group.group_review_posts {
+        info!("Status \"{}\" is awaiting review in group \"{}\"", status.id, group.id);
+        return Ok(());
+    }
+
+    let post = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        diesel::update(crate::schema::group_posts::dsl::group_posts.find(post.id))
+            .set((
+                crate::schema::group_posts::dsl::approved.eq(true),
+                crate::schema::group_posts::dsl::reviewed_at.eq(Utc::now().naive_utc()),
+            ))
+            .get_result::<models::GroupPost>(&c).with_expected_err(|| "Unable to update group post")
+    })?;
+
+    _announce_group_post(post, group, status).await
+}
+
+/// Boosts an approved post from the group to all its members
+#[celery::task]
+pub async fn announce_group_post(post: models::GroupPost) -> TaskResult<()> {
+    let db = super::config().db;
+
+    let (group, status) = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        let group = crate::schema::accounts::dsl::accounts.find(post.group_id)
+            .get_result::<models::Account>(&c).with_expected_err(|| "Unable to fetch group")?;
+        let status = crate::schema::statuses::dsl::statuses.find(post.status)
+            .get_result::<models::Status>(&c).with_expected_err(|| "Unable to fetch status")?;
+        Ok((group, status))
+    })?;
+
+    if status.deleted_at.is_some() {
+        info!("Status \"{}\" has been deleted, not announcing to group \"{}\"", status.id, group.id);
+        return Ok(());
+    }
+
+    _announce_group_post(post, group, status).await
+}
+
+async fn _announce_group_post(post: models::GroupPost, group: models::Account, status: models::Status) -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+
+    if post.announce.is_some() {
+        return Ok(());
+    }
+
+    let now = Utc::now().naive_utc();
+    let new_status = models::NewStatus {
+        id: uuid::Uuid::new_v4(),
+        url: "".to_string(),
+        uri: None,
+        text: "".to_string(),
+        created_at: now,
+        updated_at: now,
+        in_reply_to_id: None,
+        in_reply_to_url: None,
+        boost_of_url: None,
+        boost_of_id: Some(status.id),
+        sensitive: false,
+        spoiler_text: "".to_string(),
+        language: None,
+        local: true,
+        account_id: group.id,
+        deleted_at: None,
+        edited_at: None,
+        public: true,
+        visible: true,
+        text_source: None,
+        spoiler_text_source: None,
+    };
+    let audiences = vec![models::StatusAudience {
+        id: uuid::Uuid::new_v4(),
+        status_id: new_status.id,
+        mention: false,
+        account: None,
+        account_followers: Some(group.id)
+    }, models::StatusAudience {
+        id: uuid::Uuid::new_v4(),
+        status_id: new_status.id,
+        mention: false,
+        account: Some(status.account_id),
+        account_followers: None,
+    }];
+
+    let announce = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        c.transaction::<_, diesel::result::Error, _>(|| {
+            let s = diesel::insert_into(crate::schema::statuses::dsl::statuses)
+                .values(new_status)
+                .get_result::<models::Status>(&c)?;
+            diesel::insert_into(crate::schema::status_audiences::dsl::status_audiences)
+                .values(audiences)
+                .execute(&c)?;
+            diesel::update(crate::schema::group_posts::dsl::group_posts.find(post.id))
+                .set(crate::schema::group_posts::dsl::announce.eq(s.id))
+                .execute(&c)?;
+            Ok(s)
+        }).with_expected_err(|| "Unable to insert group announce")
+    })?;
+
+    config.celery.send_task(super::statuses::deliver_boost::new(announce, status, group))
+        .await.with_expected_err(|| "Unable to send task")?;
+
+    Ok(())
+}
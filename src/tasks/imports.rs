@@ -0,0 +1,267 @@
+use crate::models;
+use crate::views::activity_streams;
+use celery::prelude::*;
+use chrono::prelude::*;
+use diesel::prelude::*;
+
+/// Import types that can be processed, the others have no storage in Tafarn yet
+pub const IMPORT_TYPES: [&str; 2] = ["following", "bookmarks"];
+
+fn record_progress(import: &models::Import, success: bool) -> TaskResult<()> {
+    let db = super::config().db;
+
+    tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        let upd = diesel::update(crate::schema::imports::dsl::imports.find(import.id));
+        if success {
+            upd.set(crate::schema::imports::dsl::imported_items.eq(crate::schema::imports::dsl::imported_items + 1))
+                .execute(&c).with_expected_err(|| "Unable to update import")?;
+        } else {
+            upd.set(crate::schema::imports::dsl::failed_items.eq(crate::schema::imports::dsl::failed_items + 1))
+                .execute(&c).with_expected_err(|| "Unable to update import")?;
+        }
+        Ok(())
+    })
+}
+
+fn parse_bool(value: Option<&String>, default: bool) -> bool {
+    match value.map(|v| v.trim().to_lowercase()).as_deref() {
+        Some("true") | Some("1") => true,
+        Some("false") | Some("0") => false,
+        _ => default
+    }
+}
+
+async fn import_following(
+    import: &models::Import, account: &models::Account, rows: Vec<Vec<String>>,
+) -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+
+    let mut followed = vec![];
+    // Usernames of rows that couldn't be resolved, whose accounts are kept when overwriting
+    let mut unresolved = vec![];
+    for row in rows {
+        let acct = match row.first() {
+            Some(a) => a.trim(),
+            None => continue
+        };
+        let reblogs = parse_bool(row.get(1), true);
+        let notify = parse_bool(row.get(2), false);
+
+        let followee = match super::accounts::find_account_by_acct(acct).await {
+            Ok(Some(a)) if a.id != account.id => a,
+            Ok(_) => {
+                warn!("Import \"{}\" unable to resolve account \"{}\"", import.id, acct);
+                unresolved.extend(crate::WEBFINGER_RE.captures(acct).map(|c| c["user"].to_lowercase()));
+                record_progress(import, false)?;
+                continue;
+            }
+            Err(e) => {
+                warn!("Import \"{}\" unable to resolve account \"{}\": {}", import.id, acct, e);
+                unresolved.extend(crate::WEBFINGER_RE.captures(acct).map(|c| c["user"].to_lowercase()));
+                record_progress(import, false)?;
+                continue;
+            }
+        };
+        followed.push(followee.id);
+
+        let existing = tokio::task::block_in_place(|| -> TaskResult<_> {
+            let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+            crate::schema::following::dsl::following.filter(
+                crate::schema::following::dsl::follower.eq(account.id)
+            ).filter(
+                crate::schema::following::dsl::followee.eq(followee.id)
+            ).get_result::<models::Following>(&c).optional().with_expected_err(|| "Unable to fetch following")
+        })?;
+
+        if let Some(existing) = existing {
+            tokio::task::block_in_place(|| -> TaskResult<_> {
+                let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+                diesel::update(crate::schema::following::dsl::following.find(existing.id))
+                    .set((
+                        crate::schema::following::dsl::reblogs.eq(reblogs),
+                        crate::schema::following::dsl::notify.eq(notify),
+                    ))
+                    .execute(&c).with_expected_err(|| "Unable to update following")
+            })?;
+        } else {
+            let following_id = uuid::Uuid::new_v4();
+            let created = Utc::now();
+            tokio::task::block_in_place(|| -> TaskResult<_> {
+                let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+                diesel::insert_into(crate::schema::following::dsl::following).values(
+                    models::NewFollowing {
+                        id: following_id,
+                        follower: account.id,
+                        followee: followee.id,
+                        created_at: created.naive_utc(),
+                        pending: true,
+                        reblogs,
+                        notify,
+                    }
+                ).execute(&c).with_expected_err(|| "Unable to insert following")
+            })?;
+
+            config.celery.send_task(
+                super::relationships::follow_account::new(following_id, account.clone(), followee, created)
+            ).await.with_expected_err(|| "Unable to send task")?;
+        }
+
+        record_progress(import, true)?;
+    }
+
+    if import.overwrite {
+        let stale = tokio::task::block_in_place(|| -> TaskResult<_> {
+            let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+            let followings = crate::schema::following::dsl::following.filter(
+                crate::schema::following::dsl::follower.eq(account.id)
+            ).filter(
+                crate::schema::following::dsl::followee.ne_all(&followed)
+            ).get_results::<models::Following>(&c).with_expected_err(|| "Unable to fetch following")?;
+            let followees = crate::schema::accounts::dsl::accounts.filter(
+                crate::schema::accounts::dsl::id.eq_any(followings.iter().map(|f| f.followee).collect::<Vec<_>>())
+            ).get_results::<models::Account>(&c).with_expected_err(|| "Unable to fetch accounts")?;
+            Ok(followings.into_iter().filter_map(|f| {
+                let followee = followees.iter().find(|a| a.id == f.followee)?.clone();
+                Some((f, followee))
+            }).filter(|(_, followee)| {
+                !unresolved.contains(&followee.username.to_lowercase())
+            }).collect::<Vec<_>>())
+        })?;
+
+        for (following, followee) in stale {
+            config.celery.send_task(
+                super::relationships::unfollow_account::new(following, account.clone(), followee)
+            ).await.with_expected_err(|| "Unable to send task")?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn import_bookmarks(
+    import: &models::Import, account: &models::Account, rows: Vec<Vec<String>>,
+) -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+
+    let mut bookmarked = vec![];
+    // URLs of rows that couldn't be resolved, whose bookmarks are kept when overwriting
+    let mut unresolved = vec![];
+    for row in rows {
+        let url = match row.first() {
+            Some(u) => u.trim().to_string(),
+            None => continue
+        };
+
+        let status = match super::statuses::get_status(
+            activity_streams::ReferenceOrObject::Reference(url.clone())
+        ).await {
+            Ok(s) if s.deleted_at.is_none() => s,
+            Ok(_) => {
+                warn!("Import \"{}\" status \"{}\" has been deleted", import.id, url);
+                record_progress(import, false)?;
+                continue;
+            }
+            Err(e) => {
+                warn!("Import \"{}\" unable to resolve status \"{}\": {}", import.id, url, e);
+                unresolved.push(url);
+                record_progress(import, false)?;
+                continue;
+            }
+        };
+        bookmarked.push(status.id);
+
+        tokio::task::block_in_place(|| -> TaskResult<_> {
+            let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+            let exists = crate::schema::bookmarks::dsl::bookmarks.filter(
+                crate::schema::bookmarks::dsl::status.eq(status.id)
+            ).filter(
+                crate::schema::bookmarks::dsl::account.eq(account.id)
+            ).count().get_result::<i64>(&c).with_expected_err(|| "Unable to fetch bookmark")? > 0;
+            if !exists {
+                diesel::insert_into(crate::schema::bookmarks::dsl::bookmarks)
+                    .values(models::NewBookmark {
+                        id: uuid::Uuid::new_v4(),
+                        status: status.id,
+                        account: account.id,
+                    })
+                    .execute(&c).with_expected_err(|| "Unable to insert bookmark")?;
+            }
+            Ok(())
+        })?;
+
+        record_progress(import, true)?;
+    }
+
+    if import.overwrite {
+        tokio::task::block_in_place(|| -> TaskResult<_> {
+            let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+            let stale = crate::schema::bookmarks::dsl::bookmarks.filter(
+                crate::schema::bookmarks::dsl::account.eq(account.id)
+            ).filter(
+                crate::schema::bookmarks::dsl::status.ne_all(&bookmarked)
+            ).inner_join(
+                crate::schema::statuses::table.on(
+                    crate::schema::bookmarks::dsl::status.eq(crate::schema::statuses::dsl::id)
+                )
+            ).get_results::<(models::Bookmark, models::Status)>(&c).with_expected_err(|| "Unable to fetch bookmarks")?
+                .into_iter()
+                .filter(|(_, s)| {
+                    !unresolved.contains(&s.url(&config.uri)) &&
+                        !s.uri.as_ref().map(|u| unresolved.contains(u)).unwrap_or(false)
+                })
+                .map(|(b, _)| b.id)
+                .collect::<Vec<_>>();
+            diesel::delete(crate::schema::bookmarks::dsl::bookmarks.filter(
+                crate::schema::bookmarks::dsl::id.eq_any(stale)
+            )).execute(&c).with_expected_err(|| "Unable to delete bookmarks")
+        })?;
+    }
+
+    Ok(())
+}
+
+#[celery::task]
+pub async fn process_import(import: models::Import, rows: Vec<Vec<String>>) -> TaskResult<()> {
+    let db = super::config().db;
+
+    let account = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        crate::schema::accounts::dsl::accounts.find(import.account)
+            .get_result::<models::Account>(&c).with_expected_err(|| "Unable to fetch account")
+    })?;
+
+    // Errors aren't returned, as a retry would re-run the import and count its items twice
+    let res = match import.import_type.as_str() {
+        "following" => import_following(&import, &account, rows).await,
+        "bookmarks" => import_bookmarks(&import, &account, rows).await,
+        t => {
+            warn!("Import \"{}\" has unsupported type \"{}\"", import.id, t);
+            Ok(())
+        }
+    };
+
+    tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        let upd = diesel::update(crate::schema::imports::dsl::imports.find(import.id));
+        if let Err(e) = &res {
+            warn!("Import \"{}\" failed: {:?}", import.id, e);
+            // Whatever wasn't imported by the time of the error is counted as failed
+            upd.set((
+                crate::schema::imports::dsl::failed_items.eq(
+                    crate::schema::imports::dsl::total_items - crate::schema::imports::dsl::imported_items
+                ),
+                crate::schema::imports::dsl::finished_at.eq(Utc::now().naive_utc()),
+            )).execute(&c).with_expected_err(|| "Unable to update import")
+        } else {
+            upd.set(crate::schema::imports::dsl::finished_at.eq(Utc::now().naive_utc()))
+                .execute(&c).with_expected_err(|| "Unable to update import")
+        }
+    })?;
+
+    info!("Import \"{}\" of {} items finished", import.id, import.total_items);
+
+    Ok(())
+}
@@ -5,41 +5,43 @@ use diesel::prelude::*;
 use super::{resolve_object_or_link};
 
 #[async_recursion::async_recursion]
-async fn _process_activity(activity: activity_streams::Object, signature: Option<activity_streams::Signature>) -> TaskResult<()> {
+async fn _process_activity(
+    activity: activity_streams::Object, signature: Option<activity_streams::Signature>, body: Option<String>,
+) -> TaskResult<()> {
     let db = super::config().db.clone();
     match &activity {
-        activity_streams::Object::Accept(a) |
-        activity_streams::Object::TentativeAccept(a) |
-        activity_streams::Object::Add(a) |
-        activity_streams::Object::Arrive(a) |
-        activity_streams::Object::Create(a) |
-        activity_streams::Object::Delete(a) |
-        activity_streams::Object::Follow(a) |
-        activity_streams::Object::Ignore(a) |
-        activity_streams::Object::Join(a) |
-        activity_streams::Object::Leave(a) |
-        activity_streams::Object::Like(a) |
-        activity_streams::Object::Offer(a) |
-        activity_streams::Object::Invite(a) |
-        activity_streams::Object::Reject(a) |
-        activity_streams::Object::TentativeReject(a) |
-        activity_streams::Object::Remove(a) |
-        activity_streams::Object::Undo(a) |
-        activity_streams::Object::Update(a) |
-        activity_streams::Object::View(a) |
-        activity_streams::Object::Listen(a) |
-        activity_streams::Object::Read(a) |
-        activity_streams::Object::Move(a) |
-        activity_streams::Object::Travel(a) |
-        activity_streams::Object::Announce(a) |
-        activity_streams::Object::Block(a) |
-        activity_streams::Object::Flag(a) |
-        activity_streams::Object::Dislike(a) |
-        activity_streams::Object::Question(a) => {
-            let actor = match &a.actor {
+        activity_streams::Object::Accept(_) |
+        activity_streams::Object::TentativeAccept(_) |
+        activity_streams::Object::Add(_) |
+        activity_streams::Object::Arrive(_) |
+        activity_streams::Object::Create(_) |
+        activity_streams::Object::Delete(_) |
+        activity_streams::Object::Follow(_) |
+        activity_streams::Object::Ignore(_) |
+        activity_streams::Object::Join(_) |
+        activity_streams::Object::Leave(_) |
+        activity_streams::Object::Like(_) |
+        activity_streams::Object::Offer(_) |
+        activity_streams::Object::Invite(_) |
+        activity_streams::Object::Reject(_) |
+        activity_streams::Object::TentativeReject(_) |
+        activity_streams::Object::Remove(_) |
+        activity_streams::Object::Undo(_) |
+        activity_streams::Object::Update(_) |
+        activity_streams::Object::View(_) |
+        activity_streams::Object::Listen(_) |
+        activity_streams::Object::Read(_) |
+        activity_streams::Object::Move(_) |
+        activity_streams::Object::Travel(_) |
+        activity_streams::Object::Announce(_) |
+        activity_streams::Object::Block(_) |
+        activity_streams::Object::Flag(_) |
+        activity_streams::Object::Dislike(_) |
+        activity_streams::Object::Question(_) => {
+            let actor = match activity.actor() {
                 Some(a) => a.clone(),
                 None => {
-                    warn!("Activity \"{}\" has no actor", a.id_or_default());
+                    warn!("Activity \"{}\" has no actor", activity.id_or_default());
                     return Ok(());
                 }
             };
@@ -48,13 +50,18 @@ async fn _process_activity(activity: activity_streams::Object, signature: Option
             let account = match super::accounts::find_account(actor, true).await? {
                 Some(a) => a,
                 None => {
-                    warn!("Activity \"{}\" has an invalid actor", a.id_or_default());
+                    warn!("Activity \"{}\" has an invalid actor", activity.id_or_default());
                     return Ok(());
                 }
             };
 
             if account.local {
-                warn!("Activity \"{}\" has local actor \"{}\"", a.id_or_default(), a.id_or_default());
+                warn!("Activity \"{}\" has local actor \"{}\"", activity.id_or_default(), activity.id_or_default());
+                return Ok(());
+            }
+
+            if account.suspended_at.is_some() {
+                info!("Dropping activity \"{}\" from suspended actor \"{}\"", activity.id_or_default(), actor_id);
                 return Ok(());
             }
 
@@ -67,14 +74,14 @@ async fn _process_activity(activity: activity_streams::Object, signature: Option
                 })? {
                     Some(k) => k,
                     None => {
-                        warn!("Activity \"{}\" has unknown public key \"{}\"", a.id_or_default(), signature.key_id);
+                        warn!("Activity \"{}\" has unknown public key \"{}\"", activity.id_or_default(), signature.key_id);
                         return Ok(());
                     }
                 };
                 let pkey = openssl::pkey::PKey::public_key_from_pem(public_key.key.as_bytes()).with_unexpected_err(|| "Unable to parse public key")?;
 
                 if !signature.verify(&pkey) {
-                    warn!("Activity \"{}\" signature verification failed with key \"{}\"", a.id_or_default(), signature.key_id);
+                    warn!("Activity \"{}\" signature verification failed with key \"{}\"", activity.id_or_default(), signature.key_id);
                     return Ok(());
                 }
 
@@ -82,9 +89,9 @@ async fn _process_activity(activity: activity_streams::Object, signature: Option
                     info!(
                     "Activity \"{}\" has public key \"{}\" that does not belong to actor \"{}\", \
                      fetching activity from home server to assure authenticity",
-                    a.id_or_default(), signature.key_id, actor_id
+                    activity.id_or_default(), signature.key_id, actor_id
                 );
-                    match a.id() {
+                    match activity.id() {
                         Some(id) => {
                             let activity = match super::fetch_object::<activity_streams::Object, _>(id).await {
                                 Some(a) => a,
@@ -92,7 +99,7 @@ async fn _process_activity(activity: activity_streams::Object, signature: Option
                                     return Ok(());
                                 }
                             };
-                            _process_activity(activity, None).await?;
+                            _process_activity(activity, None, None).await?;
                         },
                         None => {
                             warn!("Activity has no ID, cannot fetch");
@@ -103,10 +110,28 @@ async fn _process_activity(activity: activity_streams::Object, signature: Option
                 }
             }
 
+            // Only a body whose signature we've checked against the actor is worth forwarding
+            if let Some(body) = body {
+                forward_activity(&activity, body, &account).await?;
+            }
+
+            // Hearing from a domain is as good a sign of life as a successful delivery
+            if let Some(domain) = reqwest::Url::parse(&actor_id).ok().and_then(|u| u.host_str().map(|h| h.to_string())) {
+                if super::delivery::is_domain_unavailable(&domain)? {
+                    super::delivery::record_delivery_success(&domain)?;
+                }
+            }
+
             let celery = super::config().celery;
             match activity {
                 activity_streams::Object::Create(a) => {
                     if let Some(o) = a.object {
+                        if super::relays::find_relay(&account).await?.is_some() {
+                            celery.send_task(
+                                super::relays::ingest_relayed_status::new(o)
+                            ).await.with_expected_err(|| "Unable to send task")?;
+                            return Ok(());
+                        }
                         celery.send_task(
                             super::statuses::create_status::new(o, account)
                         ).await.with_expected_err(|| "Unable to send task")?;
@@ -115,9 +140,19 @@ async fn _process_activity(activity: activity_streams::Object, signature: Option
                     }
                 }
                 activity_streams::Object::Announce(a) => {
-                    celery.send_task(
-                        super::statuses::create_announce::new(a, account)
-                    ).await.with_expected_err(|| "Unable to send task")?;
+                    if super::relays::find_relay(&account).await?.is_some() {
+                        if let Some(o) = a.object {
+                            celery.send_task(
+                                super::relays::ingest_relayed_status::new(o)
+                            ).await.with_expected_err(|| "Unable to send task")?;
+                        } else {
+                            warn!("Announce activity \"{}\" has no object", a.id_or_default());
+                        }
+                    } else {
+                        celery.send_task(
+                            super::statuses::create_announce::new(a, account)
+                        ).await.with_expected_err(|| "Unable to send task")?;
+                    }
                 }
                 activity_streams::Object::Like(a) => {
                     celery.send_task(
@@ -129,6 +164,26 @@ async fn _process_activity(activity: activity_streams::Object, signature: Option
                         super::relationships::process_follow::new(a, account)
                     ).await.with_expected_err(|| "Unable to send task")?;
                 }
+                activity_streams::Object::Add(a) => {
+                    celery.send_task(
+                        super::statuses::process_add::new(a, account)
+                    ).await.with_expected_err(|| "Unable to send task")?;
+                }
+                activity_streams::Object::Remove(a) => {
+                    celery.send_task(
+                        super::statuses::process_remove::new(a, account)
+                    ).await.with_expected_err(|| "Unable to send task")?;
+                }
+                activity_streams::Object::Move(a) => {
+                    celery.send_task(
+                        super::accounts::process_move::new(a, account)
+                    ).await.with_expected_err(|| "Unable to send task")?;
+                }
+                activity_streams::Object::Flag(a) => {
+                    celery.send_task(
+                        super::reports::process_flag::new(a, account)
+                    ).await.with_expected_err(|| "Unable to send task")?;
+                }
                 activity_streams::Object::Update(a) => {
                     match &a.object {
                         Some(o) => {
@@ -292,7 +347,128 @@ async fn _process_activity(activity: activity_streams::Object, signature: Option
     }
 }
 
+/// Forwards an activity to the followers of a local account, as in ActivityPub §7.1.2, when it's addressed to
+/// their followers collection and concerns one of their statuses, such as a reply to a followers-only post
+async fn forward_activity(
+    activity: &activity_streams::Object, body: String, account: &crate::models::Account,
+) -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+
+    let activity = match activity {
+        activity_streams::Object::Create(a) |
+        activity_streams::Object::Update(a) |
+        activity_streams::Object::Delete(a) |
+        activity_streams::Object::Announce(a) |
+        activity_streams::Object::Like(a) |
+        activity_streams::Object::Undo(a) => a,
+        _ => return Ok(())
+    };
+    let activity_id = match activity.id() {
+        Some(id) => id.to_string(),
+        None => return Ok(())
+    };
+
+    let local_followers_regex = regex::Regex::new(
+        &format!("^https://{}/as/users/(?P<id>[^/#]+)/followers$", config.uri)
+    ).unwrap();
+    let addressed: Vec<uuid::Uuid> = activity.common.to.as_slice().iter()
+        .chain(activity.common.cc.as_slice().iter())
+        .chain(activity.common.audience.as_slice().iter())
+        .filter_map(|a| a.id())
+        .filter_map(|a| local_followers_regex.captures(a))
+        .filter_map(|c| uuid::Uuid::parse_str(c.name("id").unwrap().as_str()).ok())
+        .collect();
+    if addressed.is_empty() {
+        return Ok(());
+    }
+
+    let mut references: Vec<&str> = activity.object.iter()
+        .chain(activity.target.iter())
+        .filter_map(|o| o.id())
+        .collect();
+    if let Some(activity_streams::ReferenceOrObject::Object(o)) = &activity.object {
+        let object_common = match o.as_ref() {
+            activity_streams::ObjectOrLink::Object(activity_streams::Object::Note(o)) |
+            activity_streams::ObjectOrLink::Object(activity_streams::Object::Article(o)) |
+            activity_streams::ObjectOrLink::Object(activity_streams::Object::Page(o)) |
+            activity_streams::ObjectOrLink::Object(activity_streams::Object::Document(o)) |
+            activity_streams::ObjectOrLink::Object(activity_streams::Object::Image(o)) |
+            activity_streams::ObjectOrLink::Object(activity_streams::Object::Video(o)) |
+            activity_streams::ObjectOrLink::Object(activity_streams::Object::Audio(o)) |
+            activity_streams::ObjectOrLink::Object(activity_streams::Object::Event(o)) => Some(o),
+            activity_streams::ObjectOrLink::Object(activity_streams::Object::Question(q)) => Some(&q.common),
+            _ => None
+        };
+        if let Some(object_common) = object_common {
+            references.extend(object_common.in_reply_to.iter().filter_map(|r| r.id()));
+            references.extend(object_common.tag.as_slice().iter().filter_map(|t| t.id()));
+        }
+    }
+
+    let local_status_regex = regex::Regex::new(
+        &format!("^https://{}/as/status/(?P<id>[^/#]+)(/activity)?$", config.uri)
+    ).unwrap();
+    let status_ids: Vec<uuid::Uuid> = references.into_iter()
+        .filter_map(|r| local_status_regex.captures(r))
+        .filter_map(|c| uuid::Uuid::parse_str(c.name("id").unwrap().as_str()).ok())
+        .collect();
+    if status_ids.is_empty() {
+        return Ok(());
+    }
+
+    let owners = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        crate::schema::accounts::dsl::accounts.filter(
+            crate::schema::accounts::dsl::id.eq_any(
+                crate::schema::statuses::dsl::statuses.filter(
+                    crate::schema::statuses::dsl::id.eq_any(&status_ids)
+                ).filter(
+                    crate::schema::statuses::dsl::local.eq(true)
+                ).select(crate::schema::statuses::dsl::account_id)
+            )
+        ).filter(
+            crate::schema::accounts::dsl::id.eq_any(&addressed)
+        ).filter(
+            crate::schema::accounts::dsl::local.eq(true)
+        ).get_results::<crate::models::Account>(&c).with_expected_err(|| "Unable to fetch accounts")
+    })?;
+
+    // The origin server already delivered to its own users
+    let origin = account.actor.as_deref()
+        .and_then(|a| reqwest::Url::parse(a).ok())
+        .and_then(|u| u.host_str().map(|h| h.to_string()));
+
+    for owner in owners {
+        let followers = tokio::task::block_in_place(|| -> TaskResult<_> {
+            let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+            crate::schema::accounts::dsl::accounts.filter(
+                crate::schema::accounts::dsl::id.eq_any(
+                    crate::schema::following::dsl::following.filter(
+                        crate::schema::following::dsl::followee.eq(owner.id)
+                    ).filter(
+                        crate::schema::following::dsl::pending.eq(false)
+                    ).select(crate::schema::following::dsl::follower)
+                )
+            ).get_results::<crate::models::Account>(&c).with_expected_err(|| "Unable to get followers")
+        })?;
+        let followers = followers.into_iter().filter(|f| {
+            let domain = f.actor.as_deref()
+                .and_then(|a| reqwest::Url::parse(a).ok())
+                .and_then(|u| u.host_str().map(|h| h.to_string()));
+            domain != origin
+        }).collect();
+
+        info!("Forwarding \"{}\" to the followers of \"{}\"", activity_id, owner.id);
+        super::delivery::forward_dedupe_inboxes(activity_id.clone(), body.clone(), followers, owner).await?;
+    }
+
+    Ok(())
+}
+
 #[celery::task]
-pub async fn process_activity(activity: activity_streams::Object, signature: activity_streams::Signature) -> TaskResult<()> {
-    _process_activity(activity, Some(signature)).await
+pub async fn process_activity(
+    activity: activity_streams::Object, signature: activity_streams::Signature, body: String,
+) -> TaskResult<()> {
+    _process_activity(activity, Some(signature), Some(body)).await
 }
\ No newline at end of file
@@ -0,0 +1,120 @@
+use celery::prelude::*;
+use chrono::prelude::*;
+use diesel::prelude::*;
+use crate::models;
+
+/// Remote profiles last fetched longer ago than this are refreshed
+const STALE_ACCOUNT_HOURS: i64 = 24;
+/// Upper bound on profile refreshes submitted per run, so one run doesn't flood remote servers
+const STALE_ACCOUNT_BATCH: i64 = 500;
+/// Authorization codes are only redeemable for a minute, but are kept a little longer for debugging
+const OAUTH_CODE_RETENTION_HOURS: i64 = 1;
+/// Sessions with a refresh token can be renewed after their access token expires, so are kept for a while
+const SESSION_REFRESH_RETENTION_DAYS: i64 = 30;
+/// Uploads not attached to a status within this time are deleted
+const ORPHANED_MEDIA_HOURS: i64 = 24;
+
+#[celery::task]
+pub async fn refresh_stale_accounts() -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+    let cutoff = (Utc::now() - chrono::Duration::hours(STALE_ACCOUNT_HOURS)).naive_utc();
+
+    let actors: Vec<Option<String>> = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        crate::schema::accounts::dsl::accounts
+            .filter(crate::schema::accounts::dsl::local.eq(false))
+            .filter(crate::schema::accounts::dsl::deleted_at.is_null())
+            .filter(crate::schema::accounts::dsl::actor.is_not_null())
+            .filter(crate::schema::accounts::dsl::updated_at.lt(cutoff))
+            .order_by(crate::schema::accounts::dsl::updated_at.asc())
+            .limit(STALE_ACCOUNT_BATCH)
+            .select(crate::schema::accounts::dsl::actor)
+            .get_results(&c).with_expected_err(|| "Unable to fetch accounts")
+    })?;
+
+    info!("Refreshing {} stale remote profiles", actors.len());
+    for actor in actors.into_iter().flatten() {
+        config.celery.send_task(
+            super::accounts::update_account::new(actor, true)
+        ).await.with_expected_err(|| "Unable to send task")?;
+    }
+
+    Ok(())
+}
+
+#[celery::task]
+pub async fn delete_expired_sessions() -> TaskResult<()> {
+    let db = super::config().db.clone();
+    let now = Utc::now();
+    let code_cutoff = (now - chrono::Duration::hours(OAUTH_CODE_RETENTION_HOURS)).naive_utc();
+    let refresh_cutoff = (now - chrono::Duration::days(SESSION_REFRESH_RETENTION_DAYS)).naive_utc();
+    let now = now.naive_utc();
+
+    let (codes, sessions) = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        c.transaction::<_, diesel::result::Error, _>(|| {
+            let codes = crate::schema::oauth_codes::dsl::oauth_codes.filter(
+                crate::schema::oauth_codes::dsl::time.lt(code_cutoff)
+            ).select(crate::schema::oauth_codes::dsl::id).get_results::<uuid::Uuid>(&c)?;
+            diesel::delete(crate::schema::oauth_code_scopes::dsl::oauth_code_scopes.filter(
+                crate::schema::oauth_code_scopes::dsl::code_id.eq_any(&codes)
+            )).execute(&c)?;
+            diesel::delete(crate::schema::oauth_codes::dsl::oauth_codes.filter(
+                crate::schema::oauth_codes::dsl::id.eq_any(&codes)
+            )).execute(&c)?;
+
+            let sessions = diesel::delete(crate::schema::session::dsl::session.filter(
+                crate::schema::session::dsl::refresh_token.is_null()
+                    .and(crate::schema::session::dsl::expires_at.lt(now))
+                    .or(crate::schema::session::dsl::expires_at.lt(refresh_cutoff))
+            )).execute(&c)?;
+
+            Ok((codes.len(), sessions))
+        }).with_expected_err(|| "Unable to delete expired sessions")
+    })?;
+
+    info!("Deleted {} expired OAuth codes and {} expired sessions", codes, sessions);
+    Ok(())
+}
+
+#[celery::task]
+pub async fn delete_orphaned_media() -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+    let cutoff = (Utc::now() - chrono::Duration::hours(ORPHANED_MEDIA_HOURS)).naive_utc();
+
+    let media = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        c.transaction::<_, diesel::result::Error, _>(|| {
+            let media = crate::schema::media::dsl::media.filter(
+                crate::schema::media::dsl::created_at.lt(cutoff)
+            ).filter(
+                diesel::dsl::not(crate::schema::media::dsl::id.eq_any(
+                    crate::schema::media_attachments::dsl::media_attachments
+                        .select(crate::schema::media_attachments::dsl::media)
+                ))
+            ).filter(
+                diesel::dsl::not(crate::schema::media::dsl::id.eq_any(
+                    crate::schema::status_media_attachments::dsl::status_media_attachments
+                        .select(crate::schema::status_media_attachments::dsl::media_attachment_id)
+                ))
+            ).for_update().get_results::<models::Media>(&c)?;
+            diesel::delete(crate::schema::media::dsl::media.filter(
+                crate::schema::media::dsl::id.eq_any(media.iter().map(|m| m.id).collect::<Vec<_>>())
+            )).execute(&c)?;
+            Ok(media)
+        }).with_expected_err(|| "Unable to delete orphaned media")
+    })?;
+
+    info!("Deleted {} orphaned media attachments", media.len());
+    for file in media.into_iter().flat_map(|m| vec![m.file, m.preview_file]).flatten() {
+        if let Err(e) = std::fs::remove_file(config.media_path.join(&file)) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Unable to delete media file \"{}\": {}", file, e);
+            }
+        }
+    }
+
+    Ok(())
+}
\ No newline at end of file
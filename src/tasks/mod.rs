@@ -7,6 +7,14 @@ pub mod relationships;
 pub mod delivery;
 pub mod notifications;
 pub mod statuses;
+pub mod trends;
+pub mod reports;
+pub mod stats;
+pub mod imports;
+pub mod archives;
+pub mod groups;
+pub mod relays;
+pub mod maintenance;
 
 const SIGNED_HEADERS: [&str; 4] = ["host", "date", "digest", "content-type"];
 
@@ -23,6 +31,8 @@ pub struct Config {
     pub web_push_client: std::sync::Arc<web_push_old::WebPushClient>,
     pub as_key: std::sync::Arc<openssl::pkey::PKey<openssl::pkey::Private>>,
     pub media_path: std::sync::Arc<std::path::PathBuf>,
+    pub archive_path: std::sync::Arc<std::path::PathBuf>,
+    pub json_ld: crate::JsonLdConfig,
 }
 
 #[inline]
@@ -30,6 +40,55 @@ pub(crate) fn config() -> Config {
     CONFIG.read().unwrap().as_ref().unwrap().clone()
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureFormat {
+    /// RFC 9421 HTTP Message Signatures with the account's Ed25519 key
+    Rfc9421Ed25519,
+    /// RFC 9421 HTTP Message Signatures
+    Rfc9421,
+    /// draft-cavage-http-signatures, as used by most of the fediverse
+    Cavage,
+}
+
+impl SignatureFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignatureFormat::Rfc9421Ed25519 => "rfc9421-ed25519",
+            SignatureFormat::Rfc9421 => "rfc9421",
+            SignatureFormat::Cavage => "cavage",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "rfc9421-ed25519" => Some(SignatureFormat::Rfc9421Ed25519),
+            "rfc9421" => Some(SignatureFormat::Rfc9421),
+            "cavage" => Some(SignatureFormat::Cavage),
+            _ => None
+        }
+    }
+}
+
+/// The keys an actor can sign requests with, RSA being the one every server understands
+pub struct SigningKeys<'a> {
+    pub rsa: (&'a openssl::pkey::PKeyRef<openssl::pkey::Private>, String),
+    pub ed25519: Option<(&'a openssl::pkey::PKeyRef<openssl::pkey::Private>, String)>,
+}
+
+pub enum SignedRequestError {
+    Signing(String),
+    Request(reqwest::Error),
+}
+
+impl std::fmt::Display for SignedRequestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignedRequestError::Signing(e) => write!(f, "{}", e),
+            SignedRequestError::Request(e) => write!(f, "Unable to send request: {}", e),
+        }
+    }
+}
+
 fn sign_request(req: &mut reqwest::Request, pkey: &openssl::pkey::PKeyRef<openssl::pkey::Private>, key_id: String) -> Result<(), String> {
     let mut signed_data = vec![
         format!("(request-target): {} {}", req.method().as_str().to_lowercase(), req.url().path()),
@@ -60,18 +119,125 @@ fn sign_request(req: &mut reqwest::Request, pkey: &openssl::pkey::PKeyRef<openss
     Ok(())
 }
 
+fn sign_request_rfc9421(req: &mut reqwest::Request, pkey: &openssl::pkey::PKeyRef<openssl::pkey::Private>, key_id: &str) -> Result<(), String> {
+    let mut components = vec!["@method", "@target-uri"];
+    if req.headers().contains_key("content-digest") {
+        components.push("content-digest");
+    }
+
+    let alg = match pkey.id() {
+        openssl::pkey::Id::ED25519 => "ed25519",
+        _ => "rsa-v1_5-sha256",
+    };
+    let signature_params = format!(
+        "({});created={};keyid=\"{}\";alg=\"{}\"",
+        components.iter().map(|c| format!("\"{}\"", c)).collect::<Vec<_>>().join(" "),
+        chrono::Utc::now().timestamp(), key_id, alg
+    );
+    let mut signature_base = vec![];
+    for component in &components {
+        let value = match *component {
+            "@method" => req.method().as_str().to_string(),
+            "@target-uri" => req.url().to_string(),
+            header => req.headers().get(header).ok_or_else(|| format!("Missing {} header", header))?
+                .to_str().map_err(|e| format!("Unable to convert header to string: {}", e))?.to_string()
+        };
+        signature_base.push(format!("\"{}\": {}", component, value));
+    }
+    signature_base.push(format!("\"@signature-params\": {}", signature_params));
+
+    let mut signer = match pkey.id() {
+        // Ed25519 hashes internally
+        openssl::pkey::Id::ED25519 => openssl::sign::Signer::new_without_digest(pkey),
+        _ => openssl::sign::Signer::new(openssl::hash::MessageDigest::sha256(), pkey),
+    }.map_err(|e| format!("Unable to create signer: {}", e))?;
+    let signature = signer.sign_oneshot_to_vec(signature_base.join("\n").as_bytes())
+        .map_err(|e| format!("Unable to sign request: {}", e))?;
+    req.headers_mut().insert("Signature-Input", format!("sig1={}", signature_params)
+        .parse().map_err(|e| format!("Unable to parse signature input header: {}", e))?);
+    req.headers_mut().insert("Signature", format!("sig1=:{}:", base64::encode(signature))
+        .parse().map_err(|e| format!("Unable to parse signature header: {}", e))?);
+
+    Ok(())
+}
+
+async fn execute_signed_as(
+    req: &reqwest::Request, format: SignatureFormat, keys: &SigningKeys<'_>,
+) -> Result<reqwest::Response, SignedRequestError> {
+    let mut req = req.try_clone()
+        .ok_or_else(|| SignedRequestError::Signing("Unable to clone request".to_string()))?;
+    let (rsa_key, rsa_key_id) = &keys.rsa;
+    match (format, &keys.ed25519) {
+        (SignatureFormat::Rfc9421Ed25519, Some((pkey, key_id))) => sign_request_rfc9421(&mut req, pkey, key_id),
+        (SignatureFormat::Rfc9421Ed25519, None) |
+        (SignatureFormat::Rfc9421, _) => sign_request_rfc9421(&mut req, rsa_key, rsa_key_id),
+        (SignatureFormat::Cavage, _) => sign_request(&mut req, rsa_key, rsa_key_id.clone()),
+    }.map_err(SignedRequestError::Signing)?;
+    crate::AS_CLIENT.execute(req).await.map_err(SignedRequestError::Request)
+}
+
+/// Sends a request signed in the format the domain is known to accept. Domains we haven't heard from
+/// yet get RFC 9421 first, with the Ed25519 key if there is one, then RSA, and the cavage draft if those
+/// signatures are rejected, remembering which worked.
+async fn execute_signed(req: reqwest::Request, keys: SigningKeys<'_>) -> Result<reqwest::Response, SignedRequestError> {
+    let domain = req.url().host_str().unwrap_or_default().to_string();
+    let known_format = match delivery::signature_format(&domain) {
+        Ok(f) => f,
+        Err(e) => {
+            warn!("Unable to get signature format for \"{}\": {:?}", domain, e);
+            None
+        }
+    };
+
+    let is_rejected = |r: &reqwest::Response| matches!(
+        r.status(),
+        reqwest::StatusCode::BAD_REQUEST | reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN
+    );
+
+    let formats = match known_format {
+        Some(f) => vec![f],
+        None if keys.ed25519.is_some() => vec![
+            SignatureFormat::Rfc9421Ed25519, SignatureFormat::Rfc9421, SignatureFormat::Cavage
+        ],
+        None => vec![SignatureFormat::Rfc9421, SignatureFormat::Cavage],
+    };
+
+    let mut formats = formats.into_iter().peekable();
+    loop {
+        let format = formats.next().unwrap();
+        let r = execute_signed_as(&req, format, &keys).await?;
+        if known_format.is_some() {
+            return Ok(r);
+        }
+        if is_rejected(&r) {
+            if let Some(next) = formats.peek() {
+                debug!("{} signature rejected by \"{}\", retrying with {}", format.as_str(), domain, next.as_str());
+                continue;
+            }
+        }
+        if r.status().is_success() {
+            if let Err(e) = delivery::remember_signature_format(&domain, format) {
+                warn!("Unable to remember signature format for \"{}\": {:?}", domain, e);
+            }
+        }
+        return Ok(r);
+    }
+}
+
 async fn authenticated_get(url: reqwest::Url) -> Result<reqwest::Response, String> {
     let config = config();
     let pkey = config.as_key;
     let host = url.host_str().map(|h| h.to_string()).ok_or("No host in URL")?;
     let date = chrono::Utc::now().naive_utc().format("%a, %d %h %Y %H:%M:%S GMT").to_string();
-    let mut req = crate::AS_CLIENT.get(url.clone())
+    let req = crate::AS_CLIENT.get(url.clone())
         .header("Host", &host)
         .header("Date", &date)
         .build().map_err(|e| format!("Unable to build request: {}", e))?;
 
-    sign_request(&mut req, &pkey, format!("https://{}/as/system#key", config.uri))?;
-    crate::AS_CLIENT.execute(req).await.map_err(|e| format!("Unable to send request: {}", e))
+    execute_signed(req, SigningKeys {
+        rsa: (&pkey, format!("https://{}/as/system#key", config.uri)),
+        ed25519: None,
+    }).await.map_err(|e| e.to_string())
 }
 
 async fn fetch_object<'a, T: serde::de::DeserializeOwned, U: Into<std::borrow::Cow<'a, str>>>(uri: U) -> Option<T> {
@@ -84,13 +250,23 @@ async fn fetch_object<'a, T: serde::de::DeserializeOwned, U: Into<std::borrow::C
         }
     };
 
-    match backoff::future::retry(backoff::ExponentialBackoff::default(), || async {
+    let start = std::time::Instant::now();
+    let result = backoff::future::retry(backoff::ExponentialBackoff::default(), || async {
         match authenticated_get(url.clone()).await {
             Ok(r) => {
                 match r.error_for_status() {
                     Ok(r) => {
-                        match r.json::<T>().await {
-                            Ok(r) => Ok(r),
+                        let json_ld = config().json_ld;
+                        if !json_ld.enabled {
+                            return match r.json::<T>().await {
+                                Ok(r) => Ok(r),
+                                Err(e) => Err(backoff::Error::Permanent(e.to_string()))
+                            };
+                        }
+                        let lenient = url.host_str().map(|h| json_ld.is_lenient(h)).unwrap_or(false);
+                        match r.json::<serde_json::Value>().await {
+                            Ok(r) => serde_json::from_value::<T>(crate::json_ld::normalise(r, lenient))
+                                .map_err(|e| backoff::Error::Permanent(e.to_string())),
                             Err(e) => Err(backoff::Error::Permanent(e.to_string()))
                         }
                     },
@@ -106,7 +282,13 @@ async fn fetch_object<'a, T: serde::de::DeserializeOwned, U: Into<std::borrow::C
             }
             Err(e) => Err(backoff::Error::Permanent(e.to_string())),
         }
-    }).await {
+    }).await;
+    crate::metrics::FETCH_DURATION.observe(
+        &[if result.is_ok() { "success" } else { "failure" }, &crate::metrics::domain_label(&uri)],
+        start.elapsed().as_secs_f64()
+    );
+
+    match result {
         Ok(r) => Some(r),
         Err(e) => {
             warn!("Failed to fetch object {}: {}", uri, e);
@@ -1,7 +1,10 @@
 use celery::prelude::*;
+use chrono::prelude::*;
 use diesel::prelude::*;
 use crate::models;
 
+const NEW_ACCOUNT_DAYS: i64 = 30;
+
 #[derive(Serialize, Deserialize, Clone)]
 struct NotificationData {
     title: String,
@@ -19,6 +22,34 @@ pub struct Notification {
     p256dh: String,
     auth: String,
     data: NotificationData,
+    #[serde(default = "default_urgency")]
+    urgency: String,
+    #[serde(default)]
+    topic: Option<String>,
+}
+
+fn default_urgency() -> String {
+    "normal".to_string()
+}
+
+fn notification_urgency(notification_type: &str) -> &'static str {
+    match notification_type {
+        "mention" => "high",
+        "favourite" | "reblog" | "status" | "update" => "low",
+        _ => "normal"
+    }
+}
+
+/// Push services replace an undelivered message with the same topic, so notifications about the
+/// same subject collapse into one on the device. Topics are limited to 32 URL safe base64 characters.
+fn notification_topic(notification: &models::Notification) -> String {
+    use sha2::Digest;
+
+    let subject = notification.status.unwrap_or(notification.cause);
+    let digest = sha2::Sha256::digest(format!("{}:{}", notification.notification_type, subject).as_bytes());
+    let mut topic = base64::encode_config(digest, base64::URL_SAFE_NO_PAD);
+    topic.truncate(32);
+    topic
 }
 
 #[celery::task]
@@ -26,7 +57,11 @@ pub async fn notify(notification: models::Notification) -> TaskResult<()> {
     let config = super::config();
     let db = config.db.clone();
 
-    let (account, cause, status, is_followed, is_following) = tokio::task::block_in_place(|| -> TaskResult<_> {
+    if notification.filtered {
+        return Ok(());
+    }
+
+    let (account, cause, status, report, is_followed, is_following) = tokio::task::block_in_place(|| -> TaskResult<_> {
         let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
         let a = crate::schema::accounts::dsl::accounts
             .filter(crate::schema::accounts::dsl::id.eq(notification.account))
@@ -41,6 +76,13 @@ pub async fn notify(notification: models::Notification) -> TaskResult<()> {
             None => None
         };
 
+        let r = match notification.report {
+            Some(rid) => Some(crate::schema::reports::dsl::reports
+                .filter(crate::schema::reports::dsl::id.eq(rid))
+                .get_result::<models::Report>(&c).with_expected_err(|| "Unable to get report")?),
+            None => None
+        };
+
         let is_followed = crate::schema::following::dsl::following
             .filter(crate::schema::following::dsl::follower.eq(notification.account))
             .filter(crate::schema::following::dsl::followee.eq(notification.cause))
@@ -50,47 +92,33 @@ pub async fn notify(notification: models::Notification) -> TaskResult<()> {
             .filter(crate::schema::following::dsl::follower.eq(notification.cause))
             .count().get_result::<i64>(&c).with_expected_err(|| "Unable to check following set")? > 0;
 
-        Ok((a, ca, s, is_followed, is_following))
+        Ok((a, ca, s, r, is_followed, is_following))
     })?;
 
-    let subscriptions = match notification.notification_type.as_str() {
-        "follow" => {
-            tokio::task::block_in_place(|| -> TaskResult<_> {
-                let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
-                crate::schema::web_push_subscriptions::dsl::web_push_subscriptions
-                    .filter(crate::schema::web_push_subscriptions::dsl::follow.eq(true))
-                    .filter(crate::schema::web_push_subscriptions::dsl::account_id.eq(notification.account))
-                    .get_results(&c).with_expected_err(|| "Unable to get subscriptions")
-            })?
-        }
-        "favourite" => {
-            tokio::task::block_in_place(|| -> TaskResult<_> {
-                let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
-                crate::schema::web_push_subscriptions::dsl::web_push_subscriptions
-                    .filter(crate::schema::web_push_subscriptions::dsl::favourite.eq(true))
-                    .filter(crate::schema::web_push_subscriptions::dsl::account_id.eq(notification.account))
-                    .get_results::<models::WebPushSubscription>(&c).with_expected_err(|| "Unable to get subscriptions")
-            })?
-        }
-        "reblog" => {
-            tokio::task::block_in_place(|| -> TaskResult<_> {
-                let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
-                crate::schema::web_push_subscriptions::dsl::web_push_subscriptions
-                    .filter(crate::schema::web_push_subscriptions::dsl::reblog.eq(true))
-                    .filter(crate::schema::web_push_subscriptions::dsl::account_id.eq(notification.account))
-                    .get_results::<models::WebPushSubscription>(&c).with_expected_err(|| "Unable to get subscriptions")
-            })?
-        }
-        "mention" => {
-            tokio::task::block_in_place(|| -> TaskResult<_> {
-                let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
-                crate::schema::web_push_subscriptions::dsl::web_push_subscriptions
-                    .filter(crate::schema::web_push_subscriptions::dsl::mention.eq(true))
-                    .filter(crate::schema::web_push_subscriptions::dsl::account_id.eq(notification.account))
-                    .get_results::<models::WebPushSubscription>(&c).with_expected_err(|| "Unable to get subscriptions")
-            })?
-        }
-        _ => {
+    let subscriptions = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        let q = crate::schema::web_push_subscriptions::dsl::web_push_subscriptions
+            .filter(crate::schema::web_push_subscriptions::dsl::account_id.eq(notification.account))
+            .into_boxed();
+        let q = match notification.notification_type.as_str() {
+            "follow" => q.filter(crate::schema::web_push_subscriptions::dsl::follow.eq(true)),
+            "follow_request" => q.filter(crate::schema::web_push_subscriptions::dsl::follow_request.eq(true)),
+            "favourite" => q.filter(crate::schema::web_push_subscriptions::dsl::favourite.eq(true)),
+            "reblog" => q.filter(crate::schema::web_push_subscriptions::dsl::reblog.eq(true)),
+            "mention" => q.filter(crate::schema::web_push_subscriptions::dsl::mention.eq(true)),
+            "poll" => q.filter(crate::schema::web_push_subscriptions::dsl::poll.eq(true)),
+            "status" => q.filter(crate::schema::web_push_subscriptions::dsl::status.eq(true)),
+            "update" => q.filter(crate::schema::web_push_subscriptions::dsl::update.eq(true)),
+            "admin.sign_up" => q.filter(crate::schema::web_push_subscriptions::dsl::admin_sign_up.eq(true)),
+            "admin.report" => q.filter(crate::schema::web_push_subscriptions::dsl::admin_report.eq(true)),
+            _ => return Ok(None)
+        };
+        q.get_results::<models::WebPushSubscription>(&c).map(Some)
+            .with_expected_err(|| "Unable to get subscriptions")
+    })?;
+    let subscriptions = match subscriptions {
+        Some(s) => s,
+        None => {
             warn!("Unknown notification type: {}", notification.notification_type);
             return Ok(());
         }
@@ -101,55 +129,43 @@ pub async fn notify(notification: models::Notification) -> TaskResult<()> {
         _ => false
     }).collect::<Vec<models::WebPushSubscription>>();
 
+    if subscriptions.is_empty() {
+        return Ok(());
+    }
+
     let localizer = crate::i18n::Localizer::get_lang_opt(account.default_language.as_deref());
 
-    let notification_data = match notification.notification_type.as_str() {
-        "follow" => {
-            NotificationData {
-                notification_id: notification.iid,
-                notification_type: "follow".to_string(),
-                title: fl!(localizer, "follow-notification", name = account.display_name),
-                icon: cause.avatar_file.as_ref().map(|f| format!("https://{}/media/{}", config.uri, f)),
-                body: cause.bio,
-                access_token: "".to_string(),
-                preferred_locale: cause.default_language.clone().unwrap_or_else(|| "en".to_string()),
-            }
-        },
-        "favourite" => {
-            NotificationData {
-                notification_id: notification.iid,
-                notification_type: "favourite".to_string(),
-                title: fl!(localizer, "favourite-notification", name = account.display_name),
-                icon: cause.avatar_file.as_ref().map(|f| format!("https://{}/media/{}", config.uri, f)),
-                body: status.as_ref().map(|s| s.text.clone()).unwrap_or_else(|| "".to_string()),
-                access_token: "".to_string(),
-                preferred_locale: cause.default_language.clone().unwrap_or_else(|| "en".to_string()),
-            }
-        }
-        "reblog" => {
-            NotificationData {
-                notification_id: notification.iid,
-                notification_type: "reblog".to_string(),
-                title: fl!(localizer, "reblog-notification", name = account.display_name),
-                icon: cause.avatar_file.as_ref().map(|f| format!("https://{}/media/{}", config.uri, f)),
-                body: status.as_ref().map(|s| s.text.clone()).unwrap_or_else(|| "".to_string()),
-                access_token: "".to_string(),
-                preferred_locale: cause.default_language.clone().unwrap_or_else(|| "en".to_string()),
-            }
-        }
-        "mention" => {
-            NotificationData {
-                notification_id: notification.iid,
-                notification_type: "mention".to_string(),
-                title: fl!(localizer, "mention-notification", name = account.display_name),
-                icon: cause.avatar_file.as_ref().map(|f| format!("https://{}/media/{}", config.uri, f)),
-                body: status.as_ref().map(|s| s.text.clone()).unwrap_or_else(|| "".to_string()),
-                access_token: "".to_string(),
-                preferred_locale: cause.default_language.clone().unwrap_or_else(|| "en".to_string()),
-            }
-        }
+    let name = cause.display_name.as_str();
+    let title = match notification.notification_type.as_str() {
+        "follow" => fl!(localizer, "follow-notification", name = name),
+        "follow_request" => fl!(localizer, "follow-request-notification", name = name),
+        "favourite" => fl!(localizer, "favourite-notification", name = name),
+        "reblog" => fl!(localizer, "reblog-notification", name = name),
+        "mention" => fl!(localizer, "mention-notification", name = name),
+        "poll" => fl!(localizer, "poll-notification"),
+        "status" => fl!(localizer, "status-notification", name = name),
+        "update" => fl!(localizer, "update-notification", name = name),
+        "admin.sign_up" => fl!(localizer, "admin-sign-up-notification", name = name),
+        "admin.report" => fl!(localizer, "admin-report-notification", name = name),
         _ => unreachable!()
     };
+    let body = match notification.notification_type.as_str() {
+        "follow" | "follow_request" | "admin.sign_up" => cause.bio.clone(),
+        "admin.report" => report.as_ref().map(|r| r.comment.clone()).unwrap_or_default(),
+        _ => status.as_ref().map(|s| s.text.clone()).unwrap_or_default(),
+    };
+
+    let notification_data = NotificationData {
+        notification_id: notification.iid,
+        notification_type: notification.notification_type.clone(),
+        title,
+        icon: cause.avatar_file.as_ref().map(|f| format!("https://{}/media/{}", config.uri, f)),
+        body,
+        access_token: "".to_string(),
+        preferred_locale: account.default_language.clone().unwrap_or_else(|| "en".to_string()),
+    };
+    let urgency = notification_urgency(&notification.notification_type);
+    let topic = notification_topic(&notification);
 
     for sub in subscriptions {
         config.celery.send_task(deliver_notification::new(Notification {
@@ -157,12 +173,142 @@ pub async fn notify(notification: models::Notification) -> TaskResult<()> {
             endpoint: sub.endpoint,
             p256dh: sub.p256dh,
             auth: sub.auth,
+            urgency: urgency.to_string(),
+            topic: Some(topic.clone()),
         }, sub.id)).await.with_expected_err(|| "Unable to submit notification delivery task")?;
     }
 
     Ok(())
 }
 
+fn most_restrictive<'a>(a: &'a str, b: &'a str) -> &'a str {
+    fn rank(action: &str) -> u8 {
+        match action {
+            "drop" => 2,
+            "filter" => 1,
+            _ => 0
+        }
+    }
+
+    if rank(b) > rank(a) {
+        b
+    } else {
+        a
+    }
+}
+
+/// Inserts a notification, applying the recipient's notification policy first.
+/// Returns `None` if the policy drops the notification, or it already exists.
+pub fn insert_notification(
+    c: &diesel::PgConnection, notification: models::NewNotification,
+) -> QueryResult<Option<models::Notification>> {
+    let action = if notification.account != notification.cause &&
+        !notification.notification_type.starts_with("admin.") {
+        filter_action(c, &notification)?
+    } else {
+        "accept"
+    };
+
+    match action {
+        "drop" => Ok(None),
+        "filter" => c.transaction(|| {
+            let now = Utc::now().naive_utc();
+            let inserted = diesel::insert_into(crate::schema::notifications::dsl::notifications)
+                .values((&notification, crate::schema::notifications::dsl::filtered.eq(true)))
+                .on_conflict_do_nothing()
+                .get_result::<models::Notification>(c).optional()?;
+            let request = diesel::insert_into(crate::schema::notification_requests::table)
+                .values(models::NewNotificationRequest {
+                    id: uuid::Uuid::new_v4(),
+                    account: notification.account,
+                    from_account: notification.cause,
+                    last_status: notification.status,
+                    created_at: now,
+                    updated_at: now,
+                })
+                .on_conflict((
+                    crate::schema::notification_requests::dsl::account,
+                    crate::schema::notification_requests::dsl::from_account
+                ));
+            match notification.status {
+                Some(s) => request.do_update().set((
+                    crate::schema::notification_requests::dsl::last_status.eq(s),
+                    crate::schema::notification_requests::dsl::updated_at.eq(now),
+                )).execute(c)?,
+                None => request.do_update().set(
+                    crate::schema::notification_requests::dsl::updated_at.eq(now),
+                ).execute(c)?,
+            };
+            Ok(inserted)
+        }),
+        _ => diesel::insert_into(crate::schema::notifications::dsl::notifications)
+            .values(&notification)
+            .on_conflict_do_nothing()
+            .get_result::<models::Notification>(c).optional()
+    }
+}
+
+fn filter_action(c: &diesel::PgConnection, notification: &models::NewNotification) -> QueryResult<&'static str> {
+    let policy = crate::schema::notification_policies::dsl::notification_policies
+        .find(notification.account)
+        .get_result::<models::NotificationPolicy>(c).optional()?
+        .unwrap_or_else(|| models::NotificationPolicy::default_for(notification.account));
+    let has_permission = crate::schema::notification_permissions::dsl::notification_permissions
+        .filter(crate::schema::notification_permissions::dsl::account.eq(notification.account))
+        .filter(crate::schema::notification_permissions::dsl::from_account.eq(notification.cause))
+        .count().get_result::<i64>(c)? > 0;
+
+    if has_permission {
+        return Ok("accept");
+    }
+
+    let cause = crate::schema::accounts::dsl::accounts
+        .find(notification.cause)
+        .get_result::<models::Account>(c)?;
+    let status = match notification.status {
+        Some(sid) => Some(crate::schema::statuses::dsl::statuses
+            .find(sid)
+            .get_result::<models::Status>(c)?),
+        None => None
+    };
+    let is_followed = crate::schema::following::dsl::following
+        .filter(crate::schema::following::dsl::follower.eq(notification.account))
+        .filter(crate::schema::following::dsl::followee.eq(notification.cause))
+        .count().get_result::<i64>(c)? > 0;
+    let is_following = crate::schema::following::dsl::following
+        .filter(crate::schema::following::dsl::followee.eq(notification.account))
+        .filter(crate::schema::following::dsl::follower.eq(notification.cause))
+        .count().get_result::<i64>(c)? > 0;
+    let is_reply_to_account = match status.as_ref().and_then(|s| s.in_reply_to_id) {
+        Some(irt) => crate::schema::statuses::dsl::statuses
+            .filter(crate::schema::statuses::dsl::id.eq(irt))
+            .filter(crate::schema::statuses::dsl::account_id.eq(notification.account))
+            .count().get_result::<i64>(c)? > 0,
+        None => false
+    };
+
+    let mut action = "accept";
+    if !is_followed {
+        action = most_restrictive(action, &policy.for_not_following);
+    }
+    if !is_following {
+        action = most_restrictive(action, &policy.for_not_followers);
+    }
+    if cause.created_at > (Utc::now() - chrono::Duration::days(NEW_ACCOUNT_DAYS)).naive_utc() {
+        action = most_restrictive(action, &policy.for_new_accounts);
+    }
+    if notification.notification_type == "mention" && !is_followed && !is_reply_to_account &&
+        status.map(|s| !s.visible).unwrap_or(false) {
+        action = most_restrictive(action, &policy.for_private_mentions);
+    }
+
+    Ok(match action {
+        "drop" => "drop",
+        "filter" => "filter",
+        _ => "accept"
+    })
+}
+
 #[celery::task]
 pub async fn deliver_notification(notification: Notification, subscription_id: uuid::Uuid) -> TaskResult<()> {
     let config = super::config();
@@ -186,7 +332,8 @@ pub async fn deliver_notification(notification: Notification, subscription_id: u
     builder.set_payload(web_push_old::ContentEncoding::AesGcm, &payload);
 
     let message = builder.build().with_unexpected_err(|| "Unable to build WebPushMessage")?;
-    let req = build_request(message).with_unexpected_err(|| "Unable to build WebPushRequest")?;
+    let req = build_request(message, &notification.urgency, notification.topic.as_deref())
+        .with_unexpected_err(|| "Unable to build WebPushRequest")?;
     let res = crate::AS_CLIENT.execute(req).await.with_expected_err(|| "Unable to execute WebPushRequest")?;
     let status = res.status();
     if status.is_success() {
@@ -208,11 +355,17 @@ pub async fn deliver_notification(notification: Notification, subscription_id: u
     return Err(TaskError::ExpectedError(format!("Unable to send WebPushMessage: {}", status)));
 }
 
-fn build_request(message: web_push_old::WebPushMessage) -> reqwest::Result<reqwest::Request> {
+fn build_request(
+    message: web_push_old::WebPushMessage, urgency: &str, topic: Option<&str>
+) -> reqwest::Result<reqwest::Request> {
     let mut builder = crate::AS_CLIENT.post(message.endpoint.to_string())
-        .header("Urgency", "normal")
+        .header("Urgency", urgency)
         .header("TTL", format!("{}", message.ttl).as_bytes());
 
+    if let Some(topic) = topic {
+        builder = builder.header("Topic", topic);
+    }
+
     if let Some(payload) = message.payload {
         builder = builder
             .header("Content-Encoding", payload.content_encoding)
@@ -54,17 +54,6 @@ pub async fn process_follow(
 
         let notification = tokio::task::block_in_place(|| -> TaskResult<_> {
             let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
-            let notification = diesel::insert_into(crate::schema::notifications::dsl::notifications)
-                .values(models::NewNotification {
-                    id: uuid::Uuid::new_v4(),
-                    notification_type: "follow".to_string(),
-                    account: followed_account.id,
-                    cause: account.id,
-                    status: None,
-                    created_at: created_at.naive_utc(),
-                })
-                .on_conflict_do_nothing()
-                .get_result::<models::Notification>(&c).with_expected_err(|| "Unable to insert notification")?;
             diesel::insert_into(crate::schema::following::dsl::following)
                 .values(models::NewFollowing {
                     id: uuid::Uuid::new_v4(),
@@ -77,10 +66,20 @@ pub async fn process_follow(
                 })
                 .on_conflict_do_nothing()
                 .execute(&c).with_expected_err(|| "Unable to insert following")?;
-            Ok(notification)
+            super::notifications::insert_notification(&c, models::NewNotification {
+                id: uuid::Uuid::new_v4(),
+                notification_type: "follow".to_string(),
+                account: followed_account.id,
+                cause: account.id,
+                status: None,
+                report: None,
+                created_at: created_at.naive_utc(),
+            }).with_expected_err(|| "Unable to insert notification")
         })?;
 
-        config.celery.send_task(super::notifications::notify::new(notification)).await.with_expected_err(|| "Unable to submit notification task")?;
+        if let Some(notification) = notification {
+            config.celery.send_task(super::notifications::notify::new(notification)).await.with_expected_err(|| "Unable to submit notification task")?;
+        }
     } else {
         info!("Follow activity \"{}\" has non-local object {:?}", activity.id_or_default(), object);
     }
@@ -151,6 +150,29 @@ pub async fn follow_account(
                 instrument: None,
             }), inbox, a);
             celery.send_task(task).await.with_expected_err(|| "Unable to submit delivery task")?;
+        } else if followee.local {
+            // Nothing to federate between two local accounts, so accept straight away
+            // unless the followee approves followers manually
+            let db = config.db.clone();
+            let notification = tokio::task::block_in_place(|| -> TaskResult<_> {
+                let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+                diesel::update(crate::schema::following::dsl::following.find(following_id))
+                    .set(crate::schema::following::dsl::pending.eq(followee.locked))
+                    .execute(&c).with_expected_err(|| "Unable to update following")?;
+                super::notifications::insert_notification(&c, models::NewNotification {
+                    id: uuid::Uuid::new_v4(),
+                    notification_type: if followee.locked { "follow_request" } else { "follow" }.to_string(),
+                    account: followee.id,
+                    cause: follower.id,
+                    status: None,
+                    report: None,
+                    created_at: created_at.naive_utc(),
+                }).with_expected_err(|| "Unable to insert notification")
+            })?;
+            if let Some(notification) = notification {
+                config.celery.send_task(super::notifications::notify::new(notification))
+                    .await.with_expected_err(|| "Unable to submit notification task")?;
+            }
         } else {
             warn!("Account \"{}\" has no inbox URL", followee.id);
         }
@@ -214,6 +236,12 @@ pub async fn unfollow_account(
                     .and(crate::schema::following::dsl::followee.eq(followee.id))
             ))
             .execute(&c).with_expected_err(|| "Unable to delete following")?;
+        diesel::delete(crate::schema::endorsements::dsl::endorsements
+            .filter(
+                crate::schema::endorsements::dsl::account.eq(follower.id)
+                    .and(crate::schema::endorsements::dsl::endorsed.eq(followee.id))
+            ))
+            .execute(&c).with_expected_err(|| "Unable to delete endorsement")?;
         Ok(())
     })?;
 
@@ -240,6 +268,9 @@ pub async fn process_accept_follow(
             return Ok(());
         }
     };
+    if actor.id() == Some(&format!("https://{}/as/system", config.uri)) {
+        return super::relays::process_relay_response(activity, account, true).await;
+    }
     let following_account = match super::accounts::find_account(actor.clone(), false).await? {
         Some(a) => a,
         None => {
@@ -297,6 +328,9 @@ pub async fn process_reject_follow(
             return Ok(());
         }
     };
+    if actor.id() == Some(&format!("https://{}/as/system", config.uri)) {
+        return super::relays::process_relay_response(activity, account, false).await;
+    }
     let following_account = match super::accounts::find_account(actor.clone(), false).await? {
         Some(a) => a,
         None => {
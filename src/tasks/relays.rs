@@ -0,0 +1,220 @@
+use crate::models;
+use crate::views::activity_streams;
+use crate::views::activity_streams::ObjectID;
+use celery::prelude::*;
+use chrono::prelude::*;
+use diesel::prelude::*;
+
+const PUBLIC: &str = "https://www.w3.org/ns/activitystreams#Public";
+
+pub fn as_render_relay_follow(relay: &models::Relay) -> activity_streams::Object {
+    let config = super::config();
+
+    activity_streams::Object::Follow(activity_streams::ActivityCommon {
+        common: activity_streams::ObjectCommon {
+            id: Some(relay.follow_id(&config.uri)),
+            published: Some(Utc.from_utc_datetime(&relay.created_at)),
+            ..Default::default()
+        },
+        actor: Some(activity_streams::ReferenceOrObject::Reference(format!("https://{}/as/system", config.uri))),
+        object: Some(activity_streams::ReferenceOrObject::Reference(PUBLIC.to_string())),
+        target: None,
+        result: None,
+        origin: None,
+        instrument: None,
+    })
+}
+
+pub fn list_relays(c: &diesel::PgConnection) -> QueryResult<Vec<models::Relay>> {
+    crate::schema::relays::dsl::relays
+        .order_by(crate::schema::relays::dsl::iid.asc())
+        .get_results(c)
+}
+
+#[celery::task]
+pub async fn subscribe_relay(inbox: String) -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+
+    reqwest::Url::parse(&inbox).with_expected_err(|| "Invalid relay inbox URL")?;
+
+    let relay = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        diesel::insert_into(crate::schema::relays::table)
+            .values(models::NewRelay {
+                id: uuid::Uuid::new_v4(),
+                inbox_url: inbox.clone(),
+                state: "pending".to_string(),
+                created_at: Utc::now().naive_utc(),
+            })
+            .on_conflict(crate::schema::relays::dsl::inbox_url)
+            .do_update()
+            .set(crate::schema::relays::dsl::state.eq("pending"))
+            .get_result::<models::Relay>(&c).with_expected_err(|| "Unable to insert relay")
+    })?;
+
+    config.celery.send_task(
+        super::delivery::deliver_system_object::new(as_render_relay_follow(&relay), relay.inbox_url)
+    ).await.with_expected_err(|| "Unable to submit delivery task")?;
+
+    Ok(())
+}
+
+#[celery::task]
+pub async fn unsubscribe_relay(inbox: String) -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+
+    let relay = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        diesel::delete(crate::schema::relays::dsl::relays.filter(
+            crate::schema::relays::dsl::inbox_url.eq(&inbox)
+        )).get_result::<models::Relay>(&c).optional().with_expected_err(|| "Unable to delete relay")
+    })?;
+
+    let relay = match relay {
+        Some(r) => r,
+        None => {
+            warn!("Not subscribed to relay \"{}\"", inbox);
+            return Ok(());
+        }
+    };
+
+    let activity = activity_streams::Object::Undo(activity_streams::ActivityCommon {
+        common: activity_streams::ObjectCommon {
+            id: Some(format!("https://{}/as/transient/{}", config.uri, uuid::Uuid::new_v4())),
+            ..Default::default()
+        },
+        actor: Some(activity_streams::ReferenceOrObject::Reference(format!("https://{}/as/system", config.uri))),
+        object: Some(activity_streams::ReferenceOrObject::Object(Box::new(
+            activity_streams::ObjectOrLink::Object(as_render_relay_follow(&relay))
+        ))),
+        target: None,
+        result: None,
+        origin: None,
+        instrument: None,
+    });
+
+    config.celery.send_task(
+        super::delivery::deliver_system_object::new(activity, relay.inbox_url)
+    ).await.with_expected_err(|| "Unable to submit delivery task")?;
+
+    Ok(())
+}
+
+/// Records a relay's answer to the system actor's follow of the public collection
+pub async fn process_relay_response(
+    activity: activity_streams::ActivityCommon, account: models::Account, accepted: bool,
+) -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+
+    let follow_id = activity.id().map(|i| i.to_string());
+    let state = if accepted { "accepted" } else { "rejected" };
+
+    let updated = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        let relays = crate::schema::relays::dsl::relays
+            .get_results::<models::Relay>(&c).with_expected_err(|| "Unable to fetch relays")?;
+        // The follow ID isn't secret, so the responder also has to be the relay we subscribed to
+        let is_relay = |r: &models::Relay| {
+            account.inbox_url.as_deref() == Some(&r.inbox_url) ||
+                account.shared_inbox_url.as_deref() == Some(&r.inbox_url) ||
+                (r.actor.is_some() && r.actor == account.actor)
+        };
+        let relay = relays.iter().filter(|r| is_relay(r))
+            .find(|r| follow_id.as_deref() == Some(&r.follow_id(&config.uri)))
+            .or_else(|| relays.iter().find(|r| is_relay(r)));
+        match relay {
+            Some(r) => {
+                diesel::update(crate::schema::relays::dsl::relays.find(r.id))
+                    .set((
+                        crate::schema::relays::dsl::state.eq(state),
+                        crate::schema::relays::dsl::actor.eq(&account.actor),
+                    ))
+                    .execute(&c).with_expected_err(|| "Unable to update relay")?;
+                Ok(true)
+            }
+            None => Ok(false)
+        }
+    })?;
+
+    if updated {
+        info!("Relay \"{}\" {} our subscription", account.actor.as_deref().unwrap_or_default(), state);
+    } else {
+        warn!("Follow activity \"{}\" does not match any relay subscription", activity.id_or_default());
+    }
+
+    Ok(())
+}
+
+/// Returns the relay subscription for an account, if the account is a relay we're subscribed to
+pub async fn find_relay(account: &models::Account) -> TaskResult<Option<models::Relay>> {
+    let db = super::config().db;
+
+    let actor = match &account.actor {
+        Some(a) => a,
+        None => return Ok(None)
+    };
+
+    tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        crate::schema::relays::dsl::relays.filter(
+            crate::schema::relays::dsl::actor.eq(actor)
+        ).filter(
+            crate::schema::relays::dsl::state.eq("accepted")
+        ).first::<models::Relay>(&c).optional().with_expected_err(|| "Unable to fetch relay")
+    })
+}
+
+/// Fetches a status passed on by a relay from its origin, adding it to the public timeline
+#[celery::task]
+pub async fn ingest_relayed_status(
+    object: activity_streams::ReferenceOrObject<activity_streams::ObjectOrLink>,
+) -> TaskResult<()> {
+    // Some relays wrap the original activity rather than passing on the object itself
+    let id = match &object {
+        activity_streams::ReferenceOrObject::Object(o) => match o.as_ref() {
+            activity_streams::ObjectOrLink::Object(activity_streams::Object::Create(a)) |
+            activity_streams::ObjectOrLink::Object(activity_streams::Object::Announce(a)) =>
+                a.object.as_ref().and_then(|o| o.id()),
+            _ => object.id()
+        },
+        activity_streams::ReferenceOrObject::Reference(_) => object.id()
+    };
+
+    let id = match id {
+        Some(id) => id.to_string(),
+        None => {
+            warn!("Relayed object has no ID: {:?}", object);
+            return Ok(());
+        }
+    };
+
+    // Always dereference the object so a relay can't speak on behalf of the original author
+    super::statuses::get_status(activity_streams::ReferenceOrObject::Reference(id)).await?;
+
+    Ok(())
+}
+
+/// Passes a local public activity on to every relay that has accepted our subscription
+pub async fn deliver_to_relays(object: activity_streams::Object, account: models::Account) -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+
+    let inboxes = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        crate::schema::relays::dsl::relays.filter(
+            crate::schema::relays::dsl::state.eq("accepted")
+        ).select(crate::schema::relays::dsl::inbox_url)
+            .get_results::<String>(&c).with_expected_err(|| "Unable to fetch relays")
+    })?;
+
+    for inbox in inboxes {
+        config.celery.send_task(
+            super::delivery::deliver_object::new(object.clone(), inbox, account.clone())
+        ).await.with_expected_err(|| "Unable to submit delivery task")?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,164 @@
+use crate::models;
+use crate::views::activity_streams;
+use celery::prelude::*;
+use chrono::prelude::*;
+use diesel::prelude::*;
+use crate::views::activity_streams::ObjectID;
+
+#[celery::task]
+pub async fn process_report(report: models::Report) -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+
+    let (target_account, statuses, moderators) = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        let target_account = crate::schema::accounts::dsl::accounts.find(report.target_account)
+            .get_result::<models::Account>(&c).with_expected_err(|| "Unable to get account")?;
+        let statuses = crate::schema::statuses::dsl::statuses.filter(
+            crate::schema::statuses::dsl::id.eq_any(&report.status_ids)
+        ).get_results::<models::Status>(&c).with_expected_err(|| "Unable to get statuses")?;
+        let moderators = crate::schema::accounts::dsl::accounts
+            .filter(crate::schema::accounts::dsl::local.eq(true))
+            .filter(crate::schema::accounts::dsl::moderator.eq(true))
+            .filter(crate::schema::accounts::dsl::deleted_at.is_null())
+            .filter(crate::schema::accounts::dsl::id.ne(report.target_account))
+            .select(crate::schema::accounts::dsl::id)
+            .get_results::<uuid::Uuid>(&c).with_expected_err(|| "Unable to get moderators")?;
+        Ok((target_account, statuses, moderators))
+    })?;
+
+    for moderator in moderators {
+        let notification = tokio::task::block_in_place(|| -> TaskResult<_> {
+            let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+            diesel::insert_into(crate::schema::notifications::dsl::notifications)
+                .values(models::NewNotification {
+                    id: uuid::Uuid::new_v4(),
+                    notification_type: "admin.report".to_string(),
+                    account: moderator,
+                    cause: report.account,
+                    status: None,
+                    report: Some(report.id),
+                    created_at: report.created_at,
+                })
+                .get_result::<models::Notification>(&c).with_expected_err(|| "Unable to insert notification")
+        })?;
+        config.celery.send_task(super::notifications::notify::new(notification))
+            .await.with_expected_err(|| "Unable to submit notification task")?;
+    }
+
+    if report.forwarded && !target_account.local {
+        let inbox = match target_account.shared_inbox_url.as_ref().or(target_account.inbox_url.as_ref()) {
+            Some(i) => i.clone(),
+            None => {
+                warn!("Account \"{}\" has no inbox URL, not forwarding report", target_account.id);
+                return Ok(());
+            }
+        };
+
+        let mut objects = vec![activity_streams::ReferenceOrObject::Reference(target_account.actor_id(&config.uri))];
+        objects.extend(statuses.iter().map(|s| activity_streams::ReferenceOrObject::Reference(s.url(&config.uri))));
+
+        let flag = activity_streams::Object::Flag(activity_streams::Flag {
+            common: activity_streams::ObjectCommon {
+                id: Some(format!("https://{}/as/transient/{}", config.uri, report.id)),
+                content: Some(report.comment.clone()),
+                published: Some(Utc.from_utc_datetime(&report.created_at)),
+                ..Default::default()
+            },
+            actor: Some(activity_streams::ReferenceOrObject::Reference(format!("https://{}/as/system", config.uri))),
+            object: activity_streams::Pluralisable::List(objects),
+        });
+
+        config.celery.send_task(
+            super::delivery::deliver_system_object::new(flag, inbox)
+        ).await.with_expected_err(|| "Unable to submit delivery task")?;
+    }
+
+    Ok(())
+}
+
+#[celery::task]
+pub async fn process_flag(
+    activity: activity_streams::Flag, account: models::Account,
+) -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+    let local_account_regex = regex::Regex::new(&format!("^https://{}/as/users/(?P<id>[^/#]+)$", config.uri)).unwrap();
+    let local_status_regex = regex::Regex::new(&format!("^https://{}/as/status/(?P<id>[^/#]+)$", config.uri)).unwrap();
+
+    let mut target_accounts = vec![];
+    let mut status_ids = vec![];
+    for object in activity.object.as_slice() {
+        let id = match object.id() {
+            Some(id) => id,
+            None => continue
+        };
+        if let Some(id) = local_account_regex.captures(id)
+            .and_then(|c| uuid::Uuid::parse_str(c.name("id").unwrap().as_str()).ok()) {
+            target_accounts.push(id);
+        } else if let Some(id) = local_status_regex.captures(id)
+            .and_then(|c| uuid::Uuid::parse_str(c.name("id").unwrap().as_str()).ok()) {
+            status_ids.push(id);
+        }
+    }
+
+    let statuses = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        crate::schema::statuses::dsl::statuses.filter(
+            crate::schema::statuses::dsl::id.eq_any(&status_ids)
+        ).filter(
+            crate::schema::statuses::dsl::local.eq(true)
+        ).get_results::<models::Status>(&c).with_expected_err(|| "Unable to get statuses")
+    })?;
+
+    let target_account = match target_accounts.first().copied()
+        .or_else(|| statuses.first().map(|s| s.account_id)) {
+        Some(a) => a,
+        None => {
+            warn!("Flag activity \"{}\" does not reference any local account", activity.id_or_default());
+            return Ok(());
+        }
+    };
+    let status_ids = statuses.into_iter()
+        .filter(|s| s.account_id == target_account)
+        .map(|s| s.id)
+        .collect::<Vec<_>>();
+
+    let now = Utc::now().naive_utc();
+    let report = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        let target_is_local = crate::schema::accounts::dsl::accounts.find(target_account)
+            .filter(crate::schema::accounts::dsl::local.eq(true))
+            .count().get_result::<i64>(&c).with_expected_err(|| "Unable to get account")? > 0;
+        if !target_is_local {
+            return Ok(None);
+        }
+        diesel::insert_into(crate::schema::reports::table)
+            .values(models::NewReport {
+                id: uuid::Uuid::new_v4(),
+                account: account.id,
+                target_account,
+                status_ids,
+                category: "other".to_string(),
+                comment: activity.common.content.clone().unwrap_or_default(),
+                forwarded: false,
+                url: activity.common.id.clone(),
+                created_at: activity.common.published.map(|p| p.naive_utc()).unwrap_or(now),
+                updated_at: now,
+            })
+            .on_conflict_do_nothing()
+            .get_result::<models::Report>(&c).optional().with_expected_err(|| "Unable to insert report")
+    })?;
+
+    match report {
+        Some(report) => {
+            config.celery.send_task(process_report::new(report))
+                .await.with_expected_err(|| "Unable to submit report task")?;
+        }
+        None => {
+            info!("Flag activity \"{}\" is a duplicate or targets a non-local account", activity.id_or_default());
+        }
+    }
+
+    Ok(())
+}
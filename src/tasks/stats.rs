@@ -0,0 +1,187 @@
+use celery::prelude::*;
+use chrono::prelude::*;
+use diesel::prelude::*;
+use crate::models;
+
+/// Number of weekly buckets reported by the instance activity endpoint
+pub const ACTIVITY_WEEKS: i64 = 12;
+/// Windows used for monthly and half-yearly active user counts
+const ACTIVE_MONTH_DAYS: i64 = 30;
+const ACTIVE_HALF_YEAR_DAYS: i64 = 180;
+
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+#[derive(Default)]
+struct WeekActivity {
+    statuses: i64,
+    logins: std::collections::HashSet<String>,
+    registrations: i64,
+}
+
+fn compute_stats(c: &diesel::PgConnection, now: DateTime<Utc>) -> QueryResult<(
+    models::InstanceStats, Vec<models::InstancePeer>, Vec<models::InstanceActivity>
+)> {
+    let now = now.naive_utc();
+    let this_week = week_start(now.date());
+    let activity_since = (this_week - chrono::Duration::weeks(ACTIVITY_WEEKS - 1)).and_hms_opt(0, 0, 0).unwrap();
+    let month_since = now - chrono::Duration::days(ACTIVE_MONTH_DAYS);
+    let half_year_since = now - chrono::Duration::days(ACTIVE_HALF_YEAR_DAYS);
+    let since = std::cmp::min(activity_since, half_year_since);
+
+    let local_accounts: Vec<(uuid::Uuid, Option<String>, chrono::NaiveDateTime, Option<chrono::NaiveDateTime>)> =
+        crate::schema::accounts::dsl::accounts
+            .filter(crate::schema::accounts::dsl::local.eq(true))
+            .filter(crate::schema::accounts::dsl::deleted_at.is_null())
+            .select((
+                crate::schema::accounts::dsl::id,
+                crate::schema::accounts::dsl::owned_by,
+                crate::schema::accounts::dsl::created_at,
+                crate::schema::accounts::dsl::suspended_at,
+            ))
+            .get_results(c)?;
+
+    let status_count = crate::schema::statuses::dsl::statuses
+        .filter(crate::schema::statuses::dsl::local.eq(true))
+        .filter(crate::schema::statuses::dsl::deleted_at.is_null())
+        .filter(crate::schema::statuses::dsl::boost_of_id.is_null())
+        .filter(crate::schema::statuses::dsl::boost_of_url.is_null())
+        .count().get_result::<i64>(c)?;
+    let local_comments = crate::schema::statuses::dsl::statuses
+        .filter(crate::schema::statuses::dsl::local.eq(true))
+        .filter(crate::schema::statuses::dsl::deleted_at.is_null())
+        .filter(crate::schema::statuses::dsl::boost_of_id.is_null())
+        .filter(crate::schema::statuses::dsl::boost_of_url.is_null())
+        .filter(crate::schema::statuses::dsl::in_reply_to_id.is_not_null().or(
+            crate::schema::statuses::dsl::in_reply_to_url.is_not_null()
+        ))
+        .count().get_result::<i64>(c)?;
+
+    let remote_actors: Vec<Option<String>> = crate::schema::accounts::dsl::accounts
+        .filter(crate::schema::accounts::dsl::local.eq(false))
+        .select(crate::schema::accounts::dsl::actor)
+        .get_results(c)?;
+
+    let recent_statuses: Vec<(uuid::Uuid, chrono::NaiveDateTime)> = crate::schema::statuses::dsl::statuses
+        .filter(crate::schema::statuses::dsl::local.eq(true))
+        .filter(crate::schema::statuses::dsl::boost_of_id.is_null())
+        .filter(crate::schema::statuses::dsl::boost_of_url.is_null())
+        .filter(crate::schema::statuses::dsl::created_at.ge(since))
+        .select((crate::schema::statuses::dsl::account_id, crate::schema::statuses::dsl::created_at))
+        .get_results(c)?;
+
+    let recent_logins: Vec<(String, Option<uuid::Uuid>, chrono::NaiveDateTime)> = crate::schema::oauth_token::dsl::oauth_token
+        .filter(crate::schema::oauth_token::dsl::time.ge(since))
+        .select((
+            crate::schema::oauth_token::dsl::user_id,
+            crate::schema::oauth_token::dsl::account_id,
+            crate::schema::oauth_token::dsl::time,
+        ))
+        .get_results(c)?;
+
+    let mut peers = remote_actors.into_iter()
+        .filter_map(|a| reqwest::Url::parse(&a?).ok()?.host_str().map(|h| h.to_string()))
+        .collect::<Vec<_>>();
+    peers.sort();
+    peers.dedup();
+
+    let mut weeks = std::collections::BTreeMap::<NaiveDate, WeekActivity>::new();
+    for i in 0..ACTIVITY_WEEKS {
+        weeks.insert(this_week - chrono::Duration::weeks(i), WeekActivity::default());
+    }
+
+    let owners = local_accounts.iter()
+        .filter_map(|(id, owned_by, _, _)| Some((owned_by.clone()?, *id)))
+        .collect::<std::collections::HashMap<_, _>>();
+    let mut active_month = std::collections::HashSet::new();
+    let mut active_half_year = std::collections::HashSet::new();
+    let mut mark_active = |account: uuid::Uuid, at: chrono::NaiveDateTime| {
+        if at >= month_since {
+            active_month.insert(account);
+        }
+        if at >= half_year_since {
+            active_half_year.insert(account);
+        }
+    };
+
+    for (account, created_at) in &recent_statuses {
+        if let Some(week) = weeks.get_mut(&week_start(created_at.date())) {
+            week.statuses += 1;
+        }
+        mark_active(*account, *created_at);
+    }
+    for (user_id, account_id, time) in &recent_logins {
+        if let Some(week) = weeks.get_mut(&week_start(time.date())) {
+            week.logins.insert(user_id.clone());
+        }
+        if let Some(account) = account_id.as_ref().or_else(|| owners.get(user_id)) {
+            mark_active(*account, *time);
+        }
+    }
+    for (_, _, created_at, _) in &local_accounts {
+        if let Some(week) = weeks.get_mut(&week_start(created_at.date())) {
+            week.registrations += 1;
+        }
+    }
+
+    let stats = models::InstanceStats {
+        id: true,
+        user_count: local_accounts.iter().filter(|a| a.3.is_none()).count() as i64,
+        status_count,
+        local_comments,
+        domain_count: peers.len() as i64,
+        active_month: active_month.len() as i64,
+        active_half_year: active_half_year.len() as i64,
+        updated_at: now,
+    };
+    let peers = peers.into_iter().map(|domain| models::InstancePeer { domain }).collect();
+    let activity = weeks.into_iter().map(|(week, a)| models::InstanceActivity {
+        week,
+        statuses: a.statuses,
+        logins: a.logins.len() as i64,
+        registrations: a.registrations,
+    }).collect();
+
+    Ok((stats, peers, activity))
+}
+
+#[celery::task]
+pub async fn update_instance_stats() -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+    let now = Utc::now();
+
+    let stats = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        let (stats, peers, activity) = compute_stats(&c, now)
+            .with_expected_err(|| "Unable to compute instance statistics")?;
+
+        c.transaction::<_, diesel::result::Error, _>(|| {
+            diesel::delete(crate::schema::instance_stats::table).execute(&c)?;
+            diesel::insert_into(crate::schema::instance_stats::table)
+                .values(&stats)
+                .execute(&c)?;
+
+            diesel::delete(crate::schema::instance_peers::table).execute(&c)?;
+            diesel::insert_into(crate::schema::instance_peers::table)
+                .values(&peers)
+                .execute(&c)?;
+
+            diesel::delete(crate::schema::instance_activity::table).execute(&c)?;
+            diesel::insert_into(crate::schema::instance_activity::table)
+                .values(&activity)
+                .execute(&c)?;
+            Ok(())
+        }).with_expected_err(|| "Unable to update instance statistics")?;
+
+        Ok(stats)
+    })?;
+
+    info!(
+        "Instance statistics updated, {} users, {} statuses, {} peers",
+        stats.user_count, stats.status_count, stats.domain_count
+    );
+
+    Ok(())
+}
@@ -6,6 +6,55 @@ use crate::tasks::{fetch_object, resolve_object, resolve_object_or_link, resolve
 use crate::views::activity_streams::{self, ObjectID};
 use futures::StreamExt;
 
+pub fn normalise_hashtag(name: &str) -> String {
+    name.trim().trim_start_matches('#').to_lowercase()
+}
+
+pub fn extract_hashtags(text: &str) -> Vec<String> {
+    let mut tags = crate::HASHTAG_RE.captures_iter(text)
+        .map(|c| normalise_hashtag(c.name("tag").unwrap().as_str()))
+        .collect::<Vec<_>>();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+pub(crate) fn set_status_tags(
+    c: &diesel::PgConnection, status_id: uuid::Uuid, names: &[String],
+) -> QueryResult<()> {
+    diesel::delete(crate::schema::status_tags::table.filter(
+        crate::schema::status_tags::dsl::status.eq(status_id)
+    )).execute(c)?;
+
+    if names.is_empty() {
+        return Ok(());
+    }
+
+    diesel::insert_into(crate::schema::tags::table)
+        .values(names.iter().map(|n| models::NewTag {
+            id: uuid::Uuid::new_v4(),
+            name: n.clone(),
+        }).collect::<Vec<_>>())
+        .on_conflict(crate::schema::tags::dsl::name)
+        .do_nothing()
+        .execute(c)?;
+
+    let tag_ids: Vec<uuid::Uuid> = crate::schema::tags::dsl::tags.filter(
+        crate::schema::tags::dsl::name.eq_any(names)
+    ).select(crate::schema::tags::dsl::id).get_results(c)?;
+
+    diesel::insert_into(crate::schema::status_tags::table)
+        .values(tag_ids.into_iter().map(|t| models::StatusTag {
+            id: uuid::Uuid::new_v4(),
+            status: status_id,
+            tag: t,
+        }).collect::<Vec<_>>())
+        .on_conflict_do_nothing()
+        .execute(c)?;
+
+    Ok(())
+}
+
 pub async fn get_status(status: activity_streams::ReferenceOrObject<activity_streams::ObjectOrLink>) -> TaskResult<models::Status> {
     let config = super::config();
     let db = config.db.clone();
@@ -378,6 +427,13 @@ async fn _update_status(
             };
             let created_at = o.published.unwrap_or_else(|| Utc::now());
 
+            let mut hashtags = tags.iter().filter_map(|t| match t {
+                activity_streams::Object::Hashtag(h) => h.name.as_deref().map(normalise_hashtag),
+                _ => None
+            }).filter(|t| !t.is_empty()).collect::<Vec<_>>();
+            hashtags.sort();
+            hashtags.dedup();
+
             let mut mentions = vec![];
             for t in &tags {
                 if let activity_streams::Object::Mention(m) = t {
@@ -395,6 +451,7 @@ async fn _update_status(
                 }
             }
 
+            let previous_edited_at = status.as_ref().and_then(|s| s.edited_at);
             let new_status = match status {
                 Some(mut existing_status) => {
                     if existing_status.local {
@@ -503,8 +560,9 @@ async fn _update_status(
                     diesel::insert_into(crate::schema::status_mentions::table)
                         .values(&mentions)
                         .execute(&c)?;
-                    Ok(())
-                }).with_expected_err(|| "Unable to update status audiences and mentions")
+
+                    set_status_tags(&c, new_status.id, &hashtags)
+                }).with_expected_err(|| "Unable to update status audiences, mentions and tags")
             })?;
 
             if is_new_status {
@@ -515,26 +573,27 @@ async fn _update_status(
                     config.celery.send_task(get_replies::new(replies))
                         .await.with_expected_err(|| "Unable to send task")?;
                 }
+                super::groups::dispatch_to_groups(&new_status).await?;
 
                 for aud in &audiences.audiences {
                     if aud.mention {
                         if let Some(account_id) = aud.account {
                             let notification = tokio::task::block_in_place(|| -> TaskResult<_> {
                                 let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
-                                diesel::insert_into(crate::schema::notifications::dsl::notifications)
-                                    .values(models::NewNotification {
-                                        id: uuid::Uuid::new_v4(),
-                                        notification_type: "mention".to_string(),
-                                        account: account_id,
-                                        cause: account.id,
-                                        status: Some(new_status.id),
-                                        created_at: created_at.naive_utc(),
-                                    })
-                                    .on_conflict_do_nothing()
-                                    .get_result::<models::Notification>(&c).with_expected_err(|| "Unable to insert notification")
+                                super::notifications::insert_notification(&c, models::NewNotification {
+                                    id: uuid::Uuid::new_v4(),
+                                    notification_type: "mention".to_string(),
+                                    account: account_id,
+                                    cause: account.id,
+                                    status: Some(new_status.id),
+                                    report: None,
+                                    created_at: created_at.naive_utc(),
+                                }).with_expected_err(|| "Unable to insert notification")
                             })?;
-                            config.celery.send_task(super::notifications::notify::new(notification))
-                                .await.with_expected_err(|| "Unable to submit notification task")?;
+                            if let Some(notification) = notification {
+                                config.celery.send_task(super::notifications::notify::new(notification))
+                                    .await.with_expected_err(|| "Unable to submit notification task")?;
+                            }
                         }
                     }
                 }
@@ -542,18 +601,47 @@ async fn _update_status(
                 for mention in &mentions {
                     let notification = tokio::task::block_in_place(|| -> TaskResult<_> {
                         let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
-                        diesel::insert_into(crate::schema::notifications::dsl::notifications)
-                            .values(models::NewNotification {
-                                id: uuid::Uuid::new_v4(),
-                                notification_type: "mention".to_string(),
-                                account: mention.account,
-                                cause: account.id,
-                                status: Some(new_status.id),
-                                created_at: created_at.naive_utc(),
-                            })
-                            .on_conflict_do_nothing()
-                            .get_result::<models::Notification>(&c).with_expected_err(|| "Unable to insert notification")
+                        super::notifications::insert_notification(&c, models::NewNotification {
+                            id: uuid::Uuid::new_v4(),
+                            notification_type: "mention".to_string(),
+                            account: mention.account,
+                            cause: account.id,
+                            status: Some(new_status.id),
+                            report: None,
+                            created_at: created_at.naive_utc(),
+                        }).with_expected_err(|| "Unable to insert notification")
                     })?;
+                    if let Some(notification) = notification {
+                        config.celery.send_task(super::notifications::notify::new(notification))
+                            .await.with_expected_err(|| "Unable to submit notification task")?;
+                    }
+                }
+            } else if new_status.edited_at.is_some() && new_status.edited_at != previous_edited_at {
+                let notifications = tokio::task::block_in_place(|| -> TaskResult<_> {
+                    let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+                    let boosters = crate::schema::statuses::dsl::statuses
+                        .filter(crate::schema::statuses::dsl::boost_of_id.eq(new_status.id))
+                        .filter(crate::schema::statuses::dsl::deleted_at.is_null())
+                        .inner_join(crate::schema::accounts::table.on(
+                            crate::schema::accounts::dsl::id.eq(crate::schema::statuses::dsl::account_id)
+                        ))
+                        .filter(crate::schema::accounts::dsl::local.eq(true))
+                        .select(crate::schema::accounts::dsl::id)
+                        .distinct()
+                        .get_results::<uuid::Uuid>(&c).with_expected_err(|| "Unable to fetch boosters")?;
+                    boosters.into_iter().map(|booster| {
+                        super::notifications::insert_notification(&c, models::NewNotification {
+                            id: uuid::Uuid::new_v4(),
+                            notification_type: "update".to_string(),
+                            account: booster,
+                            cause: account.id,
+                            status: Some(new_status.id),
+                            report: None,
+                            created_at: Utc::now().naive_utc(),
+                        }).with_expected_err(|| "Unable to insert notification")
+                    }).collect::<TaskResult<Vec<_>>>()
+                })?;
+                for notification in notifications.into_iter().flatten() {
                     config.celery.send_task(super::notifications::notify::new(notification))
                         .await.with_expected_err(|| "Unable to submit notification task")?;
                 }
@@ -604,6 +692,163 @@ pub async fn get_replies(
     Ok(())
 }
 
+/// How long before the same thread will be backfilled again
+pub const THREAD_BACKFILL_INTERVAL_MINUTES: i64 = 30;
+/// Most remote objects a single thread backfill will fetch
+const THREAD_BACKFILL_MAX_FETCHES: usize = 100;
+/// How far up a thread a backfill will follow `inReplyTo`
+const THREAD_BACKFILL_MAX_DEPTH: usize = 40;
+
+/// Fetches the parts of a remote thread we haven't seen, walking up to its root and then down through the replies
+/// collections of each status, and any FEP-7888 context collection.
+#[celery::task]
+pub async fn backfill_thread(status: models::Status) -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+    let mut fetches = 0;
+
+    // Fill in any ancestors that couldn't be fetched when their replies arrived
+    let mut root = status.clone();
+    for _ in 0..THREAD_BACKFILL_MAX_DEPTH {
+        if let Some(parent_id) = root.in_reply_to_id {
+            match tokio::task::block_in_place(|| -> TaskResult<_> {
+                let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+                crate::schema::statuses::dsl::statuses.find(parent_id)
+                    .get_result::<models::Status>(&c).optional().with_expected_err(|| "Unable to fetch status")
+            })? {
+                Some(parent) => root = parent,
+                None => break
+            }
+        } else if let Some(parent_url) = root.in_reply_to_url.clone() {
+            fetches += 1;
+            let parent = match get_status(activity_streams::ReferenceOrObject::Reference(parent_url)).await {
+                Ok(p) => p,
+                Err(e) => {
+                    warn!("Unable to fetch ancestor of \"{}\": {}", root.url, e);
+                    break;
+                }
+            };
+            let child_id = root.id;
+            tokio::task::block_in_place(|| -> TaskResult<_> {
+                let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+                diesel::update(crate::schema::statuses::dsl::statuses.find(child_id))
+                    .set((
+                        crate::schema::statuses::dsl::in_reply_to_id.eq(Some(parent.id)),
+                        crate::schema::statuses::dsl::in_reply_to_url.eq(None::<String>),
+                    ))
+                    .execute(&c).with_expected_err(|| "Unable to update status")
+            })?;
+            root = parent;
+        } else {
+            break;
+        }
+    }
+
+    let local_prefix = format!("https://{}/", config.uri);
+    let mut seen = std::collections::HashSet::new();
+    let mut queue = std::collections::VecDeque::new();
+    queue.push_back(root.url.clone());
+    queue.push_back(status.url.clone());
+    let mut context_crawled = false;
+
+    while let Some(url) = queue.pop_front() {
+        if fetches >= THREAD_BACKFILL_MAX_FETCHES {
+            info!("Stopping backfill of \"{}\" after {} fetches", status.url, fetches);
+            break;
+        }
+        if url.starts_with(&local_prefix) || !seen.insert(url.clone()) {
+            continue;
+        }
+
+        fetches += 1;
+        let object = match fetch_object::<activity_streams::Object, _>(&url).await {
+            Some(activity_streams::Object::Note(o)) => o,
+            _ => continue
+        };
+
+        // The whole conversation lives in one place, so there's no need to look at it again for each status
+        if !context_crawled {
+            if let Some(context) = object.context {
+                context_crawled = true;
+                fetches += 1;
+                match resolve_object_or_link(context).await {
+                    Some(c @ activity_streams::Object::Collection(_)) |
+                    Some(c @ activity_streams::Object::OrderedCollection(_)) => {
+                        backfill_collection(c, &mut queue, &mut fetches).await?;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if let Some(replies) = object.replies {
+            if let activity_streams::ReferenceOrObject::Reference(_) = &replies {
+                fetches += 1;
+            }
+            if let Some(replies) = resolve_object(replies).await {
+                backfill_collection(activity_streams::Object::Collection(replies), &mut queue, &mut fetches).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn backfill_collection(
+    collection: activity_streams::Object, queue: &mut std::collections::VecDeque<String>, fetches: &mut usize,
+) -> TaskResult<()> {
+    let db = super::config().db;
+    let mut items = super::collection::fetch_entire_collection(collection)?;
+    let mut pages_fetched = 0;
+
+    loop {
+        if *fetches >= THREAD_BACKFILL_MAX_FETCHES {
+            break;
+        }
+        let item = items.next().await;
+        // Paging through the collection costs fetches too
+        *fetches += items.pages_fetched() - pages_fetched;
+        pages_fetched = items.pages_fetched();
+        let item = match item {
+            Some(i) => i,
+            None => break
+        };
+
+        // Context collections may hold the activities rather than the statuses themselves
+        let id = match &item {
+            activity_streams::ReferenceOrObject::Object(o) => match o.as_ref() {
+                activity_streams::ObjectOrLink::Object(activity_streams::Object::Create(a)) =>
+                    a.object.as_ref().and_then(|o| o.id()),
+                _ => item.id()
+            },
+            activity_streams::ReferenceOrObject::Reference(_) => item.id()
+        };
+        let id = match id {
+            Some(id) => id.to_string(),
+            None => continue
+        };
+
+        let is_new_status = tokio::task::block_in_place(|| -> TaskResult<_> {
+            let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+            crate::schema::statuses::dsl::statuses.filter(
+                crate::schema::statuses::dsl::url.eq(&id)
+            ).count().get_result::<i64>(&c).with_expected_err(|| "Unable to fetch status")
+        })? == 0;
+
+        if is_new_status {
+            *fetches += 1;
+            if let Err(e) = get_status(activity_streams::ReferenceOrObject::Reference(id.clone())).await {
+                warn!("Unable to backfill status \"{}\": {}", id, e);
+                continue;
+            }
+        }
+
+        queue.push_back(id);
+    }
+
+    Ok(())
+}
+
 #[celery::task]
 pub async fn create_status(
     activity: activity_streams::ReferenceOrObject<activity_streams::ObjectOrLink>, account: models::Account,
@@ -744,20 +989,20 @@ pub async fn create_announce(
             if boost_of.local {
                 let notification = tokio::task::block_in_place(|| -> TaskResult<_> {
                     let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
-                    diesel::insert_into(crate::schema::notifications::dsl::notifications)
-                        .values(models::NewNotification {
-                            id: uuid::Uuid::new_v4(),
-                            notification_type: "reblog".to_string(),
-                            account: boost_of.account_id,
-                            cause: account.id,
-                            status: Some(boost_of.id),
-                            created_at: created_at.naive_utc(),
-                        })
-                        .on_conflict_do_nothing()
-                        .get_result::<models::Notification>(&c).with_expected_err(|| "Unable to insert notification")
+                    super::notifications::insert_notification(&c, models::NewNotification {
+                        id: uuid::Uuid::new_v4(),
+                        notification_type: "reblog".to_string(),
+                        account: boost_of.account_id,
+                        cause: account.id,
+                        status: Some(boost_of.id),
+                        report: None,
+                        created_at: created_at.naive_utc(),
+                    }).with_expected_err(|| "Unable to insert notification")
                 })?;
-                config.celery.send_task(super::notifications::notify::new(notification))
-                    .await.with_expected_err(|| "Unable to submit notification task")?;
+                if let Some(notification) = notification {
+                    config.celery.send_task(super::notifications::notify::new(notification))
+                        .await.with_expected_err(|| "Unable to submit notification task")?;
+                }
             }
         }
     }
@@ -818,20 +1063,20 @@ pub async fn create_like(
             if like_of.local {
                 let notification = tokio::task::block_in_place(|| -> TaskResult<_> {
                     let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
-                    diesel::insert_into(crate::schema::notifications::dsl::notifications)
-                        .values(models::NewNotification {
-                            id: uuid::Uuid::new_v4(),
-                            notification_type: "favourite".to_string(),
-                            account: like_of.account_id,
-                            cause: account.id,
-                            status: Some(like_of.id),
-                            created_at: created_at.naive_utc(),
-                        })
-                        .on_conflict_do_nothing()
-                        .get_result::<models::Notification>(&c).with_expected_err(|| "Unable to insert notification")
+                    super::notifications::insert_notification(&c, models::NewNotification {
+                        id: uuid::Uuid::new_v4(),
+                        notification_type: "favourite".to_string(),
+                        account: like_of.account_id,
+                        cause: account.id,
+                        status: Some(like_of.id),
+                        report: None,
+                        created_at: created_at.naive_utc(),
+                    }).with_expected_err(|| "Unable to insert notification")
                 })?;
-                config.celery.send_task(super::notifications::notify::new(notification))
-                    .await.with_expected_err(|| "Unable to submit notification task")?;
+                if let Some(notification) = notification {
+                    config.celery.send_task(super::notifications::notify::new(notification))
+                        .await.with_expected_err(|| "Unable to submit notification task")?;
+                }
             }
         }
     }
@@ -935,6 +1180,100 @@ pub async fn undo_like(
     Ok(())
 }
 
+fn targets_collection(activity: &activity_streams::ActivityCommon, collection: &Option<String>) -> bool {
+    match (activity.target.as_ref().and_then(|t| t.id()), collection) {
+        (Some(target), Some(collection)) => target == collection,
+        _ => false
+    }
+}
+
+#[celery::task]
+pub async fn process_add(
+    activity: activity_streams::ActivityCommon, account: models::Account,
+) -> TaskResult<()> {
+    let db = super::config().db.clone();
+
+    if targets_collection(&activity, &account.featured_tags_collection_url) {
+        return super::accounts::process_featured_tag(activity, account, false).await;
+    }
+    if !targets_collection(&activity, &account.featured_collection_url) {
+        warn!("Add activity \"{}\" does not target a featured collection, ignoring", activity.id_or_default());
+        return Ok(());
+    }
+
+    let object = match activity.object {
+        Some(o) => o,
+        None => {
+            warn!("Add activity \"{}\" has no object", activity.id_or_default());
+            return Ok(());
+        }
+    };
+
+    let status = get_status(object).await?;
+    if status.account_id != account.id {
+        warn!("Status \"{}\" is not owned by account \"{}\", ignoring pin", status.id, account.id);
+        return Ok(());
+    }
+
+    tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        if crate::schema::pins::dsl::pins.filter(
+            crate::schema::pins::dsl::status.eq(status.id)
+        ).filter(
+            crate::schema::pins::dsl::account.eq(account.id)
+        ).count().get_result::<i64>(&c).with_expected_err(|| "Unable to fetch pins")? == 0 {
+            diesel::insert_into(crate::schema::pins::dsl::pins)
+                .values(models::NewPin {
+                    id: uuid::Uuid::new_v4(),
+                    account: account.id,
+                    status: status.id,
+                })
+                .execute(&c).with_expected_err(|| "Unable to insert pin")?;
+        }
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+#[celery::task]
+pub async fn process_remove(
+    activity: activity_streams::ActivityCommon, account: models::Account,
+) -> TaskResult<()> {
+    let db = super::config().db.clone();
+
+    if targets_collection(&activity, &account.featured_tags_collection_url) {
+        return super::accounts::process_featured_tag(activity, account, true).await;
+    }
+    if !targets_collection(&activity, &account.featured_collection_url) {
+        warn!("Remove activity \"{}\" does not target a featured collection, ignoring", activity.id_or_default());
+        return Ok(());
+    }
+
+    let object_id = match activity.object.as_ref().and_then(|o| o.id()) {
+        Some(o) => o.to_string(),
+        None => {
+            warn!("Remove activity \"{}\" has no object", activity.id_or_default());
+            return Ok(());
+        }
+    };
+
+    tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        diesel::delete(crate::schema::pins::dsl::pins.filter(
+            crate::schema::pins::dsl::account.eq(account.id)
+        ).filter(
+            crate::schema::pins::dsl::status.eq_any(
+                crate::schema::statuses::dsl::statuses.filter(
+                    crate::schema::statuses::dsl::url.eq(&object_id)
+                ).select(crate::schema::statuses::dsl::id)
+            )
+        )).execute(&c).with_expected_err(|| "Unable to delete pin")
+    })?;
+
+    Ok(())
+}
+
 #[celery::task]
 pub async fn insert_into_timelines(
     status: models::Status, audiences: Vec<models::StatusAudience>,
@@ -981,20 +1320,38 @@ pub async fn insert_into_timelines(
                 q.get_results::<models::Following>(&c).with_expected_err(|| "Unable to fetch followers")
             })?;
             for follower in followers {
-                tokio::task::block_in_place(|| -> TaskResult<_> {
+                let notification = tokio::task::block_in_place(|| -> TaskResult<_> {
                     let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
                     let acct = crate::schema::accounts::dsl::accounts.find(follower.follower)
                         .get_result::<models::Account>(&c).with_expected_err(|| "Unable to fetch account")?;
-                    if acct.local {
-                        diesel::insert_into(crate::schema::home_timeline::table)
-                            .values(models::NewHomeTimelineEntry {
-                                status_id: status.id,
-                                account_id: acct.id,
-                            })
-                            .execute(&c).with_expected_err(|| "Unable to insert into home timeline")?;
+                    if !acct.local {
+                        return Ok(None);
+                    }
+                    diesel::insert_into(crate::schema::home_timeline::table)
+                        .values(models::NewHomeTimelineEntry {
+                            status_id: status.id,
+                            account_id: acct.id,
+                        })
+                        .execute(&c).with_expected_err(|| "Unable to insert into home timeline")?;
+                    if follower.notify && !follower.pending && status.boost_of_id.is_none() &&
+                        status.boost_of_url.is_none() && status.in_reply_to_id.is_none() {
+                        super::notifications::insert_notification(&c, models::NewNotification {
+                            id: uuid::Uuid::new_v4(),
+                            notification_type: "status".to_string(),
+                            account: acct.id,
+                            cause: status.account_id,
+                            status: Some(status.id),
+                            report: None,
+                            created_at: status.created_at,
+                        }).with_expected_err(|| "Unable to insert notification")
+                    } else {
+                        Ok(None)
                     }
-                    Ok(())
                 })?;
+                if let Some(notification) = notification {
+                    config.celery.send_task(super::notifications::notify::new(notification))
+                        .await.with_expected_err(|| "Unable to submit notification task")?;
+                }
             }
         }
     }
@@ -1117,6 +1474,17 @@ pub fn as_render_status(
         Ok(attachments)
     })?;
 
+    let tags: Vec<String> = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        crate::schema::status_tags::dsl::status_tags.filter(
+            crate::schema::status_tags::dsl::status.eq(status.id)
+        ).inner_join(
+            crate::schema::tags::table.on(
+                crate::schema::tags::dsl::id.eq(crate::schema::status_tags::dsl::tag)
+            )
+        ).select(crate::schema::tags::dsl::name).get_results(&c).with_expected_err(|| "Unable to get tags")
+    })?;
+
     Ok(activity_streams::Object::Note(activity_streams::ObjectCommon {
         id: Some(status.url(&config.uri)),
         published: Some(Utc.from_utc_datetime(&status.created_at)),
@@ -1167,6 +1535,14 @@ pub fn as_render_status(
                     ..Default::default()
                 })
             )))).collect()),
+        tag: activity_streams::Pluralisable::List(tags.into_iter()
+            .map(|t| activity_streams::ReferenceOrObject::Object(Box::new(activity_streams::ObjectOrLink::Object(
+                activity_streams::Object::Hashtag(activity_streams::Link {
+                    href: Some(format!("https://{}/tags/{}", config.uri, t)),
+                    name: Some(format!("#{}", t)),
+                    ..Default::default()
+                })
+            )))).collect()),
         ..Default::default()
     }))
 }
@@ -1202,9 +1578,14 @@ pub async fn deliver_status(
     let config = super::config();
     let aud = make_audiences(&status, true).await?;
     let activity = as_render_status_activity(&status, &account, &aud)?;
+    super::groups::dispatch_to_groups(&status).await?;
+    let public = status.public;
     config.celery.send_task(
         insert_into_timelines::new(status, aud.audiences)
     ).await.with_expected_err(|| "Unable to submit timelines task")?;
+    if public {
+        super::relays::deliver_to_relays(activity.clone(), account.clone()).await?;
+    }
     super::delivery::deliver_dedupe_inboxes(activity, aud.delivery_accounts, account).await?;
     Ok(())
 }
@@ -1255,7 +1636,7 @@ pub fn as_render_boost(
             ..Default::default()
         },
         actor: Some(activity_streams::ReferenceOrObject::Reference(account.actor_id(&config.uri))),
-        object: Some(activity_streams::ReferenceOrObject::Reference(boosted_status.url.clone())),
+        object: Some(activity_streams::ReferenceOrObject::Reference(boosted_status.url(&config.uri))),
         target: None,
         result: None,
         origin: None,
@@ -1308,6 +1689,52 @@ pub async fn deliver_undo_boost(
     Ok(())
 }
 
+fn as_render_featured(
+    status: &models::Status, account: &models::Account, aud: &ASAudiences, remove: bool,
+) -> activity_streams::Object {
+    let config = super::config();
+
+    let activity = activity_streams::ActivityCommon {
+        common: activity_streams::ObjectCommon {
+            id: Some(format!("https://{}/as/transient/{}", config.uri, uuid::Uuid::new_v4())),
+            to: activity_streams::Pluralisable::List(aud.to.clone()),
+            cc: activity_streams::Pluralisable::List(aud.cc.clone()),
+            ..Default::default()
+        },
+        actor: Some(activity_streams::ReferenceOrObject::Reference(account.actor_id(&config.uri))),
+        object: Some(activity_streams::ReferenceOrObject::Reference(status.url(&config.uri))),
+        target: account.featured_collection(&config.uri).map(activity_streams::ReferenceOrObject::Reference),
+        result: None,
+        origin: None,
+        instrument: None,
+    };
+
+    if remove {
+        activity_streams::Object::Remove(activity)
+    } else {
+        activity_streams::Object::Add(activity)
+    }
+}
+
+#[celery::task]
+pub async fn deliver_pin(
+    status: models::Status, account: models::Account,
+) -> TaskResult<()> {
+    let aud = make_audiences(&status, true).await?;
+    let activity = as_render_featured(&status, &account, &aud, false);
+    super::delivery::deliver_dedupe_inboxes(activity, aud.delivery_accounts, account).await?;
+    Ok(())
+}
+
+#[celery::task]
+pub async fn deliver_unpin(
+    status: models::Status, account: models::Account,
+) -> TaskResult<()> {
+    let aud = make_audiences(&status, true).await?;
+    let activity = as_render_featured(&status, &account, &aud, true);
+    super::delivery::deliver_dedupe_inboxes(activity, aud.delivery_accounts, account).await?;
+    Ok(())
+}
 
 pub async fn make_like_audiences(like: &models::Like, liked_status: &models::Status, account: &models::Account, resolve_delivery: bool) -> TaskResult<ASAudiences> {
     let config = super::config();
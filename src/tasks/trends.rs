@@ -0,0 +1,219 @@
+use celery::prelude::*;
+use chrono::prelude::*;
+use diesel::prelude::*;
+use crate::models;
+
+/// How many days back to look when scoring trends
+pub const TREND_WINDOW_DAYS: i64 = 7;
+/// Time after which the weight of a use or interaction halves
+const TREND_HALF_LIFE_HOURS: f64 = 24.0;
+/// Minimum number of distinct accounts that must be involved before an item can trend
+const TREND_MIN_ACCOUNTS: usize = 2;
+/// Maximum number of items of each kind kept in the trending tables
+const TREND_MAX_ITEMS: usize = 100;
+/// Interaction weights for trending statuses
+const LIKE_WEIGHT: f64 = 1.0;
+const BOOST_WEIGHT: f64 = 2.0;
+
+fn decay(now: DateTime<Utc>, at: chrono::NaiveDateTime) -> f64 {
+    let age_hours = (now.naive_utc() - at).num_minutes().max(0) as f64 / 60.0;
+    0.5_f64.powf(age_hours / TREND_HALF_LIFE_HOURS)
+}
+
+fn top_scores(scores: std::collections::HashMap<uuid::Uuid, f64>) -> Vec<(uuid::Uuid, f64)> {
+    let mut scores = scores.into_iter()
+        .filter(|(_, s)| *s > 0.0)
+        .collect::<Vec<_>>();
+    scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    scores.truncate(TREND_MAX_ITEMS);
+    scores
+}
+
+fn score_tags(c: &diesel::PgConnection, now: DateTime<Utc>) -> QueryResult<Vec<(uuid::Uuid, f64)>> {
+    let since = (now - chrono::Duration::days(TREND_WINDOW_DAYS)).naive_utc();
+
+    let uses: Vec<(uuid::Uuid, uuid::Uuid, chrono::NaiveDateTime)> = crate::schema::status_tags::dsl::status_tags
+        .inner_join(crate::schema::statuses::table.on(
+            crate::schema::statuses::dsl::id.eq(crate::schema::status_tags::dsl::status)
+        ))
+        .filter(crate::schema::statuses::dsl::public.eq(true))
+        .filter(crate::schema::statuses::dsl::deleted_at.is_null())
+        .filter(crate::schema::statuses::dsl::created_at.gt(since))
+        .filter(crate::schema::statuses::dsl::account_id.ne_all(
+            crate::schema::accounts::dsl::accounts.filter(
+                crate::schema::accounts::dsl::silenced_at.is_not_null().or(
+                    crate::schema::accounts::dsl::suspended_at.is_not_null()
+                )
+            ).select(crate::schema::accounts::dsl::id)
+        ))
+        .select((
+            crate::schema::status_tags::dsl::tag,
+            crate::schema::statuses::dsl::account_id,
+            crate::schema::statuses::dsl::created_at,
+        ))
+        .get_results(c)?;
+
+    // Each account counts once per tag, weighted by how recently it last used the tag
+    let mut latest_use = std::collections::HashMap::<uuid::Uuid, std::collections::HashMap<uuid::Uuid, chrono::NaiveDateTime>>::new();
+    for (tag, account, created_at) in uses {
+        let last = latest_use.entry(tag).or_default().entry(account).or_insert(created_at);
+        if created_at > *last {
+            *last = created_at;
+        }
+    }
+
+    Ok(top_scores(latest_use.into_iter()
+        .filter(|(_, accounts)| accounts.len() >= TREND_MIN_ACCOUNTS)
+        .map(|(tag, accounts)| (tag, accounts.values().map(|t| decay(now, *t)).sum()))
+        .collect()))
+}
+
+fn score_statuses(c: &diesel::PgConnection, now: DateTime<Utc>) -> QueryResult<Vec<(uuid::Uuid, f64)>> {
+    let since = (now - chrono::Duration::days(TREND_WINDOW_DAYS)).naive_utc();
+
+    let statuses: Vec<(uuid::Uuid, uuid::Uuid, chrono::NaiveDateTime)> = crate::schema::statuses::dsl::statuses
+        .filter(crate::schema::statuses::dsl::public.eq(true))
+        .filter(crate::schema::statuses::dsl::deleted_at.is_null())
+        .filter(crate::schema::statuses::dsl::boost_of_id.is_null())
+        .filter(crate::schema::statuses::dsl::boost_of_url.is_null())
+        .filter(crate::schema::statuses::dsl::created_at.gt(since))
+        .filter(crate::schema::statuses::dsl::account_id.ne_all(
+            crate::schema::accounts::dsl::accounts.filter(
+                crate::schema::accounts::dsl::silenced_at.is_not_null().or(
+                    crate::schema::accounts::dsl::suspended_at.is_not_null()
+                )
+            ).select(crate::schema::accounts::dsl::id)
+        ))
+        .select((
+        crate::schema::statuses::dsl::id,
+        crate::schema::statuses::dsl::account_id,
+        crate::schema::statuses::dsl::created_at,
+    )).get_results(c)?;
+
+    let likes: Vec<(Option<uuid::Uuid>, uuid::Uuid)> = crate::schema::likes::dsl::likes
+        .filter(crate::schema::likes::dsl::created_at.gt(since))
+        .filter(crate::schema::likes::dsl::status.is_not_null())
+        .select((crate::schema::likes::dsl::status, crate::schema::likes::dsl::account))
+        .get_results(c)?;
+
+    let boosts: Vec<(Option<uuid::Uuid>, uuid::Uuid)> = crate::schema::statuses::dsl::statuses
+        .filter(crate::schema::statuses::dsl::created_at.gt(since))
+        .filter(crate::schema::statuses::dsl::deleted_at.is_null())
+        .filter(crate::schema::statuses::dsl::boost_of_id.is_not_null())
+        .select((crate::schema::statuses::dsl::boost_of_id, crate::schema::statuses::dsl::account_id))
+        .get_results(c)?;
+
+    let statuses = statuses.into_iter()
+        .map(|(id, account, created_at)| (id, (account, created_at)))
+        .collect::<std::collections::HashMap<_, _>>();
+
+    // Only the strongest interaction from each account counts, and never the author's own
+    let mut interactions = std::collections::HashMap::<uuid::Uuid, std::collections::HashMap<uuid::Uuid, f64>>::new();
+    let all_interactions = likes.into_iter().map(|(s, a)| (s, a, LIKE_WEIGHT))
+        .chain(boosts.into_iter().map(|(s, a)| (s, a, BOOST_WEIGHT)));
+    for (status, account, weight) in all_interactions {
+        let status = match status {
+            Some(s) => s,
+            None => continue
+        };
+        match statuses.get(&status) {
+            Some((author, _)) if *author != account => {
+                let w = interactions.entry(status).or_default().entry(account).or_insert(weight);
+                if weight > *w {
+                    *w = weight;
+                }
+            },
+            _ => {}
+        }
+    }
+
+    Ok(top_scores(interactions.into_iter()
+        .filter(|(_, accounts)| accounts.len() >= TREND_MIN_ACCOUNTS)
+        .map(|(status, accounts)| {
+            let created_at = statuses.get(&status).unwrap().1;
+            (status, accounts.values().sum::<f64>() * decay(now, created_at))
+        })
+        .collect()))
+}
+
+#[celery::task]
+pub async fn recompute_trends() -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+    let now = Utc::now();
+
+    let (tag_count, status_count) = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        let tags = score_tags(&c, now).with_expected_err(|| "Unable to score trending tags")?;
+        let statuses = score_statuses(&c, now).with_expected_err(|| "Unable to score trending statuses")?;
+        let counts = (tags.len(), statuses.len());
+
+        c.transaction::<_, diesel::result::Error, _>(|| {
+            diesel::delete(crate::schema::trending_tags::table).execute(&c)?;
+            diesel::insert_into(crate::schema::trending_tags::table)
+                .values(tags.into_iter().map(|(tag, score)| models::TrendingTag {
+                    tag,
+                    score,
+                    updated_at: now.naive_utc(),
+                }).collect::<Vec<_>>())
+                .execute(&c)?;
+
+            diesel::delete(crate::schema::trending_statuses::table).execute(&c)?;
+            diesel::insert_into(crate::schema::trending_statuses::table)
+                .values(statuses.into_iter().map(|(status, score)| models::TrendingStatus {
+                    status,
+                    score,
+                    updated_at: now.naive_utc(),
+                }).collect::<Vec<_>>())
+                .execute(&c)?;
+            Ok(())
+        }).with_expected_err(|| "Unable to update trends")?;
+
+        Ok(counts)
+    })?;
+
+    info!("Trends recomputed, {} tags and {} statuses trending", tag_count, status_count);
+
+    Ok(())
+}
+
+#[celery::task]
+pub async fn review_tag(name: String, trendable: Option<bool>) -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+    let name = super::statuses::normalise_hashtag(&name);
+
+    let updated = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        diesel::update(crate::schema::tags::dsl::tags.filter(
+            crate::schema::tags::dsl::name.eq(&name)
+        )).set(crate::schema::tags::dsl::trendable.eq(trendable))
+            .execute(&c).with_expected_err(|| "Unable to update tag")
+    })?;
+
+    if updated == 0 {
+        warn!("Tag \"{}\" not found, not reviewing", name);
+    }
+
+    Ok(())
+}
+
+#[celery::task]
+pub async fn review_status(status_id: i64, trendable: Option<bool>) -> TaskResult<()> {
+    let config = super::config();
+    let db = config.db.clone();
+
+    let updated = tokio::task::block_in_place(|| -> TaskResult<_> {
+        let c = db.get().with_expected_err(|| "Unable to get DB pool connection")?;
+        diesel::update(crate::schema::statuses::dsl::statuses.filter(
+            crate::schema::statuses::dsl::iid.eq(status_id)
+        )).set(crate::schema::statuses::dsl::trendable.eq(trendable))
+            .execute(&c).with_expected_err(|| "Unable to update status")
+    })?;
+
+    if updated == 0 {
+        warn!("Status {} not found, not reviewing", status_id);
+    }
+
+    Ok(())
+}
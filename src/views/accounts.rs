@@ -7,106 +7,156 @@ use crate::views::parse_bool;
 
 pub async fn get_account(db: &crate::DbConn, localizer: &crate::i18n::Localizer, user: &super::oauth::TokenClaims) -> Result<models::Account, super::Error> {
     let sub = user.subject.clone();
+    let account_id = user.account_id;
+    let account: Option<models::Account> = crate::db_run(db, localizer, move |c| -> QueryResult<_> {
+        let sel = crate::schema::accounts::dsl::accounts.filter(
+            crate::schema::accounts::dsl::owned_by.eq(sub)
+        ).filter(
+            crate::schema::accounts::dsl::deleted_at.is_null()
+        );
+        // Tokens issued before accounts were bound to tokens act as the identity's first account
+        match account_id {
+            Some(account_id) => sel.filter(crate::schema::accounts::dsl::id.eq(account_id)).first(c).optional(),
+            None => sel.order_by(crate::schema::accounts::dsl::created_at.asc()).first(c).optional()
+        }
+    }).await?;
+
+    let account = match account {
+        Some(a) => a,
+        None => return Err(super::Error {
+            code: rocket::http::Status::Unauthorized,
+            error: fl!(localizer, "account-not-found")
+        })
+    };
+
+    if account.suspended_at.is_some() {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-account-suspended")
+        });
+    }
+
+    Ok(account)
+}
+
+/// All the undeleted accounts belonging to an OIDC subject, oldest first
+pub async fn owned_accounts(db: &crate::DbConn, localizer: &crate::i18n::Localizer, sub: &str) -> Result<Vec<models::Account>, super::Error> {
+    let sub = sub.to_string();
     crate::db_run(db, localizer, move |c| -> QueryResult<_> {
         crate::schema::accounts::dsl::accounts.filter(
             crate::schema::accounts::dsl::owned_by.eq(sub)
-        ).first(c)
+        ).filter(
+            crate::schema::accounts::dsl::deleted_at.is_null()
+        ).order_by(crate::schema::accounts::dsl::created_at.asc()).get_results(c)
     }).await
 }
 
+fn username_taken(c: &mut diesel::PgConnection, username: &str) -> QueryResult<bool> {
+    Ok(crate::schema::accounts::dsl::accounts.filter(
+        crate::schema::accounts::dsl::username.eq(username)
+    ).filter(
+        crate::schema::accounts::dsl::local.eq(true)
+    ).count().get_result::<i64>(c)? > 0)
+}
+
+fn generate_private_key(localizer: &crate::i18n::Localizer) -> Result<String, super::Error> {
+    Ok(String::from_utf8(
+        match match openssl::rsa::Rsa::generate(2048) {
+            Ok(k) => k,
+            Err(e) => {
+                error!("Unable to generate RSA key: {}", e);
+                return Err(super::Error {
+                    code: rocket::http::Status::InternalServerError,
+                    error: fl!(localizer, "internal-server-error")
+                });
+            }
+        }.private_key_to_pem() {
+            Ok(k) => k,
+            Err(e) => {
+                error!("Unable to convert RSA key to PEM: {}", e);
+                return Err(super::Error {
+                    code: rocket::http::Status::InternalServerError,
+                    error: fl!(localizer, "internal-server-error")
+                });
+            }
+        }
+    ).unwrap())
+}
+
+fn new_local_account(
+    owned_by: String, username: String, display_name: String, default_language: Option<String>, private_key: String
+) -> models::NewAccount {
+    models::NewAccount {
+        id: uuid::Uuid::new_v4(),
+        owned_by: Some(owned_by),
+        display_name,
+        default_sensitive: Some(false),
+        default_language,
+        discoverable: Some(true),
+        follower_count: 0,
+        following_count: 0,
+        bio: "".to_string(),
+        locked: false,
+        bot: false,
+        group: false,
+        created_at: Utc::now().naive_utc(),
+        username,
+        statuses_count: 0,
+        private_key: Some(private_key),
+        local: true,
+        inbox_url: None,
+        outbox_url: None,
+        shared_inbox_url: None,
+        actor: None,
+        updated_at: Utc::now().naive_utc(),
+        url: None,
+        avatar_file: None,
+        avatar_content_type: None,
+        avatar_remote_url: None,
+        header_file: None,
+        header_content_type: None,
+        header_remote_url: None,
+        follower_collection_url: None,
+        also_known_as: vec![],
+        featured_collection_url: None,
+        featured_tags_collection_url: None,
+    }
+}
+
 pub async fn init_account(
-    db: crate::DbConn, user: &super::oidc::OIDCIdTokenClaims, langs: &crate::i18n::Languages,
+    db: &crate::DbConn, user: &super::oidc::OIDCIdTokenClaims, langs: &crate::i18n::Languages,
     localizer: &crate::i18n::Localizer
 ) -> Result<(), super::Error> {
-    let sub = user.subject().to_string();
-    let account = crate::db_run(&db, localizer, move |c| -> QueryResult<_> {
-        Ok(crate::schema::accounts::dsl::accounts.filter(
-            crate::schema::accounts::dsl::owned_by.eq(sub)
-        ).count().get_result::<i64>(c)? > 0)
-    }).await?;
+    let account = !owned_accounts(db, localizer, user.subject().as_str()).await?.is_empty();
 
     if !account {
         let pref = user.preferred_username().map(|u| u.to_string())
             .or(user.given_name().and_then(|g| g.get(None)).map(|g| g.to_string().to_lowercase()))
             .unwrap_or_else(|| user.subject().to_string());
-        let username = crate::db_run(&db, localizer, move |c| -> QueryResult<_> {
-            if crate::schema::accounts::dsl::accounts.filter(
-                crate::schema::accounts::dsl::username.eq(&pref)
-            ).filter(
-                crate::schema::accounts::dsl::local.eq(true)
-            ).count().get_result::<i64>(c)? > 0 {
-                let i = 1;
+        let username = crate::db_run(db, localizer, move |c| -> QueryResult<_> {
+            if username_taken(c, &pref)? {
+                let mut i = 1;
                 loop {
                     let username = format!("{}{}", pref, i);
-                    if crate::schema::accounts::dsl::accounts.filter(
-                        crate::schema::accounts::dsl::username.eq(&username)
-                    ).filter(
-                        crate::schema::accounts::dsl::local.eq(true)
-                    ).count().get_result::<i64>(c)? == 0 {
+                    if !username_taken(c, &username)? {
                         break Ok(username);
                     }
+                    i += 1;
                 }
             } else {
                 Ok(pref)
             }
         }).await?;
 
-        let private_key = String::from_utf8(
-            match match openssl::rsa::Rsa::generate(2048) {
-                Ok(k) => k,
-                Err(e) => {
-                    error!("Unable to generate RSA key: {}", e);
-                    return Err(super::Error {
-                        code: rocket::http::Status::InternalServerError,
-                        error: fl!(localizer, "internal-server-error")
-                    });
-                }
-            }.private_key_to_pem() {
-                Ok(k) => k,
-                Err(e) => {
-                    error!("Unable to convert RSA key to PEM: {}", e);
-                    return Err(super::Error {
-                        code: rocket::http::Status::InternalServerError,
-                        error: fl!(localizer, "internal-server-error")
-                    });
-                }
-            }
-        ).unwrap();
-
-        let account = crate::models::NewAccount {
-            id: uuid::Uuid::new_v4(),
-            owned_by: Some(user.subject().to_string()),
-            display_name: user.name().and_then(|n| n.get(None)).map(|n| n.to_string())
-                .unwrap_or_else(|| username.clone()),
-            default_sensitive: Some(false),
-            default_language: langs.0.first().map(|l| l.language.as_str().to_string()),
-            discoverable: Some(true),
-            follower_count: 0,
-            following_count: 0,
-            bio: "".to_string(),
-            locked: false,
-            bot: false,
-            group: false,
-            created_at: Utc::now().naive_utc(),
-            username,
-            statuses_count: 0,
-            private_key: Some(private_key),
-            local: true,
-            inbox_url: None,
-            outbox_url: None,
-            shared_inbox_url: None,
-            actor: None,
-            updated_at: Utc::now().naive_utc(),
-            url: None,
-            avatar_file: None,
-            avatar_content_type: None,
-            avatar_remote_url: None,
-            header_file: None,
-            header_content_type: None,
-            header_remote_url: None,
-            follower_collection_url: None,
-        };
-        crate::db_run(&db, localizer, move |c| -> diesel::result::QueryResult<_> {
+        let account = new_local_account(
+            user.subject().to_string(),
+            username.clone(),
+            user.name().and_then(|n| n.get(None)).map(|n| n.to_string())
+                .unwrap_or(username),
+            langs.0.first().map(|l| l.language.as_str().to_string()),
+            generate_private_key(localizer)?,
+        );
+        crate::db_run(db, localizer, move |c| -> diesel::result::QueryResult<_> {
             diesel::insert_into(crate::schema::accounts::table)
                 .values(account)
                 .execute(c)
@@ -118,6 +168,24 @@ pub async fn init_account(
 
 pub async fn render_account(
     config: &crate::AppConfig, db: &crate::DbConn, localizer: &crate::i18n::Localizer, account: models::Account
+) -> Result<super::objs::Account, super::Error> {
+    let moved_to = match account.moved_to {
+        Some(moved_to) if account.suspended_at.is_none() => crate::db_run(db, localizer, move |c| -> QueryResult<_> {
+            crate::schema::accounts::dsl::accounts.find(moved_to)
+                .get_result::<models::Account>(c).optional()
+        }).await?,
+        _ => None
+    };
+
+    let mut rendered = _render_account(config, db, localizer, account).await?;
+    if let Some(moved_to) = moved_to {
+        rendered.moved = Some(Box::new(_render_account(config, db, localizer, moved_to).await?));
+    }
+    Ok(rendered)
+}
+
+async fn _render_account(
+    config: &crate::AppConfig, db: &crate::DbConn, localizer: &crate::i18n::Localizer, account: models::Account
 ) -> Result<super::objs::Account, super::Error> {
     let fields: Vec<models::AccountField> = crate::db_run(db, localizer, move |c| -> QueryResult<_> {
         crate::schema::account_fields::dsl::account_fields.filter(
@@ -153,6 +221,43 @@ pub async fn render_account(
         account.following_count as i64
     };
 
+    if account.suspended_at.is_some() {
+        return Ok(super::objs::Account {
+            id: account.iid.to_string(),
+            username: account.username.clone(),
+            acct: match domain {
+                Some(d) => format!("{}@{}", account.username, d),
+                None => account.username
+            },
+            display_name: String::new(),
+            locked: false,
+            bot: false,
+            created_at: Utc.from_local_datetime(&account.created_at).unwrap(),
+            note: String::new(),
+            url: if account.local {
+                Some(format!("https://{}/users/{}", config.uri, account.id.to_string()))
+            } else {
+                account.url
+            },
+            avatar: format!("https://{}/static/missing.png", config.uri),
+            avatar_static: format!("https://{}/static/missing.png", config.uri),
+            header: format!("https://{}/static/header.png", config.uri),
+            header_static: format!("https://{}/static/header.png", config.uri),
+            followers_count: 0,
+            following_count: 0,
+            statuses_count: 0,
+            last_status_at: None,
+            fields: vec![],
+            emojis: vec![],
+            discoverable: Some(false),
+            group: account.group,
+            limited: None,
+            moved: None,
+            noindex: Some(true),
+            suspended: Some(true),
+        });
+    }
+
     Ok(super::objs::Account {
         id: account.iid.to_string(),
         username: account.username.clone(),
@@ -198,7 +303,7 @@ pub async fn render_account(
         emojis: vec![],
         discoverable: Some(account.discoverable.unwrap_or(true)),
         group: account.group,
-        limited: None,
+        limited: if account.silenced_at.is_some() { Some(true) } else { None },
         moved: None,
         noindex: None,
         suspended: None,
@@ -486,7 +591,365 @@ pub async fn update_credentials(
     Ok(rocket::serde::json::Json(render_account(config, &db, &localizer, account).await?))
 }
 
-async fn get_account_from_db(account_id: &str, db: &crate::DbConn, localizer: &crate::i18n::Localizer) -> Result<models::Account, super::Error> {
+const MAX_ALIASES: usize = 10;
+
+fn parse_actor_uri(uri: &str, localizer: &crate::i18n::Localizer) -> Result<String, super::Error> {
+    match reqwest::Url::parse(uri) {
+        Ok(u) if u.scheme() == "https" && u.host_str().is_some() => Ok(u.to_string()),
+        _ => Err(super::Error {
+            code: rocket::http::Status::UnprocessableEntity,
+            error: fl!(localizer, "error-invalid-alias")
+        })
+    }
+}
+
+#[derive(FromForm)]
+pub struct AliasForm<'a> {
+    also_known_as_uris: Option<Vec<&'a str>>,
+}
+
+#[derive(Deserialize)]
+pub struct AliasJson<'a> {
+    #[serde(borrow, default)]
+    also_known_as_uris: Vec<&'a str>,
+}
+
+#[post("/api/v1/accounts/alias", data = "<form>", rank = 1)]
+pub async fn set_aliases_form(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    form: rocket::form::Form<AliasForm<'_>>, celery: &rocket::State<crate::CeleryApp>,
+    localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::Account>, super::Error> {
+    let uris = form.into_inner().also_known_as_uris.unwrap_or_default();
+    _set_aliases(db, config, user, uris, celery, localizer).await
+}
+
+#[post("/api/v1/accounts/alias", data = "<form>", rank = 2)]
+pub async fn set_aliases_json(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    form: rocket::serde::json::Json<AliasJson<'_>>, celery: &rocket::State<crate::CeleryApp>,
+    localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::Account>, super::Error> {
+    _set_aliases(db, config, user, form.into_inner().also_known_as_uris, celery, localizer).await
+}
+
+async fn _set_aliases(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    uris: Vec<&str>, celery: &rocket::State<crate::CeleryApp>, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::Account>, super::Error> {
+    if !user.has_scope("write:accounts") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    if uris.len() > MAX_ALIASES {
+        return Err(super::Error {
+            code: rocket::http::Status::UnprocessableEntity,
+            error: fl!(localizer, "error-too-many-aliases")
+        });
+    }
+
+    let account = get_account(&db, &localizer, &user).await?;
+    let actor_id = account.actor_id(&config.uri);
+
+    let mut aliases = vec![];
+    for uri in uris {
+        let uri = parse_actor_uri(uri, &localizer)?;
+        if uri == actor_id {
+            return Err(super::Error {
+                code: rocket::http::Status::UnprocessableEntity,
+                error: fl!(localizer, "error-invalid-alias")
+            });
+        }
+        if !aliases.contains(&uri) {
+            aliases.push(uri);
+        }
+    }
+
+    let account: models::Account = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        diesel::update(crate::schema::accounts::dsl::accounts.find(account.id))
+            .set(crate::schema::accounts::dsl::also_known_as.eq(aliases))
+            .get_result(c)
+    }).await?;
+
+    match celery.send_task(
+        crate::tasks::accounts::deliver_account_update::new(account.clone())
+    ).await {
+        Ok(_) => {}
+        Err(err) => {
+            error!("Failed to submit celery task: {:?}", err);
+            return Err(super::Error {
+                code: rocket::http::Status::InternalServerError,
+                error: fl!(localizer, "internal-server-error")
+            });
+        }
+    };
+
+    Ok(rocket::serde::json::Json(render_account(config, &db, &localizer, account).await?))
+}
+
+#[derive(FromForm)]
+pub struct MoveForm<'a> {
+    moved_to_uri: &'a str,
+}
+
+#[derive(Deserialize)]
+pub struct MoveJson<'a> {
+    moved_to_uri: &'a str,
+}
+
+#[post("/api/v1/accounts/move", data = "<form>", rank = 1)]
+pub async fn move_account_form(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    form: rocket::form::Form<MoveForm<'_>>, celery: &rocket::State<crate::CeleryApp>,
+    localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<()>, super::Error> {
+    _move_account(db, config, user, form.moved_to_uri, celery, localizer).await
+}
+
+#[post("/api/v1/accounts/move", data = "<form>", rank = 2)]
+pub async fn move_account_json(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    form: rocket::serde::json::Json<MoveJson<'_>>, celery: &rocket::State<crate::CeleryApp>,
+    localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<()>, super::Error> {
+    _move_account(db, config, user, form.moved_to_uri, celery, localizer).await
+}
+
+async fn _move_account(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    moved_to_uri: &str, celery: &rocket::State<crate::CeleryApp>, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<()>, super::Error> {
+    if !user.has_scope("write:accounts") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let account = get_account(&db, &localizer, &user).await?;
+    let target = parse_actor_uri(moved_to_uri, &localizer)?;
+    if target == account.actor_id(&config.uri) {
+        return Err(super::Error {
+            code: rocket::http::Status::UnprocessableEntity,
+            error: fl!(localizer, "error-cannot-move-to-self")
+        });
+    }
+
+    match celery.send_task(
+        crate::tasks::accounts::move_account::new(account, target)
+    ).await {
+        Ok(_) => {}
+        Err(err) => {
+            error!("Failed to submit celery task: {:?}", err);
+            return Err(super::Error {
+                code: rocket::http::Status::InternalServerError,
+                error: fl!(localizer, "internal-server-error")
+            });
+        }
+    };
+
+    Ok(rocket::serde::json::Json(()))
+}
+
+#[derive(FromForm)]
+pub struct DeleteAccountForm<'a> {
+    confirm_username: &'a str,
+}
+
+#[derive(Deserialize)]
+pub struct DeleteAccountJson<'a> {
+    confirm_username: &'a str,
+}
+
+#[post("/api/v1/accounts/delete", data = "<form>", rank = 1)]
+pub async fn delete_account_form(
+    db: crate::DbConn, user: super::oauth::TokenClaims, form: rocket::form::Form<DeleteAccountForm<'_>>,
+    celery: &rocket::State<crate::CeleryApp>, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<()>, super::Error> {
+    _delete_account(db, user, form.confirm_username, celery, localizer).await
+}
+
+#[post("/api/v1/accounts/delete", data = "<form>", rank = 2)]
+pub async fn delete_account_json(
+    db: crate::DbConn, user: super::oauth::TokenClaims, form: rocket::serde::json::Json<DeleteAccountJson<'_>>,
+    celery: &rocket::State<crate::CeleryApp>, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<()>, super::Error> {
+    _delete_account(db, user, form.confirm_username, celery, localizer).await
+}
+
+async fn _delete_account(
+    db: crate::DbConn, user: super::oauth::TokenClaims, confirm_username: &str,
+    celery: &rocket::State<crate::CeleryApp>, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<()>, super::Error> {
+    if !user.has_scope("write:accounts") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let account = get_account(&db, &localizer, &user).await?;
+    if confirm_username != account.username {
+        return Err(super::Error {
+            code: rocket::http::Status::UnprocessableEntity,
+            error: fl!(localizer, "error-delete-confirmation-mismatch")
+        });
+    }
+
+    // The row is kept as a tombstone so the username stays reserved and the actor can answer 410,
+    // but it's detached from the login so a new account can be created afterwards
+    let account = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        diesel::update(crate::schema::accounts::dsl::accounts.find(account.id))
+            .set((
+                crate::schema::accounts::dsl::deleted_at.eq(Utc::now().naive_utc()),
+                crate::schema::accounts::dsl::owned_by.eq(None::<String>),
+            ))
+            .get_result::<models::Account>(c)
+    }).await?;
+
+    match celery.send_task(
+        crate::tasks::accounts::delete_local_account::new(account)
+    ).await {
+        Ok(_) => {}
+        Err(err) => {
+            error!("Failed to submit celery task: {:?}", err);
+            return Err(super::Error {
+                code: rocket::http::Status::InternalServerError,
+                error: fl!(localizer, "internal-server-error")
+            });
+        }
+    };
+
+    Ok(rocket::serde::json::Json(()))
+}
+
+/// Maximum number of accounts a single OIDC identity can own
+const MAX_OWNED_ACCOUNTS: usize = 10;
+
+#[get("/api/v1/accounts/owned")]
+pub async fn owned_accounts_list(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<Vec<super::objs::Account>>, super::Error> {
+    if !user.has_scope("read:accounts") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let accounts = owned_accounts(&db, &localizer, &user.subject).await?;
+
+    Ok(rocket::serde::json::Json(
+        futures::stream::iter(accounts).map(|a| render_account(config, &db, &localizer, a))
+            .buffered(10).collect::<Vec<_>>().await.into_iter().collect::<Result<Vec<_>, _>>()?
+    ))
+}
+
+#[derive(FromForm)]
+pub struct OwnedAccountForm<'a> {
+    username: &'a str,
+    display_name: Option<&'a str>,
+    bot: Option<bool>,
+    group: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct OwnedAccountJson<'a> {
+    username: &'a str,
+    display_name: Option<&'a str>,
+    bot: Option<bool>,
+    group: Option<bool>,
+}
+
+#[post("/api/v1/accounts/owned", data = "<form>", rank = 1)]
+pub async fn create_owned_account_form(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    form: rocket::form::Form<OwnedAccountForm<'_>>, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::Account>, super::Error> {
+    let form = form.into_inner();
+    _create_owned_account(db, config, user, OwnedAccountJson {
+        username: form.username,
+        display_name: form.display_name,
+        bot: form.bot,
+        group: form.group,
+    }, localizer).await
+}
+
+#[post("/api/v1/accounts/owned", data = "<form>", rank = 2)]
+pub async fn create_owned_account_json(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    form: rocket::serde::json::Json<OwnedAccountJson<'_>>, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::Account>, super::Error> {
+    _create_owned_account(db, config, user, form.into_inner(), localizer).await
+}
+
+async fn _create_owned_account(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    form: OwnedAccountJson<'_>, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::Account>, super::Error> {
+    if !user.has_scope("write:accounts") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let account = get_account(&db, &localizer, &user).await?;
+
+    if !crate::USERNAME_RE.is_match(form.username) {
+        return Err(super::Error {
+            code: rocket::http::Status::UnprocessableEntity,
+            error: fl!(localizer, "error-invalid-username")
+        });
+    }
+
+    if owned_accounts(&db, &localizer, &user.subject).await?.len() >= MAX_OWNED_ACCOUNTS {
+        return Err(super::Error {
+            code: rocket::http::Status::UnprocessableEntity,
+            error: fl!(localizer, "error-too-many-accounts")
+        });
+    }
+
+    let username = form.username.to_string();
+    let c_username = username.clone();
+    if crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        username_taken(c, &c_username)
+    }).await? {
+        return Err(super::Error {
+            code: rocket::http::Status::UnprocessableEntity,
+            error: fl!(localizer, "error-username-taken")
+        });
+    }
+
+    let mut new_account = new_local_account(
+        user.subject.clone(),
+        username.clone(),
+        form.display_name.map(|d| d.to_string()).unwrap_or(username),
+        account.default_language.clone(),
+        generate_private_key(&localizer)?,
+    );
+    new_account.bot = form.bot.unwrap_or(false);
+    new_account.group = form.group.unwrap_or(false);
+
+    let new_account: models::Account = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        c.transaction(|| {
+            let new_account: models::Account = diesel::insert_into(crate::schema::accounts::table)
+                .values(new_account)
+                .get_result(c)?;
+            diesel::update(crate::schema::accounts::dsl::accounts.find(new_account.id))
+                .set(crate::schema::accounts::dsl::moderator.eq(account.moderator))
+                .get_result(c)
+        })
+    }).await?;
+
+    Ok(rocket::serde::json::Json(render_account(config, &db, &localizer, new_account).await?))
+}
+
+pub(crate) async fn get_account_from_db(account_id: &str, db: &crate::DbConn, localizer: &crate::i18n::Localizer) -> Result<models::Account, super::Error> {
     let account_id = match account_id.parse::<i64>() {
         Ok(id) => id,
         Err(_) => return Err(super::Error {
@@ -729,7 +1192,7 @@ pub async fn lists(
     Ok(rocket::serde::json::Json(vec![]))
 }
 
-async fn render_relationship<'a>(
+pub(crate) async fn render_relationship<'a>(
     db: &'a crate::DbConn, localizer: &crate::i18n::Localizer, own_account: &'a models::Account,
     other_account: std::borrow::Cow<'a, models::Account>,
 ) -> Result<super::objs::Relationship, super::Error> {
@@ -762,6 +1225,13 @@ async fn render_relationship<'a>(
             )
         ).count().get_result::<i64>(c)
     }).await?;
+    let endorsed = crate::db_run(db, &localizer, move |c| -> QueryResult<_> {
+        crate::schema::endorsements::dsl::endorsements.filter(
+            crate::schema::endorsements::dsl::account.eq(own_account_id)
+        ).filter(
+            crate::schema::endorsements::dsl::endorsed.eq(other_account_id)
+        ).count().get_result::<i64>(c)
+    }).await?;
     let note: Option<models::AccountNote> = crate::db_run(db, &localizer, move |c| -> QueryResult<_> {
         crate::schema::account_notes::dsl::account_notes.filter(
             crate::schema::account_notes::dsl::account.eq(other_account_id)
@@ -782,7 +1252,7 @@ async fn render_relationship<'a>(
         domain_blocking: false,
         showing_reblogs: following.as_ref().map(|f| f.reblogs).unwrap_or(false),
         notifying: following.as_ref().map(|f| f.notify).unwrap_or(false),
-        endorsed: false,
+        endorsed: endorsed > 0,
         languages: vec![],
         note: note.map(|n| n.note),
     })
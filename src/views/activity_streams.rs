@@ -12,7 +12,7 @@ enum DigestAlgorithm {
 
 impl DigestAlgorithm {
     fn from_str(from: &str) -> Option<Self> {
-        match from {
+        match from.to_ascii_uppercase().as_str() {
             "SHA-512" => Some(Self::SHA512),
             "SHA-256" => Some(Self::SHA256),
             "SHA-1" => Some(Self::SHA1),
@@ -121,7 +121,7 @@ pub enum Object {
     Travel(ActivityCommon),
     Announce(ActivityCommon),
     Block(ActivityCommon),
-    Flag(ActivityCommon),
+    Flag(Flag),
     Dislike(ActivityCommon),
     Question(ActivityCommon),
     Application(Actor),
@@ -140,6 +140,7 @@ pub enum Object {
     Event(ObjectCommon),
     Place(Place),
     Mention(Link),
+    Hashtag(Link),
     Profile(Profile),
     Tombstone(Tombstone),
     PropertyValue(PropertyValue),
@@ -179,9 +180,9 @@ impl ObjectID for Object {
             Object::Travel(o) |
             Object::Announce(o) |
             Object::Block(o) |
-            Object::Flag(o) |
             Object::Dislike(o) |
             Object::Question(o) => o.id(),
+            Object::Flag(o) => o.common.id.as_deref(),
             Object::Application(o) |
             Object::Group(o) |
             Object::Organization(o) |
@@ -197,7 +198,8 @@ impl ObjectID for Object {
             Object::Page(o) |
             Object::Event(o) => o.id.as_deref(),
             Object::Place(o) => o.common.id.as_deref(),
-            Object::Mention(o) => o.href.as_deref(),
+            Object::Mention(o) |
+            Object::Hashtag(o) => o.href.as_deref(),
             Object::Profile(o) => o.common.id.as_deref(),
             Object::Tombstone(o) => o.common.id.as_deref(),
             Object::PropertyValue(_) => None,
@@ -206,6 +208,41 @@ impl ObjectID for Object {
 }
 
 impl Object {
+    /// The actor of an activity, or `None` if this isn't an activity
+    pub fn actor(&self) -> Option<&ReferenceOrObject<ObjectOrLink>> {
+        match self {
+            Object::Accept(a) |
+            Object::TentativeAccept(a) |
+            Object::Add(a) |
+            Object::Arrive(a) |
+            Object::Create(a) |
+            Object::Delete(a) |
+            Object::Follow(a) |
+            Object::Ignore(a) |
+            Object::Join(a) |
+            Object::Leave(a) |
+            Object::Like(a) |
+            Object::Offer(a) |
+            Object::Invite(a) |
+            Object::Reject(a) |
+            Object::TentativeReject(a) |
+            Object::Remove(a) |
+            Object::Undo(a) |
+            Object::Update(a) |
+            Object::View(a) |
+            Object::Listen(a) |
+            Object::Read(a) |
+            Object::Move(a) |
+            Object::Travel(a) |
+            Object::Announce(a) |
+            Object::Block(a) |
+            Object::Dislike(a) |
+            Object::Question(a) => a.actor.as_ref(),
+            Object::Flag(f) => f.actor.as_ref(),
+            _ => None
+        }
+    }
+
     pub fn to_json(&self) -> String {
         let mut body = serde_json::to_value(&self).unwrap();
 
@@ -213,6 +250,7 @@ impl Object {
             obj.insert("@context".to_string(), serde_json::json!([
                 "https://www.w3.org/ns/activitystreams",
                 "https://w3id.org/security/v1",
+                "https://w3id.org/security/data-integrity/v1",
                 {
                     "toot": "http://joinmastodon.org/ns#",
                     "schema": "http://schema.org#",
@@ -263,9 +301,16 @@ impl<'r> rocket::response::Responder<'r, 'static> for Object {
     }
 }
 
+/// An object posted to an inbox, along with the exact body it was sent as so it can be forwarded unchanged
+#[derive(Debug, Clone)]
+pub struct ReceivedObject {
+    pub object: Object,
+    pub body: String,
+}
+
 #[rocket::async_trait]
-impl<'r> rocket::data::FromData<'r> for Object {
-    type Error = String;
+impl<'r> rocket::data::FromData<'r> for ReceivedObject {
+    type Error = RequestError;
 
     async fn from_data(req: &'r rocket::Request<'_>, data: rocket::data::Data<'r>) -> rocket::data::Outcome<'r, Self> {
         let mut needs_context = true;
@@ -282,28 +327,40 @@ impl<'r> rocket::data::FromData<'r> for Object {
             }
         }
 
-        let mut digests: Vec<(DigestAlgorithm, &str)> = req.headers()
-            .get("Digest")
-            .filter_map(|d| d.split_once("="))
-            .map(|(alg, digest)| (DigestAlgorithm::from_str(alg), digest))
-            .filter_map(|(alg, digest)| alg.map(|alg| (alg, digest)))
+        // RFC 9530 Content-Digest values are byte sequences wrapped in colons, otherwise the same as Digest.
+        // Only one of the headers need be covered by the signature, so every digest given has to match the body,
+        // otherwise an unsigned header could vouch for a replaced body.
+        let digest_headers: Vec<Vec<(Option<DigestAlgorithm>, &str)>> = req.headers().get("Digest")
+            .chain(req.headers().get("Content-Digest"))
+            .map(|h| h.split(',')
+                .filter_map(|d| d.trim().split_once('='))
+                .map(|(alg, digest)| (DigestAlgorithm::from_str(alg), digest.trim_matches(':')))
+                .collect())
             .collect();
-
-        digests.sort_by_key(|(alg, _)| alg.clone());
+        if digest_headers.is_empty() {
+            return RequestError::fail(rocket::http::Status::BadRequest, "Missing Digest or Content-Digest header");
+        }
+        if digest_headers.iter().any(|h| !h.iter().any(|(alg, _)| alg.is_some())) {
+            return RequestError::fail(rocket::http::Status::BadRequest, "Unsupported Digest algorithm");
+        }
 
         let data = match data.open(1 * rocket::data::ByteUnit::GiB).into_bytes().await {
             Ok(s) => s,
-            Err(e) => return rocket::data::Outcome::Failure((rocket::http::Status::BadRequest, format!("Failed to read request body: {}", e))),
+            Err(e) => return RequestError::fail(rocket::http::Status::BadRequest, format!("Failed to read request body: {}", e)),
         };
 
         if !data.is_complete() {
-            return rocket::data::Outcome::Failure((rocket::http::Status::PayloadTooLarge, "Payload too large".to_string()));
+            return RequestError::fail(rocket::http::Status::PayloadTooLarge, "Payload too large".to_string());
         }
 
-        if let Some((alg, digest)) = digests.get(0) {
+        for (alg, digest) in digest_headers.iter().flatten() {
+            let alg = match alg {
+                Some(alg) => alg,
+                None => continue
+            };
             let digest = match base64::decode(digest) {
                 Ok(digest) => digest,
-                Err(e) => return rocket::data::Outcome::Failure((rocket::http::Status::BadRequest, format!("Failed to decode digest: {}", e))),
+                Err(e) => return RequestError::fail(rocket::http::Status::BadRequest, format!("Invalid Digest encoding: {}", e)),
             };
 
             let own_digest = match alg {
@@ -322,58 +379,58 @@ impl<'r> rocket::data::FromData<'r> for Object {
             };
 
             if own_digest != digest {
-                return rocket::data::Outcome::Failure(
-                    (rocket::http::Status::UnprocessableEntity, format!("Digest mismatch: {:?} != {:?}", own_digest, digest))
-                );
+                return RequestError::fail(rocket::http::Status::Unauthorized, "Digest does not match the request body");
             }
         }
 
-        let data: serde_json::Value = match serde_json::from_slice(&data) {
+        let body = match String::from_utf8(data.into_inner()) {
+            Ok(b) => b,
+            Err(e) => return RequestError::fail(rocket::http::Status::BadRequest, format!("Invalid UTF-8 in body: {}", e)),
+        };
+
+        let data: serde_json::Value = match serde_json::from_str(&body) {
             Ok(d) => d,
             Err(e) => {
-                return rocket::data::Outcome::Failure(
-                    (rocket::http::Status::UnprocessableEntity, format!("Failed to parse JSON: {}", e))
-                );
+                return RequestError::fail(rocket::http::Status::UnprocessableEntity, format!("Failed to parse JSON: {}", e));
             }
         };
 
-        if needs_context {
-            match data {
-                serde_json::Value::Object(ref o) => {
-                    match o.get("@context") {
-                        Some(serde_json::Value::String(s)) => {
-                            if s != "https://www.w3.org/ns/activitystreams" {
-                                return rocket::data::Outcome::Failure(
-                                    (rocket::http::Status::UnprocessableEntity, format!("Invalid @context: {}", s))
-                                );
-                            }
-                        }
-                        Some(serde_json::Value::Array(s)) => {
-                            if !s.iter().any(|v| *v == serde_json::Value::String("https://www.w3.org/ns/activitystreams".to_string())) {
-                                return rocket::data::Outcome::Failure(
-                                    (rocket::http::Status::UnprocessableEntity, format!("Invalid @context: {:?}", s))
-                                );
-                            }
-                        }
-                        _ => return rocket::data::Outcome::Failure(
-                            (rocket::http::Status::UnprocessableEntity, format!("Missing @context"))
-                        ),
-                    }
-                }
-                _ => return rocket::data::Outcome::Failure(
-                    (rocket::http::Status::UnprocessableEntity, format!("Not an object"))
-                ),
-            }
+        if !data.is_object() {
+            return RequestError::fail(rocket::http::Status::UnprocessableEntity, "Not an object");
+        }
+
+        // Leniency follows the domain of the signing key, which the activity is checked against before it's
+        // processed, rather than anything the body claims about where it's from
+        let json_ld = req.rocket().state::<AppConfig>().map(|c| &c.json_ld);
+        let signer_domain = parse_signature(req).ok()
+            .and_then(|(s, _)| reqwest::Url::parse(&s.key_id).ok())
+            .and_then(|u| u.host_str().map(|h| h.to_string()));
+        let lenient = match (json_ld, signer_domain) {
+            (Some(c), Some(domain)) => c.enabled && c.is_lenient(&domain),
+            _ => false
+        };
+
+        if needs_context && !lenient && !crate::json_ld::has_activitystreams_context(&data) {
+            return match data.get("@context") {
+                Some(c) => RequestError::fail(rocket::http::Status::UnprocessableEntity, format!("Invalid @context: {}", c)),
+                None => RequestError::fail(rocket::http::Status::UnprocessableEntity, "Missing @context"),
+            };
         }
 
-        let data: Object = match serde_json::from_value(data) {
+        let data = match json_ld {
+            Some(c) if c.enabled => crate::json_ld::normalise(data, lenient),
+            _ => data
+        };
+
+        let object: Object = match serde_json::from_value(data) {
             Ok(d) => d,
-            Err(e) => return rocket::data::Outcome::Failure(
-                (rocket::http::Status::UnprocessableEntity, format!("Failed to parse object: {}", e))
-            ),
+            Err(e) => return RequestError::fail(rocket::http::Status::UnprocessableEntity, format!("Failed to parse object: {}", e)),
         };
 
-        rocket::data::Outcome::Success(data)
+        rocket::data::Outcome::Success(ReceivedObject {
+            object,
+            body,
+        })
     }
 }
 
@@ -542,6 +599,23 @@ pub struct ActivityCommon {
     pub instrument: Option<ReferenceOrObject<ObjectOrLink>>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Flag {
+    #[serde(flatten)]
+    pub common: ObjectCommon,
+
+    #[serde(rename = "actor", default, skip_serializing_if = "Option::is_none")]
+    pub actor: Option<ReferenceOrObject<ObjectOrLink>>,
+    #[serde(rename = "object", default, skip_serializing_if = "Pluralisable::is_none")]
+    pub object: Pluralisable<ReferenceOrObject<ObjectOrLink>>,
+}
+
+impl ObjectID for Flag {
+    fn id(&self) -> Option<&str> {
+        self.common.id.as_deref()
+    }
+}
+
 impl ObjectID for ActivityCommon {
     fn id(&self) -> Option<&str> {
         self.common.id.as_deref()
@@ -682,6 +756,16 @@ pub struct Actor {
     pub public_key: Pluralisable<ReferenceOrObject<PublicKey>>,
     #[serde(rename = "discoverable", default, skip_serializing_if = "Option::is_none")]
     pub discoverable: Option<bool>,
+    #[serde(rename = "alsoKnownAs", alias = "as:alsoKnownAs", default, skip_serializing_if = "Pluralisable::is_none")]
+    pub also_known_as: Pluralisable<String>,
+    #[serde(rename = "movedTo", alias = "as:movedTo", default, skip_serializing_if = "Option::is_none")]
+    pub moved_to: Option<String>,
+    #[serde(rename = "featured", alias = "toot:featured", default, skip_serializing_if = "Option::is_none")]
+    pub featured: Option<String>,
+    #[serde(rename = "featuredTags", alias = "toot:featuredTags", default, skip_serializing_if = "Option::is_none")]
+    pub featured_tags: Option<String>,
+    #[serde(rename = "assertionMethod", alias = "sec:assertionMethod", default, skip_serializing_if = "Pluralisable::is_none")]
+    pub assertion_method: Pluralisable<ReferenceOrObject<Multikey>>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -700,7 +784,7 @@ pub struct Endpoints {
     pub shared_inbox: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct Link {
     #[serde(rename = "href", default, skip_serializing_if = "Option::is_none")]
     pub href: Option<String>,
@@ -738,6 +822,89 @@ pub struct PublicKey {
     pub public_key_pem: Option<String>,
 }
 
+/// A key published as described in FEP-521a
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Multikey {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub key_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub controller: Option<String>,
+    #[serde(rename = "publicKeyMultibase", default, skip_serializing_if = "Option::is_none")]
+    pub public_key_multibase: Option<String>,
+}
+
+impl Multikey {
+    /// Decodes the key if it's a base58btc encoded Ed25519 public key, the only kind FEP-521a requires
+    pub fn ed25519_key(&self) -> Option<openssl::pkey::PKey<openssl::pkey::Public>> {
+        let encoded = self.public_key_multibase.as_deref()?.strip_prefix('z')?;
+        let decoded = decode_base58(encoded)?;
+        // 0xed01 is the multicodec varint for ed25519-pub
+        let key = decoded.strip_prefix(&[0xed, 0x01])?;
+        if key.len() != 32 {
+            return None;
+        }
+        openssl::pkey::PKey::public_key_from_raw_bytes(key, openssl::pkey::Id::ED25519).ok()
+    }
+
+    pub fn from_ed25519_key<T: openssl::pkey::HasPublic>(
+        id: String, controller: String, key: &openssl::pkey::PKeyRef<T>,
+    ) -> Option<Self> {
+        let mut bytes = vec![0xed, 0x01];
+        bytes.extend(key.raw_public_key().ok()?);
+        Some(Multikey {
+            id: Some(id),
+            key_type: Some("Multikey".to_string()),
+            controller: Some(controller),
+            public_key_multibase: Some(format!("z{}", encode_base58(&bytes))),
+        })
+    }
+}
+
+const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+fn encode_base58(bytes: &[u8]) -> String {
+    let mut digits: Vec<u8> = vec![];
+    for b in bytes {
+        let mut carry = *b as u32;
+        for d in digits.iter_mut().rev() {
+            carry += (*d as u32) << 8;
+            *d = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.insert(0, (carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    let leading_zeros = bytes.iter().take_while(|b| **b == 0).count();
+    std::iter::repeat_n(b'1', leading_zeros)
+        .chain(digits.into_iter().map(|d| BASE58_ALPHABET[d as usize]))
+        .map(|c| c as char)
+        .collect()
+}
+
+fn decode_base58(encoded: &str) -> Option<Vec<u8>> {
+    let mut bytes: Vec<u8> = vec![];
+    for c in encoded.bytes() {
+        let mut carry = BASE58_ALPHABET.iter().position(|a| *a == c)? as u32;
+        for b in bytes.iter_mut().rev() {
+            carry += (*b as u32) * 58;
+            *b = (carry & 0xff) as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.insert(0, (carry & 0xff) as u8);
+            carry >>= 8;
+        }
+    }
+    let leading_zeros = encoded.bytes().take_while(|c| *c == b'1').count();
+    let mut out = vec![0; leading_zeros];
+    out.extend(bytes);
+    Some(out)
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PropertyValue {
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -759,18 +926,31 @@ pub enum SignatureAlgorithm {
     RsaSha1,
     RsaSha256,
     RsaSha512,
+    RsaPssSha512,
     DsaSha1,
+    Ed25519,
     HmacSha1,
     HmacSha256,
     HmacSha512,
+    /// The algorithm is implied by the type of the key
+    Hs2019,
 }
 
 impl Signature {
     pub fn verify(&self, pkey: &openssl::pkey::PKeyRef<openssl::pkey::Public>) -> bool {
-        match self.algorithm {
+        let algorithm = match self.algorithm {
+            SignatureAlgorithm::Hs2019 => match pkey.id() {
+                openssl::pkey::Id::RSA => SignatureAlgorithm::RsaSha256,
+                openssl::pkey::Id::ED25519 => SignatureAlgorithm::Ed25519,
+                _ => return false,
+            },
+            a => a
+        };
+        match algorithm {
             SignatureAlgorithm::RsaSha1 |
             SignatureAlgorithm::RsaSha256 |
-            SignatureAlgorithm::RsaSha512 => {
+            SignatureAlgorithm::RsaSha512 |
+            SignatureAlgorithm::RsaPssSha512 => {
                 if !pkey.rsa().is_ok() {
                     return false;
                 }
@@ -780,22 +960,42 @@ impl Signature {
                     return false;
                 }
             }
+            SignatureAlgorithm::Ed25519 => {
+                if pkey.id() != openssl::pkey::Id::ED25519 {
+                    return false;
+                }
+                // Ed25519 hashes internally, so can only verify in one shot
+                return match openssl::sign::Verifier::new_without_digest(pkey) {
+                    Ok(mut v) => v.verify_oneshot(&self.signature, &self.signed_data).unwrap_or(false),
+                    Err(_) => false,
+                };
+            }
             SignatureAlgorithm::HmacSha1 |
             SignatureAlgorithm::HmacSha256 |
-            SignatureAlgorithm::HmacSha512 => return false,
+            SignatureAlgorithm::HmacSha512 |
+            SignatureAlgorithm::Hs2019 => return false,
         }
-        let mut verifier = match openssl::sign::Verifier::new(match self.algorithm {
+        let mut verifier = match openssl::sign::Verifier::new(match algorithm {
             SignatureAlgorithm::RsaSha1 => openssl::hash::MessageDigest::sha1(),
             SignatureAlgorithm::RsaSha256 => openssl::hash::MessageDigest::sha256(),
             SignatureAlgorithm::RsaSha512 => openssl::hash::MessageDigest::sha512(),
+            SignatureAlgorithm::RsaPssSha512 => openssl::hash::MessageDigest::sha512(),
             SignatureAlgorithm::DsaSha1 => openssl::hash::MessageDigest::sha1(),
+            SignatureAlgorithm::Ed25519 |
             SignatureAlgorithm::HmacSha1 |
             SignatureAlgorithm::HmacSha256 |
-            SignatureAlgorithm::HmacSha512 => unreachable!(),
+            SignatureAlgorithm::HmacSha512 |
+            SignatureAlgorithm::Hs2019 => unreachable!(),
         }, pkey) {
             Ok(v) => v,
             Err(_) => return false,
         };
+        if let SignatureAlgorithm::RsaPssSha512 = algorithm {
+            if verifier.set_rsa_padding(openssl::rsa::Padding::PKCS1_PSS).is_err() ||
+                verifier.set_rsa_pss_saltlen(openssl::sign::RsaPssSaltlen::DIGEST_LENGTH).is_err() {
+                return false;
+            }
+        }
         match verifier.update(&self.signed_data) {
             Ok(_) => (),
             Err(_) => return false,
@@ -807,6 +1007,136 @@ impl Signature {
     }
 }
 
+/// How old the Date of a signed request may be
+const MAX_SIGNATURE_AGE_HOURS: i64 = 12;
+/// How far ahead of our clock the Date of a signed request may be
+const MAX_CLOCK_SKEW_HOURS: i64 = 1;
+
+#[derive(Debug, Clone)]
+pub enum SignatureError {
+    Missing,
+    Malformed(&'static str),
+    UnsupportedAlgorithm,
+    Unsigned(&'static str),
+    MissingHeader(String),
+    InvalidDate,
+    Expired,
+    Replayed,
+}
+
+impl SignatureError {
+    fn status(&self) -> rocket::http::Status {
+        match self {
+            SignatureError::Missing |
+            SignatureError::Unsigned(_) |
+            SignatureError::Expired |
+            SignatureError::Replayed => rocket::http::Status::Unauthorized,
+            SignatureError::Malformed(_) |
+            SignatureError::UnsupportedAlgorithm |
+            SignatureError::MissingHeader(_) |
+            SignatureError::InvalidDate => rocket::http::Status::BadRequest,
+        }
+    }
+
+    fn fail<S>(self) -> rocket::request::Outcome<S, Self> {
+        rocket::request::Outcome::Failure((self.status(), self))
+    }
+}
+
+impl std::fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignatureError::Missing => write!(f, "Missing signature"),
+            SignatureError::Malformed(e) => write!(f, "Malformed signature: {}", e),
+            SignatureError::UnsupportedAlgorithm => write!(f, "Unsupported signature algorithm"),
+            SignatureError::Unsigned(h) => write!(f, "Signature must cover {}", h),
+            SignatureError::MissingHeader(h) => write!(f, "Signed header {} is missing from the request", h),
+            SignatureError::InvalidDate => write!(f, "Missing or invalid signature date"),
+            SignatureError::Expired => write!(f, "Signature has expired or is outside the allowed clock skew"),
+            SignatureError::Replayed => write!(f, "Signature has already been used"),
+        }
+    }
+}
+
+impl From<SignatureError> for RequestError {
+    fn from(e: SignatureError) -> Self {
+        RequestError {
+            status: e.status(),
+            reason: e.to_string(),
+        }
+    }
+}
+
+/// An error with a plain text reason for the remote server
+#[derive(Debug, Clone)]
+pub struct RequestError {
+    status: rocket::http::Status,
+    reason: String,
+}
+
+impl RequestError {
+    fn fail<'r, T>(status: rocket::http::Status, reason: impl Into<String>) -> rocket::data::Outcome<'r, T, Self> {
+        rocket::data::Outcome::Failure((status, RequestError {
+            status,
+            reason: reason.into(),
+        }))
+    }
+}
+
+impl From<rocket::http::Status> for RequestError {
+    fn from(status: rocket::http::Status) -> Self {
+        RequestError {
+            status,
+            reason: String::new(),
+        }
+    }
+}
+
+impl From<super::Error> for RequestError {
+    fn from(e: super::Error) -> Self {
+        e.code.into()
+    }
+}
+
+impl<'r> rocket::response::Responder<'r, 'static> for RequestError {
+    fn respond_to(self, req: &'r rocket::Request<'_>) -> rocket::response::Result<'static> {
+        if self.reason.is_empty() {
+            return Err(self.status);
+        }
+        rocket::response::Response::build_from(self.reason.respond_to(req)?)
+            .status(self.status)
+            .ok()
+    }
+}
+
+/// Remembers recently seen signatures so a captured request can't be replayed
+#[derive(Default)]
+pub struct SignatureReplayCache(std::sync::Mutex<SignatureReplayCacheInner>);
+
+#[derive(Default)]
+struct SignatureReplayCacheInner {
+    seen: std::collections::HashSet<Vec<u8>>,
+    order: std::collections::VecDeque<(DateTime<Utc>, Vec<u8>)>,
+}
+
+impl SignatureReplayCache {
+    /// Returns false if the signature has been seen before
+    fn record(&self, signature: &[u8]) -> bool {
+        let now = Utc::now();
+        let expiry = chrono::Duration::hours(MAX_SIGNATURE_AGE_HOURS + MAX_CLOCK_SKEW_HOURS);
+        let mut inner = self.0.lock().unwrap();
+        while inner.order.front().map(|(t, _)| now - *t > expiry).unwrap_or(false) {
+            let (_, s) = inner.order.pop_front().unwrap();
+            inner.seen.remove(&s);
+        }
+        if !inner.seen.insert(signature.to_vec()) {
+            return false;
+        }
+        inner.order.push_back((now, signature.to_vec()));
+        true
+    }
+}
+
 enum SignatureParserState {
     Name,
     Quote,
@@ -815,114 +1145,410 @@ enum SignatureParserState {
     Number,
 }
 
+/// Parses a `Signature` header in the format of draft-cavage-http-signatures, returning the time it was made
+fn parse_cavage_signature(
+    request: &rocket::Request<'_>, signature: &str
+) -> Result<(Signature, DateTime<Utc>), SignatureError> {
+    let headers = request.headers();
+    let mut params = std::collections::HashMap::new();
+
+    let mut state = SignatureParserState::Name;
+    let mut tmp_name = String::new();
+    let mut tmp_value = String::new();
+    for c in signature.chars() {
+        match &state {
+            SignatureParserState::Name => {
+                if (c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z') {
+                    tmp_name.push(c);
+                } else if c == '=' {
+                    state = SignatureParserState::Quote;
+                } else {
+                    return Err(SignatureError::Malformed("Invalid field name"));
+                }
+            }
+            SignatureParserState::Quote => {
+                if c == '"' {
+                    state = SignatureParserState::Value;
+                } else {
+                    state = SignatureParserState::Number;
+                    if c < '0' || c > '9' {
+                        return Err(SignatureError::Malformed("Invalid number"));
+                    }
+                    tmp_value.push(c)
+                }
+            }
+            SignatureParserState::Value => {
+                if c == '"' {
+                    params.insert(tmp_name, tmp_value);
+                    tmp_name = String::new();
+                    tmp_value = String::new();
+                    state = SignatureParserState::Comma;
+                } else {
+                    tmp_value.push(c);
+                }
+            }
+            SignatureParserState::Number => {
+                if c == ',' {
+                    params.insert(tmp_name, tmp_value);
+                    tmp_name = String::new();
+                    tmp_value = String::new();
+                    state = SignatureParserState::Name;
+                } else if c < '0' || c > '9' {
+                    return Err(SignatureError::Malformed("Invalid number"));
+                } else {
+                    tmp_value.push(c);
+                }
+            }
+            SignatureParserState::Comma => {
+                if c == ',' {
+                    state = SignatureParserState::Name;
+                } else {
+                    return Err(SignatureError::Malformed("Invalid structure"));
+                }
+            }
+        }
+    }
+    if let SignatureParserState::Number = state {
+        params.insert(tmp_name, tmp_value);
+    }
+
+    if !params.contains_key("signature") || !params.contains_key("keyId") {
+        return Err(SignatureError::Malformed("Missing required fields"));
+    }
+
+    let algorithm = match params.get("algorithm").map(|a| a.as_str()).unwrap_or("hs2019") {
+        "rsa-sha1" => SignatureAlgorithm::RsaSha1,
+        "rsa-sha256" => SignatureAlgorithm::RsaSha256,
+        "rsa-sha512" => SignatureAlgorithm::RsaSha512,
+        "dsa-sha1" => SignatureAlgorithm::DsaSha1,
+        "ed25519" => SignatureAlgorithm::Ed25519,
+        "hmac-sha1" => SignatureAlgorithm::HmacSha1,
+        "hmac-sha256" => SignatureAlgorithm::HmacSha256,
+        "hmac-sha512" => SignatureAlgorithm::HmacSha512,
+        "hs2019" => SignatureAlgorithm::Hs2019,
+        _ => return Err(SignatureError::UnsupportedAlgorithm),
+    };
+
+    let signature = match base64::decode(params.get("signature").unwrap()) {
+        Ok(signature) => signature,
+        Err(_) => return Err(SignatureError::Malformed("Invalid signature encoding")),
+    };
+
+    let signed_headers: Vec<String> = params.get("headers").map(|s| s.as_str()).unwrap_or("date")
+        .split(' ').filter(|h| !h.is_empty()).map(|h| h.to_lowercase()).collect();
+
+    let required_headers: &[&'static str] = if request.method() == rocket::http::Method::Post {
+        &["(request-target)", "host", "digest"]
+    } else {
+        &["(request-target)"]
+    };
+    if let Some(header) = required_headers.iter().find(|h| !signed_headers.iter().any(|s| s == *h)) {
+        return Err(SignatureError::Unsigned(header));
+    }
+    if !signed_headers.iter().any(|h| h == "date" || h == "(created)") {
+        return Err(SignatureError::Unsigned("date"));
+    }
+
+    let mut signed_data = vec![];
+    for header in &signed_headers {
+        match header.as_str() {
+            "(request-target)" => {
+                signed_data.push(format!("(request-target): {} {}", request.method().as_str().to_lowercase(), request.uri()));
+            }
+            "(created)" | "(expires)" => {
+                let param = header.trim_matches(|c| c == '(' || c == ')');
+                match params.get(param) {
+                    Some(v) => signed_data.push(format!("{}: {}", header, v)),
+                    None => return Err(SignatureError::Malformed("Missing created or expires field")),
+                }
+            }
+            _ => {
+                let values: Vec<&str> = headers.get(header).collect();
+                if values.is_empty() {
+                    return Err(SignatureError::MissingHeader(header.clone()));
+                }
+                for v in values {
+                    signed_data.push(format!("{}: {}", header, v));
+                }
+            }
+        }
+    }
+
+    let signed_at = if signed_headers.iter().any(|h| h == "(created)") {
+        parse_timestamp(params.get("created").map(|c| c.as_str()))?
+    } else {
+        match headers.get_one("Date").map(DateTime::parse_from_rfc2822) {
+            Some(Ok(d)) => d.with_timezone(&Utc),
+            _ => return Err(SignatureError::InvalidDate),
+        }
+    };
+    if let Some(expires) = params.get("expires") {
+        if parse_timestamp(Some(expires))? < Utc::now() {
+            return Err(SignatureError::Expired);
+        }
+    }
+
+    Ok((Signature {
+        key_id: params.get("keyId").unwrap().to_string(),
+        algorithm,
+        signature,
+        signed_data: signed_data.join("\n").into_bytes(),
+    }, signed_at))
+}
+
+fn parse_timestamp(timestamp: Option<&str>) -> Result<DateTime<Utc>, SignatureError> {
+    timestamp.and_then(|t| t.parse::<i64>().ok())
+        .and_then(|t| Utc.timestamp_opt(t, 0).single())
+        .ok_or(SignatureError::InvalidDate)
+}
+
+/// Splits a structured field (RFC 8941) on a separator, ignoring it inside strings, inner lists and byte sequences
+fn split_structured_field(value: &str, separator: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut start = 0;
+    let mut in_string = false;
+    let mut in_bytes = false;
+    let mut escaped = false;
+    let mut depth = 0;
+    for (i, c) in value.char_indices() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            ':' => in_bytes = !in_bytes,
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            c if c == separator && depth == 0 && !in_bytes => {
+                parts.push(value[start..i].trim());
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(value[start..].trim());
+    parts.into_iter().filter(|p| !p.is_empty()).collect()
+}
+
+/// Parses RFC 9421 `Signature-Input` and `Signature` headers, returning the time the signature was made
+fn parse_rfc9421_signature(
+    request: &rocket::Request<'_>, input: &str, signature: &str
+) -> Result<(Signature, DateTime<Utc>), SignatureError> {
+    let headers = request.headers();
+
+    let signatures = split_structured_field(signature, ',').into_iter()
+        .filter_map(|m| m.split_once('='))
+        .collect::<std::collections::HashMap<_, _>>();
+    // Only the first signature we have both the input and value for is checked
+    let (label, signature_params) = split_structured_field(input, ',').into_iter()
+        .filter_map(|m| m.split_once('='))
+        .find(|(l, _)| signatures.contains_key(l))
+        .ok_or(SignatureError::Malformed("No signature matches the signature input"))?;
+
+    let signature = signatures.get(label).unwrap().trim()
+        .strip_prefix(':').and_then(|s| s.strip_suffix(':'))
+        .and_then(|s| base64::decode(s).ok())
+        .ok_or(SignatureError::Malformed("Invalid signature encoding"))?;
+
+    let (components, params) = signature_params.strip_prefix('(')
+        .and_then(|s| s.split_once(')'))
+        .ok_or(SignatureError::Malformed("Invalid signature input"))?;
+    let components = components.split_whitespace()
+        .map(|c| c.strip_prefix('"').and_then(|c| c.strip_suffix('"')).map(|c| c.to_string()))
+        .collect::<Option<Vec<_>>>()
+        .ok_or(SignatureError::Malformed("Unsupported component parameters"))?;
+    let params = split_structured_field(params, ';').into_iter()
+        .map(|p| match p.split_once('=') {
+            Some((k, v)) => (k, v.trim_matches('"')),
+            None => (p, "?1")
+        })
+        .collect::<std::collections::HashMap<_, _>>();
+
+    let key_id = params.get("keyid").ok_or(SignatureError::Malformed("Missing required fields"))?;
+    let algorithm = match params.get("alg").copied() {
+        Some("rsa-v1_5-sha256") => SignatureAlgorithm::RsaSha256,
+        Some("rsa-pss-sha512") => SignatureAlgorithm::RsaPssSha512,
+        Some("ed25519") => SignatureAlgorithm::Ed25519,
+        Some("hmac-sha256") => SignatureAlgorithm::HmacSha256,
+        None => SignatureAlgorithm::Hs2019,
+        _ => return Err(SignatureError::UnsupportedAlgorithm),
+    };
+
+    let mut required_components = vec!["@method", "@target-uri"];
+    if request.method() == rocket::http::Method::Post && !components.iter().any(|c| c == "digest") {
+        required_components.push("content-digest");
+    }
+    if let Some(component) = required_components.into_iter().find(|r| !components.iter().any(|c| c == r)) {
+        return Err(SignatureError::Unsigned(component));
+    }
+
+    let authority = match request.rocket().state::<AppConfig>() {
+        Some(config) => config.uri.clone(),
+        None => headers.get_one("Host").unwrap_or_default().to_string(),
+    };
+    let mut signature_base = vec![];
+    for component in &components {
+        let value = match component.as_str() {
+            "@method" => request.method().as_str().to_string(),
+            "@target-uri" => format!("https://{}{}", authority, request.uri()),
+            "@authority" => authority.clone(),
+            "@scheme" => "https".to_string(),
+            "@request-target" => request.uri().to_string(),
+            "@path" => request.uri().path().to_string(),
+            "@query" => format!("?{}", request.uri().query().map(|q| q.as_str()).unwrap_or_default()),
+            c if c.starts_with('@') => return Err(SignatureError::Malformed("Unsupported derived component")),
+            h => {
+                let values: Vec<&str> = headers.get(h).map(|v| v.trim()).collect();
+                if values.is_empty() {
+                    return Err(SignatureError::MissingHeader(h.to_string()));
+                }
+                values.join(", ")
+            }
+        };
+        signature_base.push(format!("\"{}\": {}", component, value));
+    }
+    signature_base.push(format!("\"@signature-params\": {}", signature_params));
+
+    let signed_at = match params.get("created") {
+        Some(created) => parse_timestamp(Some(created))?,
+        None if components.iter().any(|c| c == "date") => {
+            match headers.get_one("Date").map(DateTime::parse_from_rfc2822) {
+                Some(Ok(d)) => d.with_timezone(&Utc),
+                _ => return Err(SignatureError::InvalidDate),
+            }
+        }
+        None => return Err(SignatureError::Unsigned("created")),
+    };
+    if let Some(expires) = params.get("expires") {
+        if parse_timestamp(Some(expires))? < Utc::now() {
+            return Err(SignatureError::Expired);
+        }
+    }
+
+    Ok((Signature {
+        key_id: key_id.to_string(),
+        algorithm,
+        signature,
+        signed_data: signature_base.join("\n").into_bytes(),
+    }, signed_at))
+}
+
+fn parse_signature(request: &rocket::Request<'_>) -> Result<(Signature, DateTime<Utc>), SignatureError> {
+    let headers = request.headers();
+    match (headers.get_one("Signature-Input"), headers.get_one("Signature")) {
+        (Some(input), Some(signature)) => parse_rfc9421_signature(request, input, signature),
+        (None, Some(signature)) => parse_cavage_signature(request, signature),
+        _ => Err(SignatureError::Missing),
+    }
+}
+
 #[rocket::async_trait]
 impl<'r> rocket::request::FromRequest<'r> for Signature {
-    type Error = &'static str;
+    type Error = SignatureError;
 
     async fn from_request(request: &'r rocket::Request<'_>) -> rocket::request::Outcome<Self, Self::Error> {
-        let headers = request.headers();
-        if let Some(signature) = headers.get_one("Signature") {
-            let mut params = std::collections::HashMap::new();
-
-            let mut state = SignatureParserState::Name;
-            let mut tmp_name = String::new();
-            let mut tmp_value = String::new();
-            for c in signature.chars() {
-                match &state {
-                    SignatureParserState::Name => {
-                        if (c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z') {
-                            tmp_name.push(c);
-                        } else if c == '=' {
-                            state = SignatureParserState::Quote;
-                        } else {
-                            return rocket::request::Outcome::Failure((rocket::http::Status::BadRequest, "Invalid field name"));
-                        }
-                    }
-                    SignatureParserState::Quote => {
-                        if c == '"' {
-                            state = SignatureParserState::Value;
-                        } else {
-                            state = SignatureParserState::Number;
-                            if c < '0' || c > '9' {
-                                return rocket::request::Outcome::Failure((rocket::http::Status::BadRequest, "Invalid number"));
-                            }
-                            tmp_value.push(c)
-                        }
-                    }
-                    SignatureParserState::Value => {
-                        if c == '"' {
-                            params.insert(tmp_name, tmp_value);
-                            tmp_name = String::new();
-                            tmp_value = String::new();
-                            state = SignatureParserState::Comma;
-                        } else {
-                            tmp_value.push(c);
-                        }
-                    }
-                    SignatureParserState::Number => {
-                        if c == ',' {
-                            params.insert(tmp_name, tmp_value);
-                            tmp_name = String::new();
-                            tmp_value = String::new();
-                            state = SignatureParserState::Name;
-                        } else if c < '0' || c > '9' {
-                            return rocket::request::Outcome::Failure((rocket::http::Status::BadRequest, "Invalid number"));
-                        } else {
-                            tmp_value.push(c);
-                        }
-                    }
-                    SignatureParserState::Comma => {
-                        if c == ',' {
-                            state = SignatureParserState::Name;
-                        } else {
-                            return rocket::request::Outcome::Failure((rocket::http::Status::BadRequest, "Invalid structure"));
-                        }
+        let (signature, signed_at) = match parse_signature(request) {
+            Ok(s) => s,
+            Err(e) => return e.fail(),
+        };
+
+        let now = Utc::now();
+        if now - signed_at > chrono::Duration::hours(MAX_SIGNATURE_AGE_HOURS) ||
+            signed_at - now > chrono::Duration::hours(MAX_CLOCK_SKEW_HOURS) {
+            return SignatureError::Expired.fail();
+        }
+
+        if let Some(config) = request.rocket().state::<AppConfig>() {
+            if config.signature_replay_cache {
+                if let Some(cache) = request.rocket().state::<SignatureReplayCache>() {
+                    if !cache.record(&signature.signature) {
+                        return SignatureError::Replayed.fail();
                     }
                 }
             }
+        }
 
-            if !params.contains_key("signature") || !params.contains_key("algorithm")
-                || !params.contains_key("keyId") {
-                return rocket::request::Outcome::Failure((rocket::http::Status::BadRequest, "Missing required fields"));
-            }
+        rocket::request::Outcome::Success(signature)
+    }
+}
 
-            let algorithm = match params.get("algorithm").unwrap().as_str() {
-                "rsa-sha1" => SignatureAlgorithm::RsaSha1,
-                "rsa-sha256" => SignatureAlgorithm::RsaSha256,
-                "rsa-sha512" => SignatureAlgorithm::RsaSha512,
-                "dsa-sha1" => SignatureAlgorithm::DsaSha1,
-                "hmac-sha1" => SignatureAlgorithm::HmacSha1,
-                "hmac-sha256" => SignatureAlgorithm::HmacSha256,
-                "hmac-sha512" => SignatureAlgorithm::HmacSha512,
-                _ => return rocket::request::Outcome::Failure((rocket::http::Status::BadRequest, "Invalid algorithm")),
-            };
+/// Who is fetching an object, when authorized fetch is enabled
+pub enum AuthorizedFetch {
+    /// Authorized fetch is disabled, so anyone can fetch public objects
+    Open,
+    /// The request wasn't signed
+    Unsigned,
+    /// The request was signed by this remote actor
+    Signed(Box<crate::models::Account>),
+}
 
-            let signature = match base64::decode(params.get("signature").unwrap()) {
-                Ok(signature) => signature,
-                Err(_) => return rocket::request::Outcome::Failure((rocket::http::Status::BadRequest, "Invalid signature encoding")),
-            };
+impl AuthorizedFetch {
+    fn require(&self) -> Result<(), RequestError> {
+        match self {
+            AuthorizedFetch::Unsigned => Err(RequestError {
+                status: rocket::http::Status::Unauthorized,
+                reason: "Request must be signed".to_string(),
+            }),
+            _ => Ok(())
+        }
+    }
+}
 
-            let signed_headers = params.get("headers").map(|s| s.as_str()).unwrap_or("date").split(" ");
-            let mut signed_data = vec![];
+#[rocket::async_trait]
+impl<'r> rocket::request::FromRequest<'r> for AuthorizedFetch {
+    type Error = RequestError;
 
-            for header in signed_headers {
-                if header == "(request-target)" {
-                    signed_data.push(format!("(request-target): {} {}", request.method().as_str().to_lowercase(), request.uri()));
-                }
-                for v in headers.get(header) {
-                    signed_data.push(format!("{}: {}", header, v));
+    async fn from_request(request: &'r rocket::Request<'_>) -> rocket::request::Outcome<Self, Self::Error> {
+        let config = match request.rocket().state::<AppConfig>() {
+            Some(c) => c,
+            None => return rocket::request::Outcome::Failure((
+                rocket::http::Status::InternalServerError, rocket::http::Status::InternalServerError.into()
+            ))
+        };
+        if !config.authorized_fetch {
+            return rocket::request::Outcome::Success(AuthorizedFetch::Open);
+        }
+
+        let signature = match request.guard::<Signature>().await {
+            rocket::request::Outcome::Success(s) => s,
+            rocket::request::Outcome::Failure((_, SignatureError::Missing)) =>
+                return rocket::request::Outcome::Success(AuthorizedFetch::Unsigned),
+            rocket::request::Outcome::Failure((status, e)) => return rocket::request::Outcome::Failure((status, e.into())),
+            rocket::request::Outcome::Forward(f) => return rocket::request::Outcome::Forward(f),
+        };
+
+        let account = match crate::tasks::accounts::find_signature_account(&signature).await {
+            Ok(Some(a)) => a,
+            r => {
+                if let Err(e) = r {
+                    warn!("Unable to resolve signature key \"{}\": {}", signature.key_id, e);
                 }
+                return rocket::request::Outcome::Failure((rocket::http::Status::Unauthorized, RequestError {
+                    status: rocket::http::Status::Unauthorized,
+                    reason: "Unable to verify signature".to_string(),
+                }));
             }
+        };
 
-            rocket::request::Outcome::Success(Signature {
-                key_id: params.get("keyId").unwrap().to_string(),
-                algorithm,
-                signature,
-                signed_data: signed_data.join("\n").into_bytes(),
-            })
-        } else {
-            rocket::request::Outcome::Forward(())
+        let domain = account.actor.as_deref()
+            .and_then(|a| reqwest::Url::parse(a).ok())
+            .and_then(|u| u.host_str().map(|h| h.to_string()));
+        let blocked = match &domain {
+            Some(d) => crate::domain_in_list(&config.blocked_domains, d),
+            None => false
+        };
+        if account.suspended_at.is_some() || blocked {
+            return rocket::request::Outcome::Failure((rocket::http::Status::Forbidden, rocket::http::Status::Forbidden.into()));
         }
+
+        rocket::request::Outcome::Success(AuthorizedFetch::Signed(Box::new(account)))
     }
 }
 
@@ -957,11 +1583,68 @@ pub async fn system_actor(
             owner: Some(ReferenceOrObject::Reference(format!("https://{}/as/system", config.uri))),
             public_key_pem: Some(String::from_utf8(config.as_key.public_key_to_pem().unwrap()).unwrap()),
         }))),
-        discoverable: Some(false)
+        discoverable: Some(false),
+        also_known_as: Pluralisable::None,
+        moved_to: None,
+        featured: None,
+        featured_tags: None,
+        assertion_method: Pluralisable::None,
     }))
 }
 
-async fn get_account(db: &crate::DbConn, localizer: &crate::i18n::Localizer, id: &str) -> Result<crate::models::Account, rocket::http::Status> {
+#[get("/as/system/inbox")]
+pub async fn get_system_inbox() -> rocket::http::Status {
+    rocket::http::Status::MethodNotAllowed
+}
+
+#[post("/as/system/inbox", data = "<data>")]
+pub async fn post_system_inbox(
+    data: Result<ReceivedObject, RequestError>, signature: Result<Signature, SignatureError>,
+    celery: &rocket::State<crate::CeleryApp>,
+) -> Result<(), RequestError> {
+    submit_activity("system", data, signature, celery).await
+}
+
+/// Queues a POSTed activity for processing, recording the outcome for the inbox metrics
+async fn submit_activity(
+    inbox: &str, data: Result<ReceivedObject, RequestError>, signature: Result<Signature, SignatureError>,
+    celery: &crate::CeleryApp,
+) -> Result<(), RequestError> {
+    let (outcome, result) = match (signature, data) {
+        (Err(e), _) => ("invalid_signature", Err(e.into())),
+        (_, Err(e)) => ("invalid_body", Err(e)),
+        (Ok(signature), Ok(data)) => match celery.send_task(
+            super::super::tasks::inbox::process_activity::new(data.object, signature, data.body)
+        ).await {
+            Ok(_) => ("accepted", Ok(())),
+            Err(err) => {
+                error!("Failed to submit celery task: {:?}", err);
+                ("queue_error", Err(rocket::http::Status::InternalServerError.into()))
+            }
+        }
+    };
+
+    crate::metrics::INBOX_REQUESTS.inc(&[inbox, outcome]);
+    result
+}
+
+#[get("/as/relay/<id>")]
+pub async fn relay_follow(
+    db: crate::DbConn, id: &str, localizer: crate::i18n::Localizer
+) -> Result<Object, rocket::http::Status> {
+    let relay_id = match uuid::Uuid::parse_str(id) {
+        Ok(id) => id,
+        Err(_) => return Err(rocket::http::Status::NotFound)
+    };
+
+    let relay: crate::models::Relay = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        crate::schema::relays::dsl::relays.find(relay_id).get_result(c)
+    }).await?;
+
+    Ok(crate::tasks::relays::as_render_relay_follow(&relay))
+}
+
+async fn get_local_account(db: &crate::DbConn, localizer: &crate::i18n::Localizer, id: &str) -> Result<crate::models::Account, rocket::http::Status> {
     let account_id = match uuid::Uuid::parse_str(id) {
         Ok(id) => id,
         Err(_) => return Err(rocket::http::Status::NotFound)
@@ -975,21 +1658,83 @@ async fn get_account(db: &crate::DbConn, localizer: &crate::i18n::Localizer, id:
         return Err(rocket::http::Status::NotFound);
     }
 
+    if account.suspended_at.is_some() {
+        return Err(rocket::http::Status::Gone);
+    }
+
+    Ok(account)
+}
+
+async fn get_account(db: &crate::DbConn, localizer: &crate::i18n::Localizer, id: &str) -> Result<crate::models::Account, rocket::http::Status> {
+    let account = get_local_account(db, localizer, id).await?;
+
+    if account.deleted_at.is_some() {
+        return Err(rocket::http::Status::Gone);
+    }
+
     Ok(account)
 }
 
+/// Strips an actor down to what another server needs to verify its signatures
+fn minimal_actor(actor: Actor) -> Actor {
+    Actor {
+        common: ObjectCommon {
+            id: actor.common.id,
+            ..Default::default()
+        },
+        preferred_username: actor.preferred_username,
+        inbox: actor.inbox,
+        outbox: actor.outbox,
+        following: None,
+        followers: None,
+        liked: None,
+        manually_approves_followers: None,
+        endpoints: actor.endpoints,
+        public_key: actor.public_key,
+        discoverable: None,
+        also_known_as: Pluralisable::None,
+        moved_to: None,
+        featured: None,
+        featured_tags: None,
+        assertion_method: actor.assertion_method,
+    }
+}
+
 #[get("/as/users/<id>")]
 pub async fn user(
-    db: crate::DbConn, localizer: crate::i18n::Localizer, id: &str,
-) -> Result<Object, rocket::http::Status> {
-    let account = get_account(&db, &localizer, id).await?;
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, localizer: crate::i18n::Localizer, id: &str,
+    fetch: Result<AuthorizedFetch, RequestError>,
+) -> Result<(rocket::http::Status, Object), RequestError> {
+    let fetch = fetch?;
+    let account = get_local_account(&db, &localizer, id).await?;
+
+    if let Some(deleted_at) = account.deleted_at {
+        return Ok((rocket::http::Status::Gone, Object::Tombstone(Tombstone {
+            common: ObjectCommon {
+                id: Some(account.actor_id(&config.uri)),
+                ..Default::default()
+            },
+            former_type: Some(if account.group { "Group" } else { "Person" }.to_string()),
+            deleted: Some(Utc.from_utc_datetime(&deleted_at)),
+        })));
+    }
 
     let account = match crate::tasks::accounts::render_account(&account) {
         Ok(a) => a,
-        Err(_) => return Err(rocket::http::Status::InternalServerError),
+        Err(_) => return Err(rocket::http::Status::InternalServerError.into()),
     };
 
-    Ok(account)
+    // Other servers fetch the actor unsigned to discover its key, so give them just that
+    if let AuthorizedFetch::Unsigned = fetch {
+        return Ok((rocket::http::Status::Ok, match account {
+            Object::Person(a) => Object::Person(minimal_actor(a)),
+            Object::Group(a) => Object::Group(minimal_actor(a)),
+            Object::Service(a) => Object::Service(minimal_actor(a)),
+            o => o
+        }));
+    }
+
+    Ok((rocket::http::Status::Ok, account))
 }
 
 #[get("/as/users/<_id>/inbox")]
@@ -999,22 +1744,11 @@ pub async fn get_inbox(_id: &str) -> rocket::http::Status {
 
 #[post("/as/users/<id>/inbox", data = "<data>")]
 pub async fn post_inbox(
-    db: crate::DbConn, id: &str, data: Object, signature: Signature,
+    db: crate::DbConn, id: &str, data: Result<ReceivedObject, RequestError>, signature: Result<Signature, SignatureError>,
     celery: &rocket::State<crate::CeleryApp>, localizer: crate::i18n::Localizer
-) -> Result<(), rocket::http::Status> {
+) -> Result<(), RequestError> {
     get_account(&db, &localizer, id).await?;
-
-    match celery.send_task(
-        super::super::tasks::inbox::process_activity::new(data, signature)
-    ).await {
-        Ok(_) => {}
-        Err(err) => {
-            error!("Failed to submit celery task: {:?}", err);
-            return Err(rocket::http::Status::InternalServerError);
-        }
-    };
-
-    Ok(())
+    submit_activity("user", data, signature, celery).await
 }
 
 #[get("/as/users/<id>/outbox")]
@@ -1065,6 +1799,192 @@ pub async fn get_outbox_page(
     }))
 }
 
+const FOLLOWERS_PAGE_SIZE: i64 = 40;
+
+#[get("/as/users/<id>/followers")]
+pub async fn get_followers(
+    db: crate::DbConn, config: &rocket::State<AppConfig>, id: &str, localizer: crate::i18n::Localizer
+) -> Result<Object, rocket::http::Status> {
+    let account = get_account(&db, &localizer, id).await?;
+
+    let account_id = account.id;
+    let total = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        crate::schema::following::dsl::following.filter(
+            crate::schema::following::dsl::followee.eq(account_id)
+        ).filter(
+            crate::schema::following::dsl::pending.eq(false)
+        ).count().get_result::<i64>(c)
+    }).await?;
+
+    Ok(Object::OrderedCollection(Collection {
+        common: ObjectCommon {
+            id: Some(account.follower_collection(&config.uri)),
+            ..Default::default()
+        },
+        total_items: Some(total as u64),
+        current: None,
+        // Only a group's members are public, everyone else just exposes the count
+        first: if account.group {
+            Some(ReferenceOrObject::Reference(format!("https://{}/as/users/{}/followers/page", config.uri, account.id)))
+        } else {
+            None
+        },
+        last: None,
+        items: None,
+    }))
+}
+
+#[get("/as/users/<id>/followers/page?<before>")]
+pub async fn get_followers_page(
+    db: crate::DbConn, config: &rocket::State<AppConfig>, id: &str, before: Option<i64>,
+    localizer: crate::i18n::Localizer, fetch: Result<AuthorizedFetch, RequestError>,
+) -> Result<Object, RequestError> {
+    fetch?.require()?;
+
+    let account = get_account(&db, &localizer, id).await?;
+
+    if !account.group {
+        return Err(rocket::http::Status::NotFound.into());
+    }
+
+    let account_id = account.id;
+    let members: Vec<(crate::models::Following, crate::models::Account)> = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        let mut sel = crate::schema::following::dsl::following.filter(
+            crate::schema::following::dsl::followee.eq(account_id)
+        ).filter(
+            crate::schema::following::dsl::pending.eq(false)
+        ).inner_join(
+            crate::schema::accounts::table.on(
+                crate::schema::accounts::dsl::id.eq(crate::schema::following::dsl::follower)
+            )
+        ).order_by(crate::schema::following::dsl::iid.desc())
+            .limit(FOLLOWERS_PAGE_SIZE)
+            .into_boxed();
+        if let Some(before) = before {
+            sel = sel.filter(crate::schema::following::dsl::iid.lt(before));
+        }
+        sel.get_results(c)
+    }).await?;
+
+    let next = if members.len() as i64 == FOLLOWERS_PAGE_SIZE {
+        members.last().map(|(f, _)| ReferenceOrObject::Reference(
+            format!("https://{}/as/users/{}/followers/page?before={}", config.uri, account.id, f.iid)
+        ))
+    } else {
+        None
+    };
+
+    Ok(Object::OrderedCollectionPage(CollectionPage {
+        common: Collection {
+            common: ObjectCommon {
+                id: Some(match before {
+                    Some(b) => format!("https://{}/as/users/{}/followers/page?before={}", config.uri, account.id, b),
+                    None => format!("https://{}/as/users/{}/followers/page", config.uri, account.id)
+                }),
+                ..Default::default()
+            },
+            total_items: None,
+            current: None,
+            first: None,
+            last: None,
+            items: Some(members.into_iter().map(|(_, a)| ReferenceOrObject::Reference(a.actor_id(&config.uri))).collect()),
+        },
+        part_of: Some(ReferenceOrObject::Reference(account.follower_collection(&config.uri))),
+        next,
+        prev: None,
+        start_index: None,
+    }))
+}
+
+#[get("/as/users/<id>/featured")]
+pub async fn get_featured(
+    db: crate::DbConn, config: &rocket::State<AppConfig>, id: &str, localizer: crate::i18n::Localizer,
+    fetch: Result<AuthorizedFetch, RequestError>,
+) -> Result<Object, RequestError> {
+    fetch?.require()?;
+
+    let account = get_account(&db, &localizer, id).await?;
+
+    let account_id = account.id;
+    let statuses: Vec<crate::models::Status> = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        crate::schema::pins::dsl::pins.filter(
+            crate::schema::pins::dsl::account.eq(account_id)
+        ).inner_join(
+            crate::schema::statuses::table.on(
+                crate::schema::pins::dsl::status.eq(crate::schema::statuses::dsl::id)
+            )
+        ).filter(
+            crate::schema::statuses::dsl::account_id.eq(account_id)
+        ).filter(
+            crate::schema::statuses::dsl::deleted_at.is_null()
+        ).filter(
+            crate::schema::statuses::dsl::boost_of_id.is_null()
+        ).order_by(crate::schema::pins::dsl::iid.desc())
+            .select(crate::schema::statuses::all_columns)
+            .get_results(c)
+    }).await?;
+
+    let mut items = vec![];
+    for status in statuses {
+        let aud = match crate::tasks::statuses::make_audiences(&status, false).await {
+            Ok(aud) => aud,
+            Err(_) => return Err(rocket::http::Status::InternalServerError.into())
+        };
+        if !aud.is_visible() {
+            continue;
+        }
+        let obj = crate::tasks::statuses::as_render_status(&status, &account, &aud)
+            .map_err(|_| rocket::http::Status::InternalServerError)?;
+        items.push(ReferenceOrObject::Object(Box::new(ObjectOrLink::Object(obj))));
+    }
+
+    Ok(Object::OrderedCollection(Collection {
+        common: ObjectCommon {
+            id: account.featured_collection(&config.uri),
+            ..Default::default()
+        },
+        total_items: Some(items.len() as u64),
+        current: None,
+        first: None,
+        last: None,
+        items: Some(items),
+    }))
+}
+
+#[get("/as/users/<id>/featured_tags")]
+pub async fn get_featured_tags(
+    db: crate::DbConn, config: &rocket::State<AppConfig>, id: &str, localizer: crate::i18n::Localizer
+) -> Result<Object, rocket::http::Status> {
+    let account = get_account(&db, &localizer, id).await?;
+
+    let account_id = account.id;
+    let tags: Vec<String> = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        crate::schema::featured_tags::dsl::featured_tags.filter(
+            crate::schema::featured_tags::dsl::account.eq(account_id)
+        ).inner_join(
+            crate::schema::tags::table.on(
+                crate::schema::featured_tags::dsl::tag.eq(crate::schema::tags::dsl::id)
+            )
+        ).order_by(crate::schema::featured_tags::dsl::iid.asc())
+            .select(crate::schema::tags::dsl::name)
+            .get_results(c)
+    }).await?;
+
+    Ok(Object::Collection(Collection {
+        common: ObjectCommon {
+            id: account.featured_tags_collection(&config.uri),
+            ..Default::default()
+        },
+        total_items: Some(tags.len() as u64),
+        current: None,
+        first: None,
+        last: None,
+        items: Some(tags.into_iter().map(|t| ReferenceOrObject::Object(Box::new(ObjectOrLink::Object(
+            crate::tasks::accounts::as_render_hashtag(&t)
+        )))).collect()),
+    }))
+}
+
 #[post("/as/users/<_id>/outbox")]
 pub async fn post_outbox(_id: &str) -> rocket::http::Status {
     rocket::http::Status::MethodNotAllowed
@@ -1077,28 +1997,21 @@ pub async fn get_shared_inbox() -> rocket::http::Status {
 
 #[post("/as/inbox", data = "<data>")]
 pub async fn post_shared_inbox(
-    data: Object, signature: Signature, celery: &rocket::State<crate::CeleryApp>,
-) -> Result<(), rocket::http::Status> {
-    match celery.send_task(
-        super::super::tasks::inbox::process_activity::new(data, signature)
-    ).await {
-        Ok(_) => {}
-        Err(err) => {
-            error!("Failed to submit celery task: {:?}", err);
-            return Err(rocket::http::Status::InternalServerError);
-        }
-    };
-
-    Ok(())
+    data: Result<ReceivedObject, RequestError>, signature: Result<Signature, SignatureError>,
+    celery: &rocket::State<crate::CeleryApp>,
+) -> Result<(), RequestError> {
+    submit_activity("shared", data, signature, celery).await
 }
 
 #[get("/as/status/<id>")]
 pub async fn status(
-    db: crate::DbConn, id: &str, localizer: crate::i18n::Localizer
-) -> Result<Object, rocket::http::Status> {
+    db: crate::DbConn, id: &str, localizer: crate::i18n::Localizer, fetch: Result<AuthorizedFetch, RequestError>,
+) -> Result<Object, RequestError> {
+    fetch?.require()?;
+
     let status_id = match uuid::Uuid::parse_str(id) {
         Ok(id) => id,
-        Err(_) => return Err(rocket::http::Status::NotFound)
+        Err(_) => return Err(rocket::http::Status::NotFound.into())
     };
 
     let (status, account): (crate::models::Status, crate::models::Account) =
@@ -1111,33 +2024,39 @@ pub async fn status(
         }).await?;
 
     if !status.local {
-        return Err(rocket::http::Status::NotFound);
+        return Err(rocket::http::Status::NotFound.into());
+    }
+
+    if account.suspended_at.is_some() || account.deleted_at.is_some() {
+        return Err(rocket::http::Status::Gone.into());
     }
 
     let aud = match crate::tasks::statuses::make_audiences(&status, false).await {
         Ok(aud) => aud,
-        Err(_) => return Err(rocket::http::Status::InternalServerError)
+        Err(_) => return Err(rocket::http::Status::InternalServerError.into())
     };
 
     if !aud.is_visible() {
-        return Err(rocket::http::Status::NotFound);
+        return Err(rocket::http::Status::NotFound.into());
     }
 
     if status.boost_of_id.is_none() {
         crate::tasks::statuses::as_render_status(&status, &account, &aud)
-            .map_err(|_| rocket::http::Status::InternalServerError)
+            .map_err(|_| rocket::http::Status::InternalServerError.into())
     } else {
-        Err(rocket::http::Status::NotFound)
+        Err(rocket::http::Status::NotFound.into())
     }
 }
 
 #[get("/as/status/<id>/activity")]
 pub async fn status_activity(
-    db: crate::DbConn, id: &str, localizer: crate::i18n::Localizer
-) -> Result<Object, rocket::http::Status> {
+    db: crate::DbConn, id: &str, localizer: crate::i18n::Localizer, fetch: Result<AuthorizedFetch, RequestError>,
+) -> Result<Object, RequestError> {
+    fetch?.require()?;
+
     let status_id = match uuid::Uuid::parse_str(id) {
         Ok(id) => id,
-        Err(_) => return Err(rocket::http::Status::NotFound)
+        Err(_) => return Err(rocket::http::Status::NotFound.into())
     };
 
     let (status, account): (crate::models::Status, crate::models::Account) =
@@ -1150,21 +2069,25 @@ pub async fn status_activity(
         }).await?;
 
     if !status.local {
-        return Err(rocket::http::Status::NotFound);
+        return Err(rocket::http::Status::NotFound.into());
+    }
+
+    if account.suspended_at.is_some() || account.deleted_at.is_some() {
+        return Err(rocket::http::Status::Gone.into());
     }
 
     let aud = match crate::tasks::statuses::make_audiences(&status, false).await {
         Ok(aud) => aud,
-        Err(_) => return Err(rocket::http::Status::InternalServerError)
+        Err(_) => return Err(rocket::http::Status::InternalServerError.into())
     };
 
     if !aud.is_visible() {
-        return Err(rocket::http::Status::NotFound);
+        return Err(rocket::http::Status::NotFound.into());
     }
 
     if let Some(boost_of_id) = status.boost_of_id {
         if status.deleted_at.is_some() {
-            return Err(rocket::http::Status::Gone);
+            return Err(rocket::http::Status::Gone.into());
         }
 
         let boosted_status: crate::models::Status = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
@@ -1176,17 +2099,19 @@ pub async fn status_activity(
         Ok(activity)
     } else {
         crate::tasks::statuses::as_render_status_activity(&status, &account, &aud)
-            .map_err(|_| rocket::http::Status::InternalServerError)
+            .map_err(|_| rocket::http::Status::InternalServerError.into())
     }
 }
 
 #[get("/as/like/<id>")]
 pub async fn like(
-    db: crate::DbConn, id: &str, localizer: crate::i18n::Localizer
-) -> Result<Object, rocket::http::Status> {
+    db: crate::DbConn, id: &str, localizer: crate::i18n::Localizer, fetch: Result<AuthorizedFetch, RequestError>,
+) -> Result<Object, RequestError> {
+    fetch?.require()?;
+
     let like_id = match uuid::Uuid::parse_str(id) {
         Ok(id) => id,
-        Err(_) => return Err(rocket::http::Status::NotFound)
+        Err(_) => return Err(rocket::http::Status::NotFound.into())
     };
 
     let (like, account, liked_status): (crate::models::Like, crate::models::Account, crate::models::Status) =
@@ -1203,21 +2128,21 @@ pub async fn like(
         }).await?;
 
     if !like.local || like.status_url.is_some() {
-        return Err(rocket::http::Status::NotFound);
+        return Err(rocket::http::Status::NotFound.into());
     }
 
     let aud = match crate::tasks::statuses::make_like_audiences(&like, &liked_status, &account, false).await {
         Ok(aud) => aud,
-        Err(_) => return Err(rocket::http::Status::InternalServerError)
+        Err(_) => return Err(rocket::http::Status::InternalServerError.into())
     };
 
     if !aud.is_visible() {
-        return Err(rocket::http::Status::NotFound);
+        return Err(rocket::http::Status::NotFound.into());
     }
 
     let activity = match crate::tasks::statuses::as_render_like(&like, &liked_status, &account, &aud).await {
         Ok(act) => act,
-        Err(_) => return Err(rocket::http::Status::InternalServerError)
+        Err(_) => return Err(rocket::http::Status::InternalServerError.into())
     };
 
     Ok(activity)
@@ -0,0 +1,611 @@
+use diesel::prelude::*;
+use chrono::prelude::*;
+use rocket::futures;
+use futures::StreamExt;
+use crate::models;
+
+const MODERATION_ACTIONS: [&str; 4] = ["none", "sensitive", "silence", "suspend"];
+
+async fn get_moderator(
+    db: &crate::DbConn, localizer: &crate::i18n::Localizer, user: &super::oauth::TokenClaims, scope: &str
+) -> Result<models::Account, super::Error> {
+    if !user.has_scope(scope) {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let account = super::accounts::get_account(db, localizer, user).await?;
+    if !account.moderator {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    Ok(account)
+}
+
+fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+pub async fn render_admin_account(
+    db: &crate::DbConn, config: &crate::AppConfig, account: models::Account,
+    localizer: &crate::i18n::Localizer
+) -> Result<super::objs::AdminAccount, super::Error> {
+    let domain = if account.local {
+        None
+    } else {
+        account.actor.as_deref().and_then(
+            |u| reqwest::Url::parse(u).ok()?.domain().map(|d| d.to_string())
+        )
+    };
+
+    Ok(super::objs::AdminAccount {
+        id: account.iid.to_string(),
+        username: account.username.clone(),
+        domain,
+        created_at: Utc.from_utc_datetime(&account.created_at),
+        email: None,
+        ip: None,
+        ips: vec![],
+        locale: account.default_language.clone(),
+        invite_request: None,
+        role: if account.moderator {
+            "moderator".to_string()
+        } else {
+            "user".to_string()
+        },
+        confirmed: true,
+        approved: true,
+        disabled: false,
+        silenced: account.silenced_at.is_some(),
+        suspended: account.suspended_at.is_some(),
+        sensitized: account.sensitized_at.is_some(),
+        account: super::accounts::render_account(config, db, localizer, account).await?,
+    })
+}
+
+async fn render_admin_account_opt(
+    db: &crate::DbConn, config: &crate::AppConfig, account_id: Option<uuid::Uuid>,
+    localizer: &crate::i18n::Localizer
+) -> Result<Option<super::objs::AdminAccount>, super::Error> {
+    match account_id {
+        Some(account_id) => {
+            let account = crate::db_run(db, localizer, move |c| -> QueryResult<_> {
+                crate::schema::accounts::dsl::accounts.find(account_id).get_result::<models::Account>(c)
+            }).await?;
+            Ok(Some(render_admin_account(db, config, account, localizer).await?))
+        }
+        None => Ok(None)
+    }
+}
+
+pub async fn render_admin_report(
+    db: &crate::DbConn, config: &crate::AppConfig, report: models::Report,
+    moderator: &models::Account, localizer: &crate::i18n::Localizer
+) -> Result<super::objs::AdminReport, super::Error> {
+    let account_id = report.account;
+    let target_account_id = report.target_account;
+    let status_ids = report.status_ids.clone();
+    let (account, target_account, statuses) = crate::db_run(db, localizer, move |c| -> QueryResult<_> {
+        let a = crate::schema::accounts::dsl::accounts.find(account_id)
+            .get_result::<models::Account>(c)?;
+        let t = crate::schema::accounts::dsl::accounts.find(target_account_id)
+            .get_result::<models::Account>(c)?;
+        let s = crate::schema::statuses::dsl::statuses.filter(
+            crate::schema::statuses::dsl::id.eq_any(status_ids)
+        ).order_by(crate::schema::statuses::dsl::created_at.desc())
+            .get_results::<models::Status>(c)?;
+        Ok((a, t, s))
+    }).await?;
+
+    Ok(super::objs::AdminReport {
+        id: report.iid.to_string(),
+        action_taken: report.action_taken_at.is_some(),
+        action_taken_at: report.action_taken_at.map(|t| Utc.from_utc_datetime(&t)),
+        category: report.category,
+        comment: report.comment,
+        forwarded: report.forwarded,
+        created_at: Utc.from_utc_datetime(&report.created_at),
+        updated_at: Utc.from_utc_datetime(&report.updated_at),
+        account: render_admin_account(db, config, account, localizer).await?,
+        target_account: render_admin_account(db, config, target_account, localizer).await?,
+        assigned_account: render_admin_account_opt(db, config, report.assigned_account, localizer).await?,
+        action_taken_by_account: render_admin_account_opt(db, config, report.action_taken_by_account, localizer).await?,
+        statuses: futures::stream::iter(statuses)
+            .map(|s| super::statuses::render_status(config, db, s, localizer, Some(moderator)))
+            .buffered(10)
+            .collect::<Vec<_>>().await.into_iter().collect::<Result<Vec<_>, _>>()?,
+        rules: vec![],
+    })
+}
+
+async fn get_report(
+    report_id: &str, db: &crate::DbConn, localizer: &crate::i18n::Localizer
+) -> Result<models::Report, super::Error> {
+    let report_id = match report_id.parse::<i64>() {
+        Ok(id) => id,
+        Err(_) => return Err(super::Error {
+            code: rocket::http::Status::NotFound,
+            error: fl!(localizer, "error-report-not-found")
+        })
+    };
+
+    match crate::db_run(db, localizer, move |c| -> QueryResult<_> {
+        crate::schema::reports::dsl::reports.filter(
+            crate::schema::reports::dsl::iid.eq(report_id)
+        ).get_result(c).optional()
+    }).await? {
+        Some(r) => Ok(r),
+        None => Err(super::Error {
+            code: rocket::http::Status::NotFound,
+            error: fl!(localizer, "error-report-not-found")
+        })
+    }
+}
+
+#[get("/api/v1/admin/accounts?<local>&<remote>&<by_domain>&<active>&<pending>&<disabled>&<silenced>&<suspended>&<sensitized>&<username>&<display_name>&<staff>&<max_id>&<since_id>&<min_id>&<limit>")]
+pub async fn list_accounts(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    local: Option<&str>, remote: Option<&str>, by_domain: Option<&str>, active: Option<&str>,
+    pending: Option<&str>, disabled: Option<&str>, silenced: Option<&str>, suspended: Option<&str>,
+    sensitized: Option<&str>, username: Option<&str>, display_name: Option<&str>, staff: Option<&str>,
+    max_id: Option<i64>, since_id: Option<i64>, min_id: Option<i64>, limit: Option<u64>,
+    host: &rocket::http::uri::Host<'_>, localizer: crate::i18n::Localizer
+) -> Result<super::LinkedResponse<rocket::serde::json::Json<Vec<super::objs::AdminAccount>>>, super::Error> {
+    get_moderator(&db, &localizer, &user, "admin:read:accounts").await?;
+
+    let local = super::parse_bool(local, false, &localizer)?;
+    let remote = super::parse_bool(remote, false, &localizer)?;
+    let active = super::parse_bool(active, false, &localizer)?;
+    let pending = super::parse_bool(pending, false, &localizer)?;
+    let disabled = super::parse_bool(disabled, false, &localizer)?;
+    let silenced = super::parse_bool(silenced, false, &localizer)?;
+    let suspended = super::parse_bool(suspended, false, &localizer)?;
+    let sensitized = super::parse_bool(sensitized, false, &localizer)?;
+    let staff = super::parse_bool(staff, false, &localizer)?;
+    let by_domain = by_domain.map(|d| format!("https://{}/%", escape_like(d)));
+    let username = username.map(|u| format!("{}%", escape_like(u)));
+    let display_name = display_name.map(|d| format!("%{}%", escape_like(d)));
+
+    let limit = limit.unwrap_or(100);
+    if limit > 200 {
+        return Err(super::Error {
+            code: rocket::http::Status::BadRequest,
+            error: fl!(localizer, "limit-too-large")
+        });
+    }
+
+    let accounts: Vec<models::Account> = if pending || disabled {
+        // Accounts never await approval and can't be disabled separately from suspension
+        vec![]
+    } else {
+        crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+            let mut q = crate::schema::accounts::dsl::accounts
+                .filter(crate::schema::accounts::dsl::deleted_at.is_null())
+                .limit(limit as i64)
+                .order_by(crate::schema::accounts::dsl::iid.desc())
+                .into_boxed();
+            if local {
+                q = q.filter(crate::schema::accounts::dsl::local.eq(true));
+            }
+            if remote {
+                q = q.filter(crate::schema::accounts::dsl::local.eq(false));
+            }
+            if let Some(by_domain) = by_domain {
+                q = q.filter(crate::schema::accounts::dsl::actor.like(by_domain));
+            }
+            if active {
+                q = q.filter(crate::schema::accounts::dsl::suspended_at.is_null());
+            }
+            if silenced {
+                q = q.filter(crate::schema::accounts::dsl::silenced_at.is_not_null());
+            }
+            if suspended {
+                q = q.filter(crate::schema::accounts::dsl::suspended_at.is_not_null());
+            }
+            if sensitized {
+                q = q.filter(crate::schema::accounts::dsl::sensitized_at.is_not_null());
+            }
+            if staff {
+                q = q.filter(crate::schema::accounts::dsl::moderator.eq(true));
+            }
+            if let Some(username) = username {
+                q = q.filter(crate::schema::accounts::dsl::username.ilike(username));
+            }
+            if let Some(display_name) = display_name {
+                q = q.filter(crate::schema::accounts::dsl::display_name.ilike(display_name));
+            }
+            if let Some(min_id) = min_id {
+                q = q.filter(crate::schema::accounts::dsl::iid.gt(min_id));
+            }
+            if let Some(max_id) = max_id {
+                q = q.filter(crate::schema::accounts::dsl::iid.lt(max_id));
+            }
+            if let Some(since_id) = since_id {
+                q = q.filter(crate::schema::accounts::dsl::iid.gt(since_id));
+            }
+            q.load(c)
+        }).await?
+    };
+
+    let mut links = vec![];
+
+    if let Some(last_id) = accounts.last().map(|a| a.iid) {
+        links.push(super::Link {
+            rel: "next".to_string(),
+            href: format!("https://{}/api/v1/admin/accounts?max_id={}", host, last_id)
+        });
+    }
+    if let Some(first_id) = accounts.first().map(|a| a.iid) {
+        links.push(super::Link {
+            rel: "prev".to_string(),
+            href: format!("https://{}/api/v1/admin/accounts?min_id={}", host, first_id)
+        });
+    }
+
+    Ok(super::LinkedResponse {
+        inner: rocket::serde::json::Json(
+            futures::stream::iter(accounts)
+                .map(|a| render_admin_account(&db, config, a, &localizer))
+                .buffered(10)
+                .collect::<Vec<_>>().await.into_iter().collect::<Result<Vec<_>, _>>()?
+        ),
+        links
+    })
+}
+
+#[get("/api/v1/admin/accounts/<account_id>")]
+pub async fn get_account(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    account_id: &str, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::AdminAccount>, super::Error> {
+    get_moderator(&db, &localizer, &user, "admin:read:accounts").await?;
+    let account = super::accounts::get_account_from_db(account_id, &db, &localizer).await?;
+
+    Ok(rocket::serde::json::Json(render_admin_account(&db, config, account, &localizer).await?))
+}
+
+#[derive(FromForm)]
+pub struct AccountActionForm<'a> {
+    #[field(name = "type")]
+    action_type: Option<&'a str>,
+    report_id: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+pub struct AccountActionJson<'a> {
+    #[serde(default, rename = "type")]
+    action_type: Option<&'a str>,
+    #[serde(default)]
+    report_id: Option<&'a str>,
+}
+
+pub struct AccountAction<'a> {
+    action_type: &'a str,
+    report_id: Option<&'a str>,
+}
+
+impl<'a> From<AccountActionForm<'a>> for AccountAction<'a> {
+    fn from(value: AccountActionForm<'a>) -> Self {
+        AccountAction {
+            action_type: value.action_type.unwrap_or("none"),
+            report_id: value.report_id,
+        }
+    }
+}
+
+impl<'a> From<AccountActionJson<'a>> for AccountAction<'a> {
+    fn from(value: AccountActionJson<'a>) -> Self {
+        AccountAction {
+            action_type: value.action_type.unwrap_or("none"),
+            report_id: value.report_id,
+        }
+    }
+}
+
+#[post("/api/v1/admin/accounts/<account_id>/action", data = "<form>", rank = 1)]
+pub async fn account_action_form(
+    db: crate::DbConn, user: super::oauth::TokenClaims, account_id: &str,
+    form: rocket::form::Form<AccountActionForm<'_>>, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<()>, super::Error> {
+    _account_action(db, user, account_id, form.into_inner().into(), localizer).await
+}
+
+#[post("/api/v1/admin/accounts/<account_id>/action", data = "<form>", rank = 2)]
+pub async fn account_action_json(
+    db: crate::DbConn, user: super::oauth::TokenClaims, account_id: &str,
+    form: rocket::serde::json::Json<AccountActionJson<'_>>, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<()>, super::Error> {
+    _account_action(db, user, account_id, form.into_inner().into(), localizer).await
+}
+
+async fn _account_action(
+    db: crate::DbConn, user: super::oauth::TokenClaims, account_id: &str,
+    form: AccountAction<'_>, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<()>, super::Error> {
+    let moderator = get_moderator(&db, &localizer, &user, "admin:write:accounts").await?;
+
+    if !MODERATION_ACTIONS.contains(&form.action_type) {
+        return Err(super::Error {
+            code: rocket::http::Status::UnprocessableEntity,
+            error: fl!(localizer, "error-invalid-moderation-action")
+        });
+    }
+
+    let account = super::accounts::get_account_from_db(account_id, &db, &localizer).await?;
+    if account.id == moderator.id {
+        return Err(super::Error {
+            code: rocket::http::Status::UnprocessableEntity,
+            error: fl!(localizer, "error-cannot-moderate-self")
+        });
+    }
+
+    let report = match form.report_id {
+        Some(report_id) => Some(get_report(report_id, &db, &localizer).await?),
+        None => None
+    };
+
+    let action_type = form.action_type.to_string();
+    let now = Utc::now().naive_utc();
+    crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        c.transaction(|| {
+            let target = crate::schema::accounts::dsl::accounts.find(account.id);
+            match action_type.as_str() {
+                "sensitive" => {
+                    diesel::update(target)
+                        .set(crate::schema::accounts::dsl::sensitized_at.eq(now))
+                        .execute(c)?;
+                }
+                "silence" => {
+                    diesel::update(target)
+                        .set(crate::schema::accounts::dsl::silenced_at.eq(now))
+                        .execute(c)?;
+                }
+                "suspend" => {
+                    diesel::update(target)
+                        .set(crate::schema::accounts::dsl::suspended_at.eq(now))
+                        .execute(c)?;
+                }
+                _ => {}
+            }
+
+            if let Some(report) = report {
+                diesel::update(crate::schema::reports::dsl::reports.find(report.id))
+                    .set((
+                        crate::schema::reports::dsl::action_taken_at.eq(now),
+                        crate::schema::reports::dsl::action_taken_by_account.eq(moderator.id),
+                        crate::schema::reports::dsl::updated_at.eq(now),
+                    ))
+                    .execute(c)?;
+            }
+
+            Ok(())
+        })
+    }).await?;
+
+    Ok(rocket::serde::json::Json(()))
+}
+
+async fn _set_account_flag<F>(
+    db: crate::DbConn, config: &crate::AppConfig, user: super::oauth::TokenClaims, account_id: &str,
+    localizer: crate::i18n::Localizer, set: F
+) -> Result<rocket::serde::json::Json<super::objs::AdminAccount>, super::Error>
+    where F: 'static + FnOnce(uuid::Uuid, &mut diesel::PgConnection) -> QueryResult<models::Account> + Send {
+    get_moderator(&db, &localizer, &user, "admin:write:accounts").await?;
+    let account = super::accounts::get_account_from_db(account_id, &db, &localizer).await?;
+
+    let account_id = account.id;
+    let account = crate::db_run(&db, &localizer, move |c| set(account_id, c)).await?;
+
+    Ok(rocket::serde::json::Json(render_admin_account(&db, config, account, &localizer).await?))
+}
+
+#[post("/api/v1/admin/accounts/<account_id>/unsuspend")]
+pub async fn unsuspend_account(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    account_id: &str, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::AdminAccount>, super::Error> {
+    _set_account_flag(db, config, user, account_id, localizer, |id, c| {
+        diesel::update(crate::schema::accounts::dsl::accounts.find(id))
+            .set(crate::schema::accounts::dsl::suspended_at.eq(None::<chrono::NaiveDateTime>))
+            .get_result(c)
+    }).await
+}
+
+#[post("/api/v1/admin/accounts/<account_id>/unsilence")]
+pub async fn unsilence_account(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    account_id: &str, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::AdminAccount>, super::Error> {
+    _set_account_flag(db, config, user, account_id, localizer, |id, c| {
+        diesel::update(crate::schema::accounts::dsl::accounts.find(id))
+            .set(crate::schema::accounts::dsl::silenced_at.eq(None::<chrono::NaiveDateTime>))
+            .get_result(c)
+    }).await
+}
+
+#[post("/api/v1/admin/accounts/<account_id>/unsensitive")]
+pub async fn unsensitive_account(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    account_id: &str, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::AdminAccount>, super::Error> {
+    _set_account_flag(db, config, user, account_id, localizer, |id, c| {
+        diesel::update(crate::schema::accounts::dsl::accounts.find(id))
+            .set(crate::schema::accounts::dsl::sensitized_at.eq(None::<chrono::NaiveDateTime>))
+            .get_result(c)
+    }).await
+}
+
+#[get("/api/v1/admin/reports?<resolved>&<account_id>&<target_account_id>&<max_id>&<since_id>&<min_id>&<limit>")]
+pub async fn list_reports(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    resolved: Option<&str>, account_id: Option<&str>, target_account_id: Option<&str>,
+    max_id: Option<i64>, since_id: Option<i64>, min_id: Option<i64>, limit: Option<u64>,
+    host: &rocket::http::uri::Host<'_>, localizer: crate::i18n::Localizer
+) -> Result<super::LinkedResponse<rocket::serde::json::Json<Vec<super::objs::AdminReport>>>, super::Error> {
+    let moderator = get_moderator(&db, &localizer, &user, "admin:read:reports").await?;
+
+    let resolved = super::parse_bool(resolved, false, &localizer)?;
+    let account_id = match account_id {
+        Some(id) => Some(super::accounts::get_account_from_db(id, &db, &localizer).await?.id),
+        None => None
+    };
+    let target_account_id = match target_account_id {
+        Some(id) => Some(super::accounts::get_account_from_db(id, &db, &localizer).await?.id),
+        None => None
+    };
+
+    let limit = limit.unwrap_or(100);
+    if limit > 200 {
+        return Err(super::Error {
+            code: rocket::http::Status::BadRequest,
+            error: fl!(localizer, "limit-too-large")
+        });
+    }
+
+    let reports: Vec<models::Report> = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        let mut q = crate::schema::reports::dsl::reports
+            .limit(limit as i64)
+            .order_by(crate::schema::reports::dsl::iid.desc())
+            .into_boxed();
+        if resolved {
+            q = q.filter(crate::schema::reports::dsl::action_taken_at.is_not_null());
+        } else {
+            q = q.filter(crate::schema::reports::dsl::action_taken_at.is_null());
+        }
+        if let Some(account_id) = account_id {
+            q = q.filter(crate::schema::reports::dsl::account.eq(account_id));
+        }
+        if let Some(target_account_id) = target_account_id {
+            q = q.filter(crate::schema::reports::dsl::target_account.eq(target_account_id));
+        }
+        if let Some(min_id) = min_id {
+            q = q.filter(crate::schema::reports::dsl::iid.gt(min_id));
+        }
+        if let Some(max_id) = max_id {
+            q = q.filter(crate::schema::reports::dsl::iid.lt(max_id));
+        }
+        if let Some(since_id) = since_id {
+            q = q.filter(crate::schema::reports::dsl::iid.gt(since_id));
+        }
+        q.load(c)
+    }).await?;
+
+    let mut links = vec![];
+
+    if let Some(last_id) = reports.last().map(|a| a.iid) {
+        links.push(super::Link {
+            rel: "next".to_string(),
+            href: format!("https://{}/api/v1/admin/reports?max_id={}", host, last_id)
+        });
+    }
+    if let Some(first_id) = reports.first().map(|a| a.iid) {
+        links.push(super::Link {
+            rel: "prev".to_string(),
+            href: format!("https://{}/api/v1/admin/reports?min_id={}", host, first_id)
+        });
+    }
+
+    Ok(super::LinkedResponse {
+        inner: rocket::serde::json::Json(
+            futures::stream::iter(reports)
+                .map(|r| render_admin_report(&db, config, r, &moderator, &localizer))
+                .buffered(10)
+                .collect::<Vec<_>>().await.into_iter().collect::<Result<Vec<_>, _>>()?
+        ),
+        links
+    })
+}
+
+#[get("/api/v1/admin/reports/<report_id>")]
+pub async fn report(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    report_id: &str, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::AdminReport>, super::Error> {
+    let moderator = get_moderator(&db, &localizer, &user, "admin:read:reports").await?;
+    let report = get_report(report_id, &db, &localizer).await?;
+
+    Ok(rocket::serde::json::Json(render_admin_report(&db, config, report, &moderator, &localizer).await?))
+}
+
+async fn _update_report<F>(
+    db: crate::DbConn, config: &crate::AppConfig, user: super::oauth::TokenClaims, report_id: &str,
+    localizer: crate::i18n::Localizer, update: F
+) -> Result<rocket::serde::json::Json<super::objs::AdminReport>, super::Error>
+    where F: 'static + FnOnce(uuid::Uuid, &models::Account, &mut diesel::PgConnection) -> QueryResult<models::Report> + Send {
+    let moderator = get_moderator(&db, &localizer, &user, "admin:write:reports").await?;
+    let report = get_report(report_id, &db, &localizer).await?;
+
+    let report_id = report.id;
+    let m = moderator.clone();
+    let report = crate::db_run(&db, &localizer, move |c| update(report_id, &m, c)).await?;
+
+    Ok(rocket::serde::json::Json(render_admin_report(&db, config, report, &moderator, &localizer).await?))
+}
+
+#[post("/api/v1/admin/reports/<report_id>/assign_to_self")]
+pub async fn assign_report(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    report_id: &str, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::AdminReport>, super::Error> {
+    _update_report(db, config, user, report_id, localizer, |id, moderator, c| {
+        diesel::update(crate::schema::reports::dsl::reports.find(id))
+            .set((
+                crate::schema::reports::dsl::assigned_account.eq(moderator.id),
+                crate::schema::reports::dsl::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .get_result(c)
+    }).await
+}
+
+#[post("/api/v1/admin/reports/<report_id>/unassign")]
+pub async fn unassign_report(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    report_id: &str, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::AdminReport>, super::Error> {
+    _update_report(db, config, user, report_id, localizer, |id, _, c| {
+        diesel::update(crate::schema::reports::dsl::reports.find(id))
+            .set((
+                crate::schema::reports::dsl::assigned_account.eq(None::<uuid::Uuid>),
+                crate::schema::reports::dsl::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .get_result(c)
+    }).await
+}
+
+#[post("/api/v1/admin/reports/<report_id>/resolve")]
+pub async fn resolve_report(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    report_id: &str, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::AdminReport>, super::Error> {
+    _update_report(db, config, user, report_id, localizer, |id, moderator, c| {
+        let now = Utc::now().naive_utc();
+        diesel::update(crate::schema::reports::dsl::reports.find(id))
+            .set((
+                crate::schema::reports::dsl::action_taken_at.eq(now),
+                crate::schema::reports::dsl::action_taken_by_account.eq(moderator.id),
+                crate::schema::reports::dsl::updated_at.eq(now),
+            ))
+            .get_result(c)
+    }).await
+}
+
+#[post("/api/v1/admin/reports/<report_id>/reopen")]
+pub async fn reopen_report(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    report_id: &str, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::AdminReport>, super::Error> {
+    _update_report(db, config, user, report_id, localizer, |id, _, c| {
+        diesel::update(crate::schema::reports::dsl::reports.find(id))
+            .set((
+                crate::schema::reports::dsl::action_taken_at.eq(None::<chrono::NaiveDateTime>),
+                crate::schema::reports::dsl::action_taken_by_account.eq(None::<uuid::Uuid>),
+                crate::schema::reports::dsl::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .get_result(c)
+    }).await
+}
@@ -0,0 +1,198 @@
+use diesel::prelude::*;
+use chrono::prelude::*;
+use crate::models;
+
+fn render_archive(config: &crate::AppConfig, archive: models::AccountArchive) -> super::objs::Archive {
+    let now = Utc::now().naive_utc();
+    let expired = archive.finished_at.is_some() &&
+        (archive.file.is_none() || archive.expires_at.map(|e| e < now).unwrap_or(false));
+
+    super::objs::Archive {
+        id: archive.iid.to_string(),
+        state: if archive.failed_at.is_some() {
+            "failed"
+        } else if expired {
+            "expired"
+        } else if archive.finished_at.is_some() {
+            "ready"
+        } else {
+            "processing"
+        }.to_string(),
+        size: if expired { None } else { archive.size.map(|s| s as u64) },
+        url: if archive.finished_at.is_some() && !expired {
+            Some(format!("https://{}/api/v1/archives/{}/download", config.uri, archive.iid))
+        } else {
+            None
+        },
+        created_at: Utc.from_utc_datetime(&archive.created_at),
+        expires_at: archive.expires_at.map(|e| Utc.from_utc_datetime(&e)),
+    }
+}
+
+#[post("/api/v1/archives")]
+pub async fn create_archive(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    celery: &rocket::State<crate::CeleryApp>, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::Archive>, super::Error> {
+    if !user.has_scope("read") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let account = super::accounts::get_account(&db, &localizer, &user).await?;
+
+    let now = Utc::now().naive_utc();
+    let since = now - chrono::Duration::days(crate::tasks::archives::ARCHIVE_INTERVAL_DAYS);
+    if crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        crate::schema::account_archives::dsl::account_archives.filter(
+            crate::schema::account_archives::dsl::account.eq(account.id)
+        ).filter(
+            crate::schema::account_archives::dsl::created_at.gt(since)
+        ).filter(
+            crate::schema::account_archives::dsl::failed_at.is_null()
+        ).count().get_result::<i64>(c)
+    }).await? > 0 {
+        return Err(super::Error {
+            code: rocket::http::Status::TooManyRequests,
+            error: fl!(localizer, "error-archive-rate-limited")
+        });
+    }
+
+    let new_archive = models::NewAccountArchive {
+        id: uuid::Uuid::new_v4(),
+        account: account.id,
+        created_at: now,
+    };
+    let archive: models::AccountArchive = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        diesel::insert_into(crate::schema::account_archives::table)
+            .values(new_archive)
+            .get_result(c)
+    }).await?;
+
+    match celery.send_task(
+        crate::tasks::archives::build_archive::new(archive.clone())
+    ).await {
+        Ok(_) => {}
+        Err(e) => {
+            error!("Failed to submit celery task: {:?}", e);
+            crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+                diesel::update(crate::schema::account_archives::dsl::account_archives.find(archive.id))
+                    .set(crate::schema::account_archives::dsl::failed_at.eq(Utc::now().naive_utc()))
+                    .execute(c)
+            }).await?;
+            return Err(super::Error {
+                code: rocket::http::Status::InternalServerError,
+                error: fl!(localizer, "internal-server-error")
+            });
+        }
+    };
+
+    Ok(rocket::serde::json::Json(render_archive(config, archive)))
+}
+
+#[get("/api/v1/archives")]
+pub async fn archives(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<Vec<super::objs::Archive>>, super::Error> {
+    if !user.has_scope("read") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let account = super::accounts::get_account(&db, &localizer, &user).await?;
+
+    let archives: Vec<models::AccountArchive> = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        crate::schema::account_archives::dsl::account_archives.filter(
+            crate::schema::account_archives::dsl::account.eq(account.id)
+        ).order_by(crate::schema::account_archives::dsl::iid.desc()).get_results(c)
+    }).await?;
+
+    Ok(rocket::serde::json::Json(archives.into_iter().map(|a| render_archive(config, a)).collect()))
+}
+
+#[derive(Responder)]
+pub struct ArchiveDownload {
+    inner: rocket::fs::NamedFile,
+    content_type: rocket::http::ContentType,
+    disposition: rocket::http::Header<'static>,
+}
+
+#[get("/api/v1/archives/<archive_id>/download")]
+pub async fn download_archive(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    archive_id: &str, localizer: crate::i18n::Localizer
+) -> Result<ArchiveDownload, super::Error> {
+    if !user.has_scope("read") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let account = super::accounts::get_account(&db, &localizer, &user).await?;
+    let archive_id = match archive_id.parse::<i64>() {
+        Ok(id) => id,
+        Err(_) => return Err(super::Error {
+            code: rocket::http::Status::NotFound,
+            error: fl!(localizer, "error-archive-not-found")
+        })
+    };
+
+    let archive = match crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        crate::schema::account_archives::dsl::account_archives.filter(
+            crate::schema::account_archives::dsl::iid.eq(archive_id)
+        ).filter(
+            crate::schema::account_archives::dsl::account.eq(account.id)
+        ).get_result::<models::AccountArchive>(c).optional()
+    }).await? {
+        Some(a) => a,
+        None => return Err(super::Error {
+            code: rocket::http::Status::NotFound,
+            error: fl!(localizer, "error-archive-not-found")
+        })
+    };
+
+    if archive.failed_at.is_some() {
+        return Err(super::Error {
+            code: rocket::http::Status::Gone,
+            error: fl!(localizer, "error-archive-failed")
+        });
+    }
+
+    if archive.finished_at.is_none() {
+        return Err(super::Error {
+            code: rocket::http::Status::Conflict,
+            error: fl!(localizer, "error-archive-not-ready")
+        });
+    }
+
+    let file = match (&archive.file, archive.expires_at) {
+        (Some(f), Some(e)) if e >= Utc::now().naive_utc() => f,
+        _ => return Err(super::Error {
+            code: rocket::http::Status::Gone,
+            error: fl!(localizer, "error-archive-expired")
+        })
+    };
+
+    let inner = rocket::fs::NamedFile::open(config.archive_path.join(file)).await.map_err(|e| {
+        error!("Unable to open archive \"{}\": {}", file, e);
+        super::Error {
+            code: rocket::http::Status::InternalServerError,
+            error: fl!(localizer, "internal-server-error")
+        }
+    })?;
+
+    Ok(ArchiveDownload {
+        inner,
+        content_type: rocket::http::ContentType::ZIP,
+        disposition: rocket::http::Header::new(
+            "Content-Disposition",
+            format!("attachment; filename=\"archive-{}.zip\"", archive.created_at.format("%Y%m%d%H%M%S"))
+        ),
+    })
+}
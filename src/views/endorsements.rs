@@ -0,0 +1,150 @@
+use diesel::prelude::*;
+use chrono::prelude::*;
+use crate::models;
+
+#[get("/api/v1/endorsements?<limit>&<min_id>&<max_id>")]
+pub async fn endorsements(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    limit: Option<u64>, min_id: Option<i64>, max_id: Option<i64>, host: &rocket::http::uri::Host<'_>,
+    localizer: crate::i18n::Localizer
+) -> Result<super::LinkedResponse<rocket::serde::json::Json<Vec<super::objs::Account>>>, super::Error> {
+    if !user.has_scope("read:accounts") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let limit = limit.unwrap_or(40);
+    if limit > 80 {
+        return Err(super::Error {
+            code: rocket::http::Status::BadRequest,
+            error: fl!(localizer, "limit-too-large")
+        });
+    }
+
+    let account = super::accounts::get_account(&db, &localizer, &user).await?;
+
+    let endorsed: Vec<(models::Endorsement, models::Account)> = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        let mut sel = crate::schema::endorsements::dsl::endorsements.filter(
+            crate::schema::endorsements::dsl::account.eq(account.id)
+        ).inner_join(
+            crate::schema::accounts::table.on(
+                crate::schema::endorsements::dsl::endorsed.eq(crate::schema::accounts::dsl::id)
+            )
+        ).filter(
+            crate::schema::accounts::dsl::deleted_at.is_null()
+        ).order_by(crate::schema::endorsements::dsl::iid.desc()).limit(limit as i64).into_boxed();
+        if let Some(min_id) = min_id {
+            sel = sel.filter(crate::schema::endorsements::dsl::iid.gt(min_id));
+        }
+        if let Some(max_id) = max_id {
+            sel = sel.filter(crate::schema::endorsements::dsl::iid.lt(max_id));
+        }
+        sel.get_results(c)
+    }).await?;
+
+    let mut links = vec![];
+
+    if let Some(last_id) = endorsed.first().map(|(e, _)| e.iid) {
+        links.push(super::Link {
+            rel: "prev".to_string(),
+            href: format!("https://{}/api/v1/endorsements?min_id={}", host, last_id)
+        });
+    }
+    if let Some(first_id) = endorsed.last().map(|(e, _)| e.iid) {
+        links.push(super::Link {
+            rel: "next".to_string(),
+            href: format!("https://{}/api/v1/endorsements?max_id={}", host, first_id)
+        });
+    }
+
+    Ok(super::LinkedResponse {
+        inner: rocket::serde::json::Json(futures::future::try_join_all(
+            endorsed.into_iter().map(|(_, a)| super::accounts::render_account(config, &db, &localizer, a)).collect::<Vec<_>>()
+        ).await?),
+        links,
+    })
+}
+
+#[post("/api/v1/accounts/<account_id>/pin")]
+pub async fn endorse_account(
+    db: crate::DbConn, user: super::oauth::TokenClaims, account_id: String, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::Relationship>, super::Error> {
+    if !user.has_scope("write:accounts") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let account = super::accounts::get_account(&db, &localizer, &user).await?;
+    let endorsed_account = super::accounts::get_account_from_db(&account_id, &db, &localizer).await?;
+
+    // Like Mastodon, only followed accounts can be featured on a profile
+    let own_account_id = account.id;
+    let endorsed_account_id = endorsed_account.id;
+    if crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        crate::schema::following::dsl::following.filter(
+            crate::schema::following::dsl::follower.eq(own_account_id)
+        ).filter(
+            crate::schema::following::dsl::followee.eq(endorsed_account_id)
+        ).filter(
+            crate::schema::following::dsl::pending.eq(false)
+        ).count().get_result::<i64>(c)
+    }).await? == 0 {
+        return Err(super::Error {
+            code: rocket::http::Status::UnprocessableEntity,
+            error: fl!(localizer, "error-endorse-not-following")
+        });
+    }
+
+    crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        diesel::insert_into(crate::schema::endorsements::table)
+            .values(models::NewEndorsement {
+                id: uuid::Uuid::new_v4(),
+                account: own_account_id,
+                endorsed: endorsed_account_id,
+                created_at: Utc::now().naive_utc(),
+            })
+            .on_conflict_do_nothing()
+            .execute(c)
+    }).await?;
+
+    let relationship = super::accounts::render_relationship(
+        &db, &localizer, &account, std::borrow::Cow::Borrowed(&endorsed_account)
+    ).await?;
+
+    Ok(rocket::serde::json::Json(relationship))
+}
+
+#[post("/api/v1/accounts/<account_id>/unpin")]
+pub async fn unendorse_account(
+    db: crate::DbConn, user: super::oauth::TokenClaims, account_id: String, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::Relationship>, super::Error> {
+    if !user.has_scope("write:accounts") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let account = super::accounts::get_account(&db, &localizer, &user).await?;
+    let endorsed_account = super::accounts::get_account_from_db(&account_id, &db, &localizer).await?;
+
+    let own_account_id = account.id;
+    let endorsed_account_id = endorsed_account.id;
+    crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        diesel::delete(crate::schema::endorsements::dsl::endorsements.filter(
+            crate::schema::endorsements::dsl::account.eq(own_account_id)
+        ).filter(
+            crate::schema::endorsements::dsl::endorsed.eq(endorsed_account_id)
+        )).execute(c)
+    }).await?;
+
+    let relationship = super::accounts::render_relationship(
+        &db, &localizer, &account, std::borrow::Cow::Borrowed(&endorsed_account)
+    ).await?;
+
+    Ok(rocket::serde::json::Json(relationship))
+}
@@ -0,0 +1,129 @@
+use diesel::prelude::*;
+use crate::models;
+
+fn export_scope(file: &str) -> Option<&'static str> {
+    match file {
+        "following_accounts.csv" | "followers.csv" => Some("read:follows"),
+        "bookmarks.csv" => Some("read:bookmarks"),
+        _ => None
+    }
+}
+
+fn acct(config: &crate::AppConfig, account: &models::Account) -> String {
+    let domain = if account.local {
+        Some(config.uri.clone())
+    } else {
+        account.url.as_deref().or(account.actor.as_deref()).and_then(
+            |u| reqwest::Url::parse(u).ok()?.domain().map(|d| d.to_string())
+        )
+    };
+    match domain {
+        Some(d) => format!("{}@{}", account.username, d),
+        None => account.username.clone()
+    }
+}
+
+async fn follow_accounts(
+    db: &crate::DbConn, localizer: &crate::i18n::Localizer, account: &models::Account, followers: bool
+) -> Result<Vec<(models::Following, models::Account)>, super::Error> {
+    let account_id = account.id;
+    crate::db_run(db, localizer, move |c| -> QueryResult<_> {
+        let mut sel = crate::schema::following::dsl::following.filter(
+            crate::schema::following::dsl::pending.eq(false)
+        ).order_by(crate::schema::following::dsl::iid.asc()).into_boxed();
+        sel = if followers {
+            sel.filter(crate::schema::following::dsl::followee.eq(account_id))
+        } else {
+            sel.filter(crate::schema::following::dsl::follower.eq(account_id))
+        };
+        let followings: Vec<models::Following> = sel.get_results(c)?;
+
+        let accounts: Vec<models::Account> = crate::schema::accounts::dsl::accounts.filter(
+            crate::schema::accounts::dsl::id.eq_any(followings.iter().map(|f| if followers {
+                f.follower
+            } else {
+                f.followee
+            }).collect::<Vec<_>>())
+        ).filter(
+            crate::schema::accounts::dsl::deleted_at.is_null()
+        ).get_results(c)?;
+
+        Ok(followings.into_iter().filter_map(|f| {
+            let other = if followers { f.follower } else { f.followee };
+            let a = accounts.iter().find(|a| a.id == other)?.clone();
+            Some((f, a))
+        }).collect())
+    }).await
+}
+
+#[get("/api/v1/exports/<file>")]
+pub async fn export(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    file: &str, localizer: crate::i18n::Localizer
+) -> Result<(rocket::http::ContentType, Vec<u8>), super::Error> {
+    let scope = match export_scope(file) {
+        Some(s) => s,
+        None => return Err(super::Error {
+            code: rocket::http::Status::NotFound,
+            error: fl!(localizer, "error-export-not-found")
+        })
+    };
+    if !user.has_scope(scope) {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let account = super::accounts::get_account(&db, &localizer, &user).await?;
+
+    let mut rows: Vec<Vec<String>> = vec![];
+    match file {
+        "following_accounts.csv" => {
+            rows.push(vec![
+                "Account address".to_string(), "Show boosts".to_string(),
+                "Notify on new posts".to_string(), "Languages".to_string()
+            ]);
+            rows.extend(follow_accounts(&db, &localizer, &account, false).await?.into_iter().map(|(f, a)| vec![
+                acct(config, &a), f.reblogs.to_string(), f.notify.to_string(), String::new()
+            ]));
+        }
+        "followers.csv" => {
+            rows.push(vec!["Account address".to_string()]);
+            rows.extend(follow_accounts(&db, &localizer, &account, true).await?.into_iter().map(|(_, a)| vec![
+                acct(config, &a)
+            ]));
+        }
+        "bookmarks.csv" => {
+            let statuses: Vec<models::Status> = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+                crate::schema::bookmarks::dsl::bookmarks.filter(
+                    crate::schema::bookmarks::dsl::account.eq(account.id)
+                ).inner_join(
+                    crate::schema::statuses::table.on(
+                        crate::schema::bookmarks::dsl::status.eq(crate::schema::statuses::dsl::id)
+                    )
+                ).filter(
+                    crate::schema::statuses::dsl::deleted_at.is_null()
+                ).order_by(crate::schema::bookmarks::dsl::iid.asc())
+                    .select(crate::schema::statuses::all_columns)
+                    .get_results(c)
+            }).await?;
+            rows.extend(statuses.into_iter().map(|s| vec![s.url(&config.uri)]));
+        }
+        _ => unreachable!()
+    }
+
+    let mut writer = csv::WriterBuilder::new().flexible(true).from_writer(vec![]);
+    for row in rows {
+        writer.write_record(&row).map_err(|_| super::Error {
+            code: rocket::http::Status::InternalServerError,
+            error: fl!(localizer, "internal-server-error")
+        })?;
+    }
+    let data = writer.into_inner().map_err(|_| super::Error {
+        code: rocket::http::Status::InternalServerError,
+        error: fl!(localizer, "internal-server-error")
+    })?;
+
+    Ok((rocket::http::ContentType::CSV, data))
+}
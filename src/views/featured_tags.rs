@@ -0,0 +1,317 @@
+use diesel::prelude::*;
+use chrono::prelude::*;
+use crate::models;
+
+pub const MAX_FEATURED_TAGS: usize = 10;
+
+async fn render_featured_tags(
+    db: &crate::DbConn, config: &crate::AppConfig, localizer: &crate::i18n::Localizer, account: &models::Account
+) -> Result<Vec<super::objs::FeaturedTag>, super::Error> {
+    let account_id = account.id;
+    let tags: Vec<(models::FeaturedTag, models::Tag, i64, Option<NaiveDateTime>)> = crate::db_run(db, localizer, move |c| -> QueryResult<_> {
+        crate::schema::featured_tags::dsl::featured_tags.filter(
+            crate::schema::featured_tags::dsl::account.eq(account_id)
+        ).inner_join(
+            crate::schema::tags::table.on(
+                crate::schema::featured_tags::dsl::tag.eq(crate::schema::tags::dsl::id)
+            )
+        ).order_by(crate::schema::featured_tags::dsl::iid.asc())
+            .get_results::<(models::FeaturedTag, models::Tag)>(c)?
+            .into_iter()
+            .map(|(f, t)| {
+                let statuses = crate::schema::statuses::dsl::statuses.filter(
+                    crate::schema::statuses::dsl::account_id.eq(account_id)
+                ).filter(
+                    crate::schema::statuses::dsl::deleted_at.is_null()
+                ).filter(
+                    crate::schema::statuses::dsl::visible.eq(true)
+                ).filter(
+                    crate::schema::statuses::dsl::id.eq_any(
+                        crate::schema::status_tags::dsl::status_tags.filter(
+                            crate::schema::status_tags::dsl::tag.eq(t.id)
+                        ).select(crate::schema::status_tags::dsl::status)
+                    )
+                );
+                let count = statuses.count().get_result::<i64>(c)?;
+                let last = statuses.select(diesel::dsl::max(crate::schema::statuses::dsl::created_at))
+                    .get_result::<Option<NaiveDateTime>>(c)?;
+                Ok((f, t, count, last))
+            })
+            .collect()
+    }).await?;
+
+    Ok(tags.into_iter().map(|(f, t, count, last)| super::objs::FeaturedTag {
+        id: f.iid.to_string(),
+        url: format!("https://{}/tags/{}", config.uri, t.name),
+        name: t.name,
+        statuses_count: count as u64,
+        last_status_at: last.map(|l| l.date()),
+    }).collect())
+}
+
+#[get("/api/v1/featured_tags")]
+pub async fn featured_tags(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<Vec<super::objs::FeaturedTag>>, super::Error> {
+    if !user.has_scope("read:accounts") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let account = super::accounts::get_account(&db, &localizer, &user).await?;
+
+    Ok(rocket::serde::json::Json(render_featured_tags(&db, config, &localizer, &account).await?))
+}
+
+#[get("/api/v1/accounts/<account_id>/featured_tags")]
+pub async fn account_featured_tags(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, account_id: String,
+    localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<Vec<super::objs::FeaturedTag>>, super::Error> {
+    let account = super::accounts::get_account_from_db(&account_id, &db, &localizer).await?;
+
+    Ok(rocket::serde::json::Json(render_featured_tags(&db, config, &localizer, &account).await?))
+}
+
+#[derive(FromForm)]
+pub struct FeaturedTagForm<'a> {
+    name: &'a str,
+}
+
+#[derive(Deserialize)]
+pub struct FeaturedTagJson<'a> {
+    name: &'a str,
+}
+
+#[post("/api/v1/featured_tags", data = "<form>", rank = 1)]
+pub async fn create_featured_tag_form(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    form: rocket::form::Form<FeaturedTagForm<'_>>, celery: &rocket::State<crate::CeleryApp>,
+    localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::FeaturedTag>, super::Error> {
+    _create_featured_tag(db, config, user, form.name, celery, localizer).await
+}
+
+#[post("/api/v1/featured_tags", data = "<form>", rank = 2)]
+pub async fn create_featured_tag_json(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    form: rocket::serde::json::Json<FeaturedTagJson<'_>>, celery: &rocket::State<crate::CeleryApp>,
+    localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::FeaturedTag>, super::Error> {
+    _create_featured_tag(db, config, user, form.name, celery, localizer).await
+}
+
+async fn _create_featured_tag(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    name: &str, celery: &rocket::State<crate::CeleryApp>, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::FeaturedTag>, super::Error> {
+    if !user.has_scope("write:accounts") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let name = crate::tasks::statuses::normalise_hashtag(name);
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return Err(super::Error {
+            code: rocket::http::Status::UnprocessableEntity,
+            error: fl!(localizer, "error-invalid-tag")
+        });
+    }
+
+    let account = super::accounts::get_account(&db, &localizer, &user).await?;
+
+    let account_id = account.id;
+    let tag_name = name.clone();
+    let created = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        c.transaction(|| {
+            diesel::insert_into(crate::schema::tags::table)
+                .values(models::NewTag {
+                    id: uuid::Uuid::new_v4(),
+                    name: tag_name.clone(),
+                })
+                .on_conflict(crate::schema::tags::dsl::name)
+                .do_nothing()
+                .execute(c)?;
+            let tag: models::Tag = crate::schema::tags::dsl::tags.filter(
+                crate::schema::tags::dsl::name.eq(&tag_name)
+            ).get_result(c)?;
+
+            let featured = crate::schema::featured_tags::dsl::featured_tags.filter(
+                crate::schema::featured_tags::dsl::account.eq(account_id)
+            ).select(crate::schema::featured_tags::dsl::tag).get_results::<uuid::Uuid>(c)?;
+            if featured.contains(&tag.id) {
+                return Ok(Some(false));
+            }
+            if featured.len() >= MAX_FEATURED_TAGS {
+                return Ok(None);
+            }
+
+            diesel::insert_into(crate::schema::featured_tags::table)
+                .values(models::NewFeaturedTag {
+                    id: uuid::Uuid::new_v4(),
+                    account: account_id,
+                    tag: tag.id,
+                    created_at: Utc::now().naive_utc(),
+                })
+                .execute(c)?;
+            Ok(Some(true))
+        })
+    }).await?;
+
+    match created {
+        None => return Err(super::Error {
+            code: rocket::http::Status::UnprocessableEntity,
+            error: fl!(localizer, "error-too-many-featured-tags")
+        }),
+        Some(true) => match celery.send_task(
+            crate::tasks::accounts::deliver_featured_tag::new(account.clone(), name.clone(), false)
+        ).await {
+            Ok(_) => {}
+            Err(e) => {
+                error!("Failed to submit celery task: {:?}", e);
+                return Err(super::Error {
+                    code: rocket::http::Status::InternalServerError,
+                    error: fl!(localizer, "internal-server-error")
+                });
+            }
+        },
+        Some(false) => {}
+    }
+
+    render_featured_tags(&db, config, &localizer, &account).await?
+        .into_iter().find(|t| t.name == name)
+        .map(rocket::serde::json::Json)
+        .ok_or(super::Error {
+            code: rocket::http::Status::InternalServerError,
+            error: fl!(localizer, "internal-server-error")
+        })
+}
+
+#[delete("/api/v1/featured_tags/<tag_id>")]
+pub async fn delete_featured_tag(
+    db: crate::DbConn, user: super::oauth::TokenClaims, tag_id: &str,
+    celery: &rocket::State<crate::CeleryApp>, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<()>, super::Error> {
+    if !user.has_scope("write:accounts") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let account = super::accounts::get_account(&db, &localizer, &user).await?;
+    let tag_id = match tag_id.parse::<i64>() {
+        Ok(id) => id,
+        Err(_) => return Err(super::Error {
+            code: rocket::http::Status::NotFound,
+            error: fl!(localizer, "error-featured-tag-not-found")
+        })
+    };
+
+    let account_id = account.id;
+    let tag = match crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        crate::schema::featured_tags::dsl::featured_tags.filter(
+            crate::schema::featured_tags::dsl::iid.eq(tag_id)
+        ).filter(
+            crate::schema::featured_tags::dsl::account.eq(account_id)
+        ).inner_join(
+            crate::schema::tags::table.on(
+                crate::schema::featured_tags::dsl::tag.eq(crate::schema::tags::dsl::id)
+            )
+        ).get_result::<(models::FeaturedTag, models::Tag)>(c).optional()
+    }).await? {
+        Some(t) => t,
+        None => return Err(super::Error {
+            code: rocket::http::Status::NotFound,
+            error: fl!(localizer, "error-featured-tag-not-found")
+        })
+    };
+
+    let featured_tag_id = tag.0.id;
+    crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        diesel::delete(crate::schema::featured_tags::dsl::featured_tags.find(featured_tag_id))
+            .execute(c)
+    }).await?;
+
+    match celery.send_task(
+        crate::tasks::accounts::deliver_featured_tag::new(account, tag.1.name, true)
+    ).await {
+        Ok(_) => {}
+        Err(e) => {
+            error!("Failed to submit celery task: {:?}", e);
+            return Err(super::Error {
+                code: rocket::http::Status::InternalServerError,
+                error: fl!(localizer, "internal-server-error")
+            });
+        }
+    };
+
+    Ok(rocket::serde::json::Json(()))
+}
+
+#[get("/api/v1/featured_tags/suggestions")]
+pub async fn featured_tag_suggestions(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<Vec<super::objs::Tag>>, super::Error> {
+    if !user.has_scope("read:accounts") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let account = super::accounts::get_account(&db, &localizer, &user).await?;
+
+    // The tags most recently used by the account that aren't already featured
+    let account_id = account.id;
+    let tags: Vec<(models::Tag, Vec<super::objs::TagHistory>)> = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        let used: Vec<(models::Tag, NaiveDateTime)> = crate::schema::status_tags::dsl::status_tags.inner_join(
+            crate::schema::statuses::table.on(
+                crate::schema::status_tags::dsl::status.eq(crate::schema::statuses::dsl::id)
+            )
+        ).inner_join(
+            crate::schema::tags::table.on(
+                crate::schema::status_tags::dsl::tag.eq(crate::schema::tags::dsl::id)
+            )
+        ).filter(
+            crate::schema::statuses::dsl::account_id.eq(account_id)
+        ).filter(
+            crate::schema::statuses::dsl::deleted_at.is_null()
+        ).filter(
+            diesel::dsl::not(crate::schema::status_tags::dsl::tag.eq_any(
+                crate::schema::featured_tags::dsl::featured_tags.filter(
+                    crate::schema::featured_tags::dsl::account.eq(account_id)
+                ).select(crate::schema::featured_tags::dsl::tag)
+            ))
+        ).order_by(crate::schema::statuses::dsl::created_at.desc())
+            .select((crate::schema::tags::all_columns, crate::schema::statuses::dsl::created_at))
+            .limit(200)
+            .get_results(c)?;
+
+        let mut tags: Vec<models::Tag> = vec![];
+        for (tag, _) in used {
+            if !tags.iter().any(|t| t.id == tag.id) {
+                tags.push(tag);
+            }
+        }
+        tags.truncate(MAX_FEATURED_TAGS);
+        let mut histories = super::trends::tag_histories(c, &tags.iter().map(|t| t.id).collect::<Vec<_>>())?;
+        Ok(tags.into_iter().map(|t| {
+            let history = histories.remove(&t.id).unwrap_or_default();
+            (t, history)
+        }).collect())
+    }).await?;
+
+    Ok(rocket::serde::json::Json(tags.into_iter().map(|(t, history)| super::objs::Tag {
+        url: format!("https://{}/tags/{}", config.uri, t.name),
+        name: t.name,
+        history,
+        following: None,
+    }).collect()))
+}
@@ -0,0 +1,218 @@
+use diesel::prelude::*;
+use chrono::prelude::*;
+use crate::models;
+
+async fn get_group(
+    db: &crate::DbConn, localizer: &crate::i18n::Localizer, user: &super::oauth::TokenClaims
+) -> Result<models::Account, super::Error> {
+    let account = super::accounts::get_account(db, localizer, user).await?;
+    if !account.group {
+        return Err(super::Error {
+            code: rocket::http::Status::UnprocessableEntity,
+            error: fl!(localizer, "error-not-a-group")
+        });
+    }
+    Ok(account)
+}
+
+async fn get_group_post(
+    db: &crate::DbConn, localizer: &crate::i18n::Localizer, group: &models::Account, post_id: &str
+) -> Result<models::GroupPost, super::Error> {
+    let post_id = match post_id.parse::<i64>() {
+        Ok(id) => id,
+        Err(_) => return Err(super::Error {
+            code: rocket::http::Status::NotFound,
+            error: fl!(localizer, "error-group-post-not-found")
+        })
+    };
+
+    let group_id = group.id;
+    match crate::db_run(db, localizer, move |c| -> QueryResult<_> {
+        crate::schema::group_posts::dsl::group_posts.filter(
+            crate::schema::group_posts::dsl::iid.eq(post_id)
+        ).filter(
+            crate::schema::group_posts::dsl::group_id.eq(group_id)
+        ).get_result::<models::GroupPost>(c).optional()
+    }).await? {
+        Some(p) => Ok(p),
+        None => Err(super::Error {
+            code: rocket::http::Status::NotFound,
+            error: fl!(localizer, "error-group-post-not-found")
+        })
+    }
+}
+
+async fn render_group_post(
+    config: &crate::AppConfig, db: &crate::DbConn, localizer: &crate::i18n::Localizer,
+    group: &models::Account, post: models::GroupPost, status: models::Status,
+) -> Result<super::objs::GroupPost, super::Error> {
+    Ok(super::objs::GroupPost {
+        id: post.iid.to_string(),
+        state: if post.reviewed_at.is_none() {
+            "pending"
+        } else if post.approved {
+            "approved"
+        } else {
+            "rejected"
+        }.to_string(),
+        status: super::statuses::render_status(config, db, status, localizer, Some(group)).await?,
+        created_at: Utc.from_utc_datetime(&post.created_at),
+    })
+}
+
+#[get("/api/v1/groups/posts?<pending>")]
+pub async fn group_posts(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    pending: Option<bool>, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<Vec<super::objs::GroupPost>>, super::Error> {
+    if !user.has_scope("read:statuses") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let group = get_group(&db, &localizer, &user).await?;
+
+    let group_id = group.id;
+    let posts: Vec<(models::GroupPost, models::Status)> = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        let mut sel = crate::schema::group_posts::dsl::group_posts.filter(
+            crate::schema::group_posts::dsl::group_id.eq(group_id)
+        ).inner_join(
+            crate::schema::statuses::table.on(
+                crate::schema::group_posts::dsl::status.eq(crate::schema::statuses::dsl::id)
+            )
+        ).filter(
+            crate::schema::statuses::dsl::deleted_at.is_null()
+        ).order_by(crate::schema::group_posts::dsl::iid.desc()).into_boxed();
+        if pending == Some(true) {
+            sel = sel.filter(crate::schema::group_posts::dsl::reviewed_at.is_null());
+        }
+        sel.limit(100).get_results(c)
+    }).await?;
+
+    let mut out = vec![];
+    for (post, status) in posts {
+        out.push(render_group_post(config, &db, &localizer, &group, post, status).await?);
+    }
+
+    Ok(rocket::serde::json::Json(out))
+}
+
+async fn review_group_post(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    post_id: &str, approve: bool, celery: &rocket::State<crate::CeleryApp>, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::GroupPost>, super::Error> {
+    if !user.has_scope("write:statuses") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let group = get_group(&db, &localizer, &user).await?;
+    let post = get_group_post(&db, &localizer, &group, post_id).await?;
+
+    let (post, status) = match crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        let post = match diesel::update(crate::schema::group_posts::dsl::group_posts
+            .find(post.id)
+            .filter(crate::schema::group_posts::dsl::reviewed_at.is_null()))
+            .set((
+                crate::schema::group_posts::dsl::approved.eq(approve),
+                crate::schema::group_posts::dsl::reviewed_at.eq(Utc::now().naive_utc()),
+            ))
+            .get_result::<models::GroupPost>(c).optional()? {
+            Some(p) => p,
+            None => return Ok(None)
+        };
+        let status = crate::schema::statuses::dsl::statuses.find(post.status)
+            .get_result::<models::Status>(c)?;
+        Ok(Some((post, status)))
+    }).await? {
+        Some(p) => p,
+        None => return Err(super::Error {
+            code: rocket::http::Status::Conflict,
+            error: fl!(localizer, "error-group-post-already-reviewed")
+        })
+    };
+
+    if approve {
+        match celery.send_task(
+            crate::tasks::groups::announce_group_post::new(post.clone())
+        ).await {
+            Ok(_) => {}
+            Err(e) => {
+                error!("Failed to submit celery task: {:?}", e);
+                return Err(super::Error {
+                    code: rocket::http::Status::InternalServerError,
+                    error: fl!(localizer, "internal-server-error")
+                });
+            }
+        };
+    }
+
+    Ok(rocket::serde::json::Json(render_group_post(config, &db, &localizer, &group, post, status).await?))
+}
+
+#[post("/api/v1/groups/posts/<post_id>/approve")]
+pub async fn approve_group_post(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    post_id: &str, celery: &rocket::State<crate::CeleryApp>, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::GroupPost>, super::Error> {
+    review_group_post(db, config, user, post_id, true, celery, localizer).await
+}
+
+#[post("/api/v1/groups/posts/<post_id>/reject")]
+pub async fn reject_group_post(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    post_id: &str, celery: &rocket::State<crate::CeleryApp>, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::GroupPost>, super::Error> {
+    review_group_post(db, config, user, post_id, false, celery, localizer).await
+}
+
+#[derive(FromForm)]
+pub struct GroupSettingsForm {
+    review_posts: bool,
+}
+
+#[derive(Deserialize)]
+pub struct GroupSettingsJson {
+    review_posts: bool,
+}
+
+#[post("/api/v1/groups/settings", data = "<form>", rank = 1)]
+pub async fn group_settings_form(
+    db: crate::DbConn, user: super::oauth::TokenClaims, form: rocket::form::Form<GroupSettingsForm>,
+    localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<()>, super::Error> {
+    _group_settings(db, user, form.review_posts, localizer).await
+}
+
+#[post("/api/v1/groups/settings", data = "<form>", rank = 2)]
+pub async fn group_settings_json(
+    db: crate::DbConn, user: super::oauth::TokenClaims, form: rocket::serde::json::Json<GroupSettingsJson>,
+    localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<()>, super::Error> {
+    _group_settings(db, user, form.review_posts, localizer).await
+}
+
+async fn _group_settings(
+    db: crate::DbConn, user: super::oauth::TokenClaims, review_posts: bool, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<()>, super::Error> {
+    if !user.has_scope("write:accounts") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let group = get_group(&db, &localizer, &user).await?;
+
+    crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        diesel::update(crate::schema::accounts::dsl::accounts.find(group.id))
+            .set(crate::schema::accounts::dsl::group_review_posts.eq(review_posts))
+            .execute(c)
+    }).await?;
+
+    Ok(rocket::serde::json::Json(()))
+}
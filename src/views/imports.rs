@@ -0,0 +1,193 @@
+use diesel::prelude::*;
+use chrono::prelude::*;
+use crate::models;
+
+const IMPORT_MODES: [&str; 2] = ["merge", "overwrite"];
+const MAX_IMPORT_ROWS: usize = 8000;
+
+fn import_scope(import_type: &str) -> &'static str {
+    match import_type {
+        "bookmarks" => "write:bookmarks",
+        _ => "write:follows",
+    }
+}
+
+fn is_header(import_type: &str, row: &[String]) -> bool {
+    match import_type {
+        "following" => row.first().map(|c| c.trim().eq_ignore_ascii_case("Account address")).unwrap_or(false),
+        _ => false
+    }
+}
+
+fn render_import(import: models::Import) -> super::objs::Import {
+    super::objs::Import {
+        id: import.iid.to_string(),
+        import_type: import.import_type,
+        mode: if import.overwrite { "overwrite" } else { "merge" }.to_string(),
+        state: if import.finished_at.is_some() { "finished" } else { "in_progress" }.to_string(),
+        total_items: import.total_items as u64,
+        imported_items: import.imported_items as u64,
+        failed_items: import.failed_items as u64,
+        created_at: Utc.from_utc_datetime(&import.created_at),
+        finished_at: import.finished_at.map(|f| Utc.from_utc_datetime(&f)),
+    }
+}
+
+#[derive(FromForm)]
+pub struct ImportForm<'a> {
+    #[field(name = "type")]
+    import_type: &'a str,
+    mode: Option<&'a str>,
+    data: rocket::fs::TempFile<'a>,
+}
+
+#[post("/api/v1/imports", data = "<form>")]
+pub async fn create_import(
+    db: crate::DbConn, user: super::oauth::TokenClaims, form: rocket::form::Form<ImportForm<'_>>,
+    celery: &rocket::State<crate::CeleryApp>, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::Import>, super::Error> {
+    if !crate::tasks::imports::IMPORT_TYPES.contains(&form.import_type) {
+        return Err(super::Error {
+            code: rocket::http::Status::UnprocessableEntity,
+            error: fl!(localizer, "error-unsupported-import-type")
+        });
+    }
+
+    if !user.has_scope(import_scope(form.import_type)) {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let mode = form.mode.unwrap_or("merge");
+    if !IMPORT_MODES.contains(&mode) {
+        return Err(super::Error {
+            code: rocket::http::Status::UnprocessableEntity,
+            error: fl!(localizer, "error-invalid-import-mode")
+        });
+    }
+
+    let account = super::accounts::get_account(&db, &localizer, &user).await?;
+
+    let data = match form.data.path() {
+        Some(p) => tokio::fs::read(p).await.map_err(|_| super::Error {
+            code: rocket::http::Status::InternalServerError,
+            error: fl!(localizer, "internal-server-error")
+        })?,
+        None => return Err(super::Error {
+            code: rocket::http::Status::InternalServerError,
+            error: fl!(localizer, "internal-server-error")
+        })
+    };
+
+    let mut rows = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(data.as_slice())
+        .records()
+        .map(|r| r.map(|r| r.iter().map(|c| c.to_string()).collect::<Vec<_>>()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| super::Error {
+            code: rocket::http::Status::UnprocessableEntity,
+            error: fl!(localizer, "error-invalid-import-file")
+        })?;
+    if rows.first().map(|r| is_header(form.import_type, r)).unwrap_or(false) {
+        rows.remove(0);
+    }
+    rows.retain(|r| r.first().map(|c| !c.trim().is_empty()).unwrap_or(false));
+
+    if rows.len() > MAX_IMPORT_ROWS {
+        return Err(super::Error {
+            code: rocket::http::Status::UnprocessableEntity,
+            error: fl!(localizer, "error-import-too-large")
+        });
+    }
+
+    let new_import = models::NewImport {
+        id: uuid::Uuid::new_v4(),
+        account: account.id,
+        import_type: form.import_type.to_string(),
+        overwrite: mode == "overwrite",
+        total_items: rows.len() as i32,
+        created_at: Utc::now().naive_utc(),
+    };
+    let import: models::Import = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        diesel::insert_into(crate::schema::imports::table)
+            .values(new_import)
+            .get_result(c)
+    }).await?;
+
+    match celery.send_task(
+        crate::tasks::imports::process_import::new(import.clone(), rows)
+    ).await {
+        Ok(_) => {}
+        Err(e) => {
+            error!("Failed to submit celery task: {:?}", e);
+            return Err(super::Error {
+                code: rocket::http::Status::InternalServerError,
+                error: fl!(localizer, "internal-server-error")
+            });
+        }
+    };
+
+    Ok(rocket::serde::json::Json(render_import(import)))
+}
+
+#[get("/api/v1/imports")]
+pub async fn imports(
+    db: crate::DbConn, user: super::oauth::TokenClaims, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<Vec<super::objs::Import>>, super::Error> {
+    if !user.has_scope("read:accounts") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let account = super::accounts::get_account(&db, &localizer, &user).await?;
+
+    let imports: Vec<models::Import> = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        crate::schema::imports::dsl::imports.filter(
+            crate::schema::imports::dsl::account.eq(account.id)
+        ).order_by(crate::schema::imports::dsl::iid.desc()).get_results(c)
+    }).await?;
+
+    Ok(rocket::serde::json::Json(imports.into_iter().map(render_import).collect()))
+}
+
+#[get("/api/v1/imports/<import_id>")]
+pub async fn import(
+    db: crate::DbConn, user: super::oauth::TokenClaims, import_id: &str,
+    localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::Import>, super::Error> {
+    if !user.has_scope("read:accounts") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let account = super::accounts::get_account(&db, &localizer, &user).await?;
+    let import_id = match import_id.parse::<i64>() {
+        Ok(id) => id,
+        Err(_) => return Err(super::Error {
+            code: rocket::http::Status::NotFound,
+            error: fl!(localizer, "error-import-not-found")
+        })
+    };
+
+    match crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        crate::schema::imports::dsl::imports.filter(
+            crate::schema::imports::dsl::iid.eq(import_id)
+        ).filter(
+            crate::schema::imports::dsl::account.eq(account.id)
+        ).get_result::<models::Import>(c).optional()
+    }).await? {
+        Some(i) => Ok(rocket::serde::json::Json(render_import(i))),
+        None => Err(super::Error {
+            code: rocket::http::Status::NotFound,
+            error: fl!(localizer, "error-import-not-found")
+        })
+    }
+}
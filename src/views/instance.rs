@@ -1,43 +1,97 @@
+use diesel::prelude::*;
 use crate::AppConfig;
 use crate::views::objs::InstanceV2Configuration;
 
+/// Reported for limits that aren't enforced, the largest value clients reliably parse as an integer
+const UNLIMITED: u64 = i32::MAX as u64;
+
+fn version() -> String {
+    format!("4.0.2 (compatible; Tafarn {})", env!("CARGO_PKG_VERSION"))
+}
+
+async fn contact_account(
+    db: &crate::DbConn, config: &AppConfig, localizer: &crate::i18n::Localizer
+) -> Result<Option<super::objs::Account>, super::Error> {
+    let username = match &config.instance.contact_account {
+        Some(u) => u.clone(),
+        None => return Ok(None)
+    };
+
+    let account: Option<crate::models::Account> = crate::db_run(db, localizer, move |c| -> QueryResult<_> {
+        crate::schema::accounts::dsl::accounts.filter(
+            crate::schema::accounts::dsl::username.eq(username)
+        ).filter(
+            crate::schema::accounts::dsl::local.eq(true)
+        ).first(c).optional()
+    }).await?;
+
+    Ok(match account {
+        Some(a) => Some(super::accounts::render_account(config, db, localizer, a).await?),
+        None => None
+    })
+}
+
+pub async fn get_instance_stats(
+    db: &crate::DbConn, localizer: &crate::i18n::Localizer
+) -> Result<Option<crate::models::InstanceStats>, super::Error> {
+    crate::db_run(db, localizer, move |c| -> QueryResult<_> {
+        crate::schema::instance_stats::dsl::instance_stats.first(c).optional()
+    }).await
+}
+
+fn rules(config: &AppConfig) -> Vec<super::objs::Rule> {
+    config.instance.rules.iter().enumerate().map(|(i, r)| super::objs::Rule {
+        id: (i + 1).to_string(),
+        text: r.clone(),
+    }).collect()
+}
+
 #[get("/api/v1/instance")]
-pub async fn instance(config: &rocket::State<AppConfig>) -> rocket::serde::json::Json<super::objs::Instance> {
-    rocket::serde::json::Json(super::objs::Instance {
+pub async fn instance(
+    db: crate::DbConn, config: &rocket::State<AppConfig>, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::Instance>, super::Error> {
+    let stats = get_instance_stats(&db, &localizer).await?;
+
+    Ok(rocket::serde::json::Json(super::objs::Instance {
         uri: config.uri.clone(),
-        title: "Tafarn Test".to_string(),
-        short_description: "".to_string(),
-        description: "".to_string(),
-        email: "test@example.com".to_string(),
-        version: "4.0.2".to_string(),
+        title: config.instance.title.clone(),
+        short_description: config.instance.short_description.clone(),
+        description: config.instance.description.clone(),
+        email: config.instance.contact_email.clone(),
+        version: version(),
         urls: super::objs::InstanceURLs {
             streaming_api: None,
         },
         stats: super::objs::InstanceStats {
-            user_count: 0,
-            status_count: 0,
-            domain_count: 0,
+            user_count: stats.as_ref().map(|s| s.user_count as u64).unwrap_or_default(),
+            status_count: stats.as_ref().map(|s| s.status_count as u64).unwrap_or_default(),
+            domain_count: stats.as_ref().map(|s| s.domain_count as u64).unwrap_or_default(),
         },
-        thumbnail: None,
-        languages: vec!["en".to_string()],
+        thumbnail: config.instance.thumbnail.clone(),
+        languages: config.instance.languages.clone(),
         registrations: true,
         approval_required: false,
-        contact_account: None,
+        contact_account: contact_account(&db, config, &localizer).await?,
         invites_enabled: false,
-    })
+    }))
 }
 
 #[get("/api/v2/instance")]
-pub async fn instance_v2(config: &rocket::State<AppConfig>) -> rocket::serde::json::Json<super::objs::InstanceV2> {
-    rocket::serde::json::Json(super::objs::InstanceV2 {
+pub async fn instance_v2(
+    db: crate::DbConn, config: &rocket::State<AppConfig>, limits: &rocket::data::Limits,
+    localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::InstanceV2>, super::Error> {
+    let stats = get_instance_stats(&db, &localizer).await?;
+
+    Ok(rocket::serde::json::Json(super::objs::InstanceV2 {
         domain: config.uri.clone(),
-        title: "Tafarn Test".to_string(),
-        description: "".to_string(),
-        version: "4.0.2".to_string(),
-        source_url: "".to_string(),
+        title: config.instance.title.clone(),
+        description: config.instance.short_description.clone(),
+        version: version(),
+        source_url: env!("CARGO_PKG_REPOSITORY").to_string(),
         usage: super::objs::InstanceV2Usage {
             users: super::objs::InstanceV2UsageUsers {
-                active_month: 0,
+                active_month: stats.as_ref().map(|s| s.active_month as u64).unwrap_or_default(),
             },
         },
         configuration: InstanceV2Configuration {
@@ -45,17 +99,17 @@ pub async fn instance_v2(config: &rocket::State<AppConfig>) -> rocket::serde::js
                 streaming_api: "".to_string(),
             },
             accounts: super::objs::InstanceV2Accounts {
-                max_featured_tags: 0
+                max_featured_tags: super::featured_tags::MAX_FEATURED_TAGS as u64
             },
             statuses: super::objs::InstanceV2Statuses {
-                max_characters: 500,
-                max_media_attachments: 0,
-                characters_reserved_per_url: 0
+                max_characters: UNLIMITED,
+                max_media_attachments: UNLIMITED,
+                characters_reserved_per_url: 0,
             },
             media_attachments: super::objs::InstanceV2MediaAttachments {
-                supported_mime_types: vec![],
-                image_size_limit: 0,
-                image_matrix_limit: 0,
+                supported_mime_types: super::media::SUPPORTED_MIME_TYPES.iter().map(|m| m.to_string()).collect(),
+                image_size_limit: limits.get("file").unwrap_or(rocket::data::Limits::FILE).as_u64(),
+                image_matrix_limit: UNLIMITED,
                 video_size_limit: 0,
                 video_frame_rate_limit: 0,
                 video_matrix_limit: 0
@@ -71,40 +125,76 @@ pub async fn instance_v2(config: &rocket::State<AppConfig>) -> rocket::serde::js
             }
         },
         thumbnail: super::objs::InstanceV2Thumbnail {
-            url: "".to_string(),
+            url: config.instance.thumbnail.clone()
+                .unwrap_or_else(|| format!("https://{}/static/header.png", config.uri)),
             blurhash: None,
             versions: None,
         },
-        languages: vec!["en".to_string()],
+        languages: config.instance.languages.clone(),
         registrations: super::objs::InstanceV2Registrations {
             enabled: true,
             approval_required: false,
             message: None,
         },
         contact: super::objs::InstanceV2Contact {
-            email: "test@example.com".to_string(),
-            account: None,
+            email: config.instance.contact_email.clone(),
+            account: contact_account(&db, config, &localizer).await?,
         },
-        rules: vec![]
+        rules: rules(config),
+    }))
+}
+
+#[get("/api/v1/instance/rules")]
+pub async fn instance_rules(config: &rocket::State<AppConfig>) -> rocket::serde::json::Json<Vec<super::objs::Rule>> {
+    rocket::serde::json::Json(rules(config))
+}
+
+#[get("/api/v1/instance/extended_description")]
+pub async fn instance_extended_description(
+    config: &rocket::State<AppConfig>
+) -> rocket::serde::json::Json<super::objs::ExtendedDescription> {
+    rocket::serde::json::Json(super::objs::ExtendedDescription {
+        updated_at: config.loaded_at,
+        content: config.instance.extended_description.clone(),
     })
 }
 
 #[get("/api/v1/instance/peers")]
-pub async fn instance_peers() -> rocket::serde::json::Json<Vec<String>> {
-    rocket::serde::json::Json(vec![])
+pub async fn instance_peers(
+    db: crate::DbConn, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<Vec<String>>, super::Error> {
+    Ok(rocket::serde::json::Json(crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        crate::schema::instance_peers::dsl::instance_peers
+            .select(crate::schema::instance_peers::dsl::domain)
+            .order_by(crate::schema::instance_peers::dsl::domain.asc())
+            .get_results(c)
+    }).await?))
 }
 
 #[derive(Serialize)]
 pub struct Activity {
-    week: i64,
-    statuses: i64,
-    logins: i64,
-    registrations: i64
+    week: String,
+    statuses: String,
+    logins: String,
+    registrations: String
 }
 
 #[get("/api/v1/instance/activity")]
-pub async fn instance_activity() -> rocket::serde::json::Json<Vec<Activity>> {
-    rocket::serde::json::Json(vec![])
+pub async fn instance_activity(
+    db: crate::DbConn, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<Vec<Activity>>, super::Error> {
+    let activity: Vec<crate::models::InstanceActivity> = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        crate::schema::instance_activity::dsl::instance_activity
+            .order_by(crate::schema::instance_activity::dsl::week.desc())
+            .get_results(c)
+    }).await?;
+
+    Ok(rocket::serde::json::Json(activity.into_iter().map(|a| Activity {
+        week: a.week.and_hms_opt(0, 0, 0).unwrap().timestamp().to_string(),
+        statuses: a.statuses.to_string(),
+        logins: a.logins.to_string(),
+        registrations: a.registrations.to_string(),
+    }).collect()))
 }
 
 #[get("/api/v1/custom_emojis")]
@@ -14,6 +14,22 @@ impl<'a> rocket::form::FromFormField<'a> for Focus {
     }
 }
 
+/// Content types accepted for uploads, those the `image` crate can decode with our enabled features
+pub const SUPPORTED_MIME_TYPES: [&str; 15] = [
+    "image/jpeg", "image/png", "image/gif", "image/webp", "image/tiff", "image/x-targa", "image/x-tga",
+    "image/bmp", "image/x-icon", "image/vnd.radiance", "image/x-exr", "image/x-portable-bitmap",
+    "image/x-portable-graymap", "image/x-portable-pixmap", "image/x-portable-anymap",
+];
+
+pub fn image_format(content_type: &rocket::http::ContentType) -> Option<image::ImageFormat> {
+    let content_type = content_type.to_string();
+    if SUPPORTED_MIME_TYPES.contains(&content_type.as_str()) {
+        image::ImageFormat::from_mime_type(content_type)
+    } else {
+        None
+    }
+}
+
 #[derive(FromForm)]
 pub struct MediaForm<'a> {
     file: rocket::fs::TempFile<'a>,
@@ -35,7 +51,7 @@ pub async fn upload_media(
     }
 
     let format = match form.file.content_type() {
-        Some(f) => match image::ImageFormat::from_mime_type(f.to_string()) {
+        Some(f) => match image_format(&f) {
             Some(f) => f,
             None => return Err(super::Error {
                 code: rocket::http::Status::UnprocessableEntity,
@@ -76,7 +92,7 @@ pub async fn upload_media(
     let (preview_content_type, (preview_width, preview_height)) = match &mut form.thumbnail {
         Some(thumbnail) => {
             let preview_format = match thumbnail.content_type() {
-                Some(f) => match image::ImageFormat::from_mime_type(f.to_string()) {
+                Some(f) => match image_format(&f) {
                     Some(f) => f,
                     None => return Err(super::Error {
                         code: rocket::http::Status::UnprocessableEntity,
@@ -318,7 +334,7 @@ pub async fn update_media(
 
     if let Some(thumbnail) = &mut form.thumbnail {
         let format = match thumbnail.content_type() {
-            Some(f) => match image::ImageFormat::from_mime_type(f.to_string()) {
+            Some(f) => match image_format(&f) {
                 Some(f) => f,
                 None => return Err(super::Error {
                     code: rocket::http::Status::UnprocessableEntity,
@@ -119,10 +119,13 @@ pub async fn web_finger(
     let account: Option<crate::models::Account> = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
         crate::schema::accounts::dsl::accounts.filter(
             crate::schema::accounts::dsl::username.eq(username)
+        ).filter(
+            crate::schema::accounts::dsl::local.eq(true)
         ).first(c).optional()
     }).await?;
     
     let account = match account {
+        Some(account) if account.suspended_at.is_some() || account.deleted_at.is_some() => return Err(rocket::http::Status::Gone),
         Some(account) => account,
         None => return Err(rocket::http::Status::NotFound)
     };
@@ -147,3 +150,35 @@ pub async fn web_finger(
     })
 }
 
+/// The bearer token sent with a request, if any
+pub struct BearerToken(Option<String>);
+
+#[rocket::async_trait]
+impl<'r> rocket::request::FromRequest<'r> for BearerToken {
+    type Error = ();
+
+    async fn from_request(request: &'r rocket::Request<'_>) -> rocket::request::Outcome<Self, Self::Error> {
+        rocket::request::Outcome::Success(BearerToken(
+            request.headers().get_one("Authorization")
+                .and_then(|h| h.strip_prefix("Bearer "))
+                .map(|t| t.trim().to_string())
+        ))
+    }
+}
+
+#[get("/metrics")]
+pub async fn metrics(
+    config: &rocket::State<AppConfig>, token: BearerToken
+) -> Result<(rocket::http::ContentType, String), rocket::http::Status> {
+    if !config.metrics.enabled {
+        return Err(rocket::http::Status::NotFound);
+    }
+    if !crate::metrics::check_token(config.metrics.bearer_token.as_deref(), token.0.as_deref()) {
+        return Err(rocket::http::Status::Unauthorized);
+    }
+
+    Ok((
+        rocket::http::ContentType::new("text", "plain").with_params(("version", "0.0.4")),
+        crate::metrics::render()
+    ))
+}
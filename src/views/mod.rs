@@ -24,6 +24,15 @@ pub mod favourites;
 pub mod objs;
 pub mod activity_streams;
 pub mod nodeinfo;
+pub mod trends;
+pub mod reports;
+pub mod admin;
+pub mod imports;
+pub mod exports;
+pub mod archives;
+pub mod groups;
+pub mod featured_tags;
+pub mod endorsements;
 
 pub fn parse_bool(s: Option<&str>, default: bool, localizer: &crate::i18n::Localizer) -> Result<bool, Error> {
     Ok(match s {
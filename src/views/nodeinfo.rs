@@ -190,11 +190,27 @@ impl<'r, T: serde::ser::Serialize> rocket::response::Responder<'r, 'static> for
     }
 }
 
+async fn usage(db: &crate::DbConn, localizer: &crate::i18n::Localizer) -> Result<Usage2_0, rocket::http::Status> {
+    let stats = super::instance::get_instance_stats(db, localizer).await?;
+
+    Ok(Usage2_0 {
+        users: Users2_0 {
+            total: stats.as_ref().map(|s| s.user_count as u64),
+            active_half_year: stats.as_ref().map(|s| s.active_half_year as u64),
+            active_month: stats.as_ref().map(|s| s.active_month as u64)
+        },
+        local_posts: stats.as_ref().map(|s| s.status_count as u64),
+        local_comments: stats.as_ref().map(|s| s.local_comments as u64)
+    })
+}
+
 #[get("/nodeinfo/2.1")]
-pub fn node_info_2_1() -> NodeInfo<NodeInfo2_1> {
+pub async fn node_info_2_1(
+    db: crate::DbConn, localizer: crate::i18n::Localizer
+) -> Result<NodeInfo<NodeInfo2_1>, rocket::http::Status> {
     let repository = env!("CARGO_PKG_REPOSITORY").to_string();
     let homepage = env!("CARGO_PKG_HOMEPAGE").to_string();
-    NodeInfo {
+    Ok(NodeInfo {
         inner: NodeInfo2_1 {
             version: "2.1".to_string(),
             software: Software2_1 {
@@ -214,24 +230,18 @@ pub fn node_info_2_1() -> NodeInfo<NodeInfo2_1> {
             protocols: vec![Protocols2_0::ActivityPub],
             services: Services2_0 { inbound: vec![], outbound: vec![] },
             open_registrations: true,
-            usage: Usage2_0 {
-                users: Users2_0 {
-                    total: None,
-                    active_half_year: None,
-                    active_month: None
-                },
-                local_posts: None,
-                local_comments: None
-            },
+            usage: usage(&db, &localizer).await?,
             metadata: Default::default()
         },
         profile: "http://nodeinfo.diaspora.software/ns/schema/2.1#"
-    }
+    })
 }
 
 #[get("/nodeinfo/2.0")]
-pub fn node_info_2_0() -> NodeInfo<NodeInfo2_0> {
-    NodeInfo {
+pub async fn node_info_2_0(
+    db: crate::DbConn, localizer: crate::i18n::Localizer
+) -> Result<NodeInfo<NodeInfo2_0>, rocket::http::Status> {
+    Ok(NodeInfo {
         inner: NodeInfo2_0 {
             version: "2.1".to_string(),
             software: Software2_0 {
@@ -241,17 +251,9 @@ pub fn node_info_2_0() -> NodeInfo<NodeInfo2_0> {
             protocols: vec![Protocols2_0::ActivityPub],
             services: Services2_0 { inbound: vec![], outbound: vec![] },
             open_registrations: true,
-            usage: Usage2_0 {
-                users: Users2_0 {
-                    total: None,
-                    active_half_year: None,
-                    active_month: None
-                },
-                local_posts: None,
-                local_comments: None
-            },
+            usage: usage(&db, &localizer).await?,
             metadata: Default::default()
         },
         profile: "http://nodeinfo.diaspora.software/ns/schema/2.0#"
-    }
+    })
 }
\ No newline at end of file
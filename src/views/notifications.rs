@@ -7,14 +7,18 @@ pub async fn render_notification(
     db: &crate::DbConn, config: &crate::AppConfig, notification: models::Notification,
     localizer: &crate::i18n::Localizer
 ) -> Result<super::objs::Notification, super::Error> {
-    let (account, status) = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+    let (account, status, report) = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
         let a = crate::schema::accounts::dsl::accounts.find(notification.cause).get_result(c)?;
         let s = notification.status
             .map(|sid| crate::schema::statuses::dsl::statuses.find(sid)
                 .get_result::<models::Status>(c))
             .transpose()?;
+        let r = notification.report
+            .map(|rid| crate::schema::reports::dsl::reports.find(rid)
+                .get_result::<models::Report>(c))
+            .transpose()?;
 
-        Ok((a, s))
+        Ok((a, s, r))
     }).await?;
 
     Ok(super::objs::Notification {
@@ -26,16 +30,20 @@ pub async fn render_notification(
             None => None
         },
         account: super::accounts::render_account(config, &db, &localizer, account).await?,
-        report: None,
+        report: match report {
+            Some(r) => Some(super::reports::render_report(db, config, r, localizer).await?),
+            None => None
+        },
     })
 }
 
-#[get("/api/v1/notifications?<limit>&<types>&<exclude_types>&<account_id>&<min_id>&<max_id>")]
+#[get("/api/v1/notifications?<limit>&<types>&<exclude_types>&<account_id>&<min_id>&<max_id>&<include_filtered>")]
 pub async fn notifications(
     db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
     min_id: Option<i64>, max_id: Option<i64>, limit: Option<u64>,
     types: Option<Vec<String>>, exclude_types: Option<Vec<String>>,
-    account_id: Option<String>, host: &rocket::http::uri::Host<'_>, localizer: crate::i18n::Localizer
+    account_id: Option<String>, include_filtered: Option<bool>, host: &rocket::http::uri::Host<'_>,
+    localizer: crate::i18n::Localizer
 ) -> Result<super::LinkedResponse<rocket::serde::json::Json<Vec<super::objs::Notification>>>, super::Error> {
     if !user.has_scope("read:notifications") {
         return Err(super::Error {
@@ -68,6 +76,9 @@ pub async fn notifications(
         let mut q = crate::schema::notifications::dsl::notifications.filter(
             crate::schema::notifications::dsl::account.eq(&account.id)
         ).limit(limit as i64).order_by(crate::schema::notifications::created_at.desc()).into_boxed();
+        if !include_filtered.unwrap_or(false) {
+            q = q.filter(crate::schema::notifications::dsl::filtered.eq(false));
+        }
         if let Some(types) = types {
             q = q.filter(crate::schema::notifications::dsl::notification_type.eq_any(types));
         }
@@ -165,7 +176,7 @@ pub async fn notification(
 
 #[post("/api/v1/notifications/clear")]
 pub async fn clear_notifications(
-    user: super::oauth::TokenClaims, localizer: crate::i18n::Localizer
+    db: crate::DbConn, user: super::oauth::TokenClaims, localizer: crate::i18n::Localizer
 ) -> Result<rocket::serde::json::Json<()>, super::Error> {
     if !user.has_scope("write:notifications") {
         return Err(super::Error {
@@ -174,10 +185,19 @@ pub async fn clear_notifications(
         });
     }
 
+    let account = super::accounts::get_account(&db, &localizer, &user).await?;
+    crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        diesel::delete(crate::schema::notifications::dsl::notifications.filter(
+            crate::schema::notifications::dsl::account.eq(account.id)
+        ).filter(
+            crate::schema::notifications::dsl::filtered.eq(false)
+        )).execute(c)
+    }).await?;
+
     Ok(rocket::serde::json::Json(()))
 }
 
-#[post("/api/v1/notifications/<notification_id>/dimiss")]
+#[post("/api/v1/notifications/<notification_id>/dismiss")]
 pub async fn dismiss_notification(
     db: crate::DbConn, user: super::oauth::TokenClaims, notification_id: String,
     localizer: crate::i18n::Localizer
@@ -190,7 +210,351 @@ pub async fn dismiss_notification(
     }
 
     let account = super::accounts::get_account(&db, &localizer, &user).await?;
-    let _notification = get_notification_and_check_visibility(&notification_id, &account, &db, &localizer).await?;
+    let notification = get_notification_and_check_visibility(&notification_id, &account, &db, &localizer).await?;
+
+    crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        diesel::delete(crate::schema::notifications::dsl::notifications.find(notification.id)).execute(c)
+    }).await?;
+
+    Ok(rocket::serde::json::Json(()))
+}
+
+async fn render_notification_policy(
+    db: &crate::DbConn, policy: models::NotificationPolicy, localizer: &crate::i18n::Localizer
+) -> Result<super::objs::NotificationPolicy, super::Error> {
+    let account_id = policy.account;
+    let (pending_requests_count, pending_notifications_count) = crate::db_run(db, localizer, move |c| -> QueryResult<_> {
+        let requests = crate::schema::notification_requests::dsl::notification_requests.filter(
+            crate::schema::notification_requests::dsl::account.eq(account_id)
+        ).count().get_result::<i64>(c)?;
+        let notifications = crate::schema::notifications::dsl::notifications.filter(
+            crate::schema::notifications::dsl::account.eq(account_id)
+        ).filter(
+            crate::schema::notifications::dsl::filtered.eq(true)
+        ).count().get_result::<i64>(c)?;
+        Ok((requests, notifications))
+    }).await?;
+
+    Ok(super::objs::NotificationPolicy {
+        for_not_following: policy.for_not_following,
+        for_not_followers: policy.for_not_followers,
+        for_new_accounts: policy.for_new_accounts,
+        for_private_mentions: policy.for_private_mentions,
+        summary: super::objs::NotificationPolicySummary {
+            pending_requests_count: pending_requests_count as u64,
+            pending_notifications_count: pending_notifications_count as u64,
+        }
+    })
+}
+
+async fn get_notification_policy(
+    db: &crate::DbConn, account: &models::Account, localizer: &crate::i18n::Localizer
+) -> Result<models::NotificationPolicy, super::Error> {
+    let account_id = account.id;
+    Ok(crate::db_run(db, localizer, move |c| -> QueryResult<_> {
+        crate::schema::notification_policies::dsl::notification_policies.find(account_id)
+            .get_result::<models::NotificationPolicy>(c).optional()
+    }).await?.unwrap_or_else(|| models::NotificationPolicy::default_for(account_id)))
+}
+
+#[get("/api/v2/notifications/policy")]
+pub async fn notification_policy(
+    db: crate::DbConn, user: super::oauth::TokenClaims, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::NotificationPolicy>, super::Error> {
+    if !user.has_scope("read:notifications") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let account = super::accounts::get_account(&db, &localizer, &user).await?;
+    let policy = get_notification_policy(&db, &account, &localizer).await?;
+
+    Ok(rocket::serde::json::Json(render_notification_policy(&db, policy, &localizer).await?))
+}
+
+#[derive(FromForm, Deserialize)]
+pub struct NotificationPolicyForm<'a> {
+    #[serde(default)]
+    for_not_following: Option<&'a str>,
+    #[serde(default)]
+    for_not_followers: Option<&'a str>,
+    #[serde(default)]
+    for_new_accounts: Option<&'a str>,
+    #[serde(default)]
+    for_private_mentions: Option<&'a str>,
+}
+
+#[patch("/api/v2/notifications/policy", data = "<form>", rank = 1)]
+pub async fn update_notification_policy_form(
+    db: crate::DbConn, user: super::oauth::TokenClaims, form: rocket::form::Form<NotificationPolicyForm<'_>>,
+    localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::NotificationPolicy>, super::Error> {
+    _update_notification_policy(db, user, form.into_inner(), localizer).await
+}
+
+#[patch("/api/v2/notifications/policy", data = "<form>", rank = 2)]
+pub async fn update_notification_policy_json(
+    db: crate::DbConn, user: super::oauth::TokenClaims, form: rocket::serde::json::Json<NotificationPolicyForm<'_>>,
+    localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::NotificationPolicy>, super::Error> {
+    _update_notification_policy(db, user, form.into_inner(), localizer).await
+}
+
+async fn _update_notification_policy(
+    db: crate::DbConn, user: super::oauth::TokenClaims, form: NotificationPolicyForm<'_>,
+    localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::NotificationPolicy>, super::Error> {
+    if !user.has_scope("write:notifications") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let account = super::accounts::get_account(&db, &localizer, &user).await?;
+    let mut policy = get_notification_policy(&db, &account, &localizer).await?;
+
+    let parse_action = |action: Option<&str>, current: &mut String| -> Result<(), super::Error> {
+        match action {
+            Some(a @ ("accept" | "filter" | "drop")) => {
+                *current = a.to_string();
+                Ok(())
+            }
+            Some(_) => Err(super::Error {
+                code: rocket::http::Status::UnprocessableEntity,
+                error: fl!(localizer, "error-invalid-notification-policy")
+            }),
+            None => Ok(())
+        }
+    };
+    parse_action(form.for_not_following, &mut policy.for_not_following)?;
+    parse_action(form.for_not_followers, &mut policy.for_not_followers)?;
+    parse_action(form.for_new_accounts, &mut policy.for_new_accounts)?;
+    parse_action(form.for_private_mentions, &mut policy.for_private_mentions)?;
+
+    let policy = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        diesel::insert_into(crate::schema::notification_policies::table)
+            .values(&policy)
+            .on_conflict(crate::schema::notification_policies::dsl::account)
+            .do_update()
+            .set(&policy)
+            .get_result::<models::NotificationPolicy>(c)
+    }).await?;
+
+    Ok(rocket::serde::json::Json(render_notification_policy(&db, policy, &localizer).await?))
+}
+
+async fn render_notification_request(
+    db: &crate::DbConn, config: &crate::AppConfig, request: models::NotificationRequest,
+    account: &models::Account, localizer: &crate::i18n::Localizer
+) -> Result<super::objs::NotificationRequest, super::Error> {
+    let (from_account, last_status, count) = crate::db_run(db, localizer, move |c| -> QueryResult<_> {
+        let a = crate::schema::accounts::dsl::accounts.find(request.from_account)
+            .get_result::<models::Account>(c)?;
+        let s = request.last_status
+            .map(|sid| crate::schema::statuses::dsl::statuses.find(sid)
+                .get_result::<models::Status>(c).optional())
+            .transpose()?.flatten();
+        let count = crate::schema::notifications::dsl::notifications.filter(
+            crate::schema::notifications::dsl::account.eq(request.account)
+        ).filter(
+            crate::schema::notifications::dsl::cause.eq(request.from_account)
+        ).filter(
+            crate::schema::notifications::dsl::filtered.eq(true)
+        ).count().get_result::<i64>(c)?;
+        Ok((a, s, count))
+    }).await?;
+
+    Ok(super::objs::NotificationRequest {
+        id: request.iid.to_string(),
+        created_at: Utc.from_utc_datetime(&request.created_at),
+        updated_at: Utc.from_utc_datetime(&request.updated_at),
+        account: super::accounts::render_account(config, db, localizer, from_account).await?,
+        notifications_count: count.to_string(),
+        last_status: match last_status {
+            Some(s) => Some(super::statuses::render_status(config, db, s, localizer, Some(account)).await?),
+            None => None
+        },
+    })
+}
+
+async fn get_notification_request(
+    request_id: &str, account: &models::Account, db: &crate::DbConn, localizer: &crate::i18n::Localizer
+) -> Result<models::NotificationRequest, super::Error> {
+    let request_id = match request_id.parse::<i64>() {
+        Ok(id) => id,
+        Err(_) => return Err(super::Error {
+            code: rocket::http::Status::NotFound,
+            error: fl!(localizer, "error-notification-request-not-found")
+        })
+    };
+
+    let account_id = account.id;
+    match crate::db_run(db, localizer, move |c| -> QueryResult<_> {
+        crate::schema::notification_requests::dsl::notification_requests.filter(
+            crate::schema::notification_requests::dsl::iid.eq(request_id)
+        ).filter(
+            crate::schema::notification_requests::dsl::account.eq(account_id)
+        ).get_result(c).optional()
+    }).await? {
+        Some(r) => Ok(r),
+        None => Err(super::Error {
+            code: rocket::http::Status::NotFound,
+            error: fl!(localizer, "error-notification-request-not-found")
+        })
+    }
+}
+
+#[get("/api/v1/notifications/requests?<limit>&<min_id>&<max_id>")]
+pub async fn notification_requests(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    min_id: Option<i64>, max_id: Option<i64>, limit: Option<u64>,
+    host: &rocket::http::uri::Host<'_>, localizer: crate::i18n::Localizer
+) -> Result<super::LinkedResponse<rocket::serde::json::Json<Vec<super::objs::NotificationRequest>>>, super::Error> {
+    if !user.has_scope("read:notifications") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let limit = limit.unwrap_or(40);
+    if limit > 80 {
+        return Err(super::Error {
+            code: rocket::http::Status::BadRequest,
+            error: fl!(localizer, "limit-too-large")
+        });
+    }
+
+    let account = super::accounts::get_account(&db, &localizer, &user).await?;
+    let account_id = account.id;
+    let requests: Vec<models::NotificationRequest> = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        let mut q = crate::schema::notification_requests::dsl::notification_requests.filter(
+            crate::schema::notification_requests::dsl::account.eq(account_id)
+        ).limit(limit as i64).order_by(crate::schema::notification_requests::iid.desc()).into_boxed();
+        if let Some(min_id) = min_id {
+            q = q.filter(crate::schema::notification_requests::dsl::iid.gt(min_id));
+        }
+        if let Some(max_id) = max_id {
+            q = q.filter(crate::schema::notification_requests::dsl::iid.lt(max_id));
+        }
+        q.load(c)
+    }).await?;
+
+    let mut links = vec![];
+
+    if let Some(last_id) = requests.last().map(|a| a.iid) {
+        links.push(super::Link {
+            rel: "next".to_string(),
+            href: format!("https://{}/api/v1/notifications/requests?max_id={}", host, last_id)
+        });
+    }
+    if let Some(first_id) = requests.first().map(|a| a.iid) {
+        links.push(super::Link {
+            rel: "prev".to_string(),
+            href: format!("https://{}/api/v1/notifications/requests?min_id={}", host, first_id)
+        });
+    }
+
+    Ok(super::LinkedResponse {
+        inner: rocket::serde::json::Json(
+            futures::stream::iter(requests)
+                .map(|r| render_notification_request(&db, config, r, &account, &localizer))
+                .buffered(10)
+                .collect::<Vec<_>>().await.into_iter().collect::<Result<Vec<_>, _>>()?
+        ),
+        links
+    })
+}
+
+#[get("/api/v1/notifications/requests/<request_id>")]
+pub async fn notification_request(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    request_id: String, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::NotificationRequest>, super::Error> {
+    if !user.has_scope("read:notifications") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let account = super::accounts::get_account(&db, &localizer, &user).await?;
+    let request = get_notification_request(&request_id, &account, &db, &localizer).await?;
+
+    Ok(rocket::serde::json::Json(render_notification_request(&db, config, request, &account, &localizer).await?))
+}
+
+#[post("/api/v1/notifications/requests/<request_id>/accept")]
+pub async fn accept_notification_request(
+    db: crate::DbConn, user: super::oauth::TokenClaims, request_id: String,
+    localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<()>, super::Error> {
+    if !user.has_scope("write:notifications") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let account = super::accounts::get_account(&db, &localizer, &user).await?;
+    let request = get_notification_request(&request_id, &account, &db, &localizer).await?;
+
+    crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        c.transaction(|| {
+            diesel::insert_into(crate::schema::notification_permissions::table)
+                .values(models::NotificationPermission {
+                    account: request.account,
+                    from_account: request.from_account,
+                    created_at: Utc::now().naive_utc(),
+                })
+                .on_conflict_do_nothing()
+                .execute(c)?;
+            diesel::update(crate::schema::notifications::dsl::notifications.filter(
+                crate::schema::notifications::dsl::account.eq(request.account)
+            ).filter(
+                crate::schema::notifications::dsl::cause.eq(request.from_account)
+            )).set(crate::schema::notifications::dsl::filtered.eq(false)).execute(c)?;
+            diesel::delete(crate::schema::notification_requests::dsl::notification_requests.find(request.id))
+                .execute(c)?;
+            Ok(())
+        })
+    }).await?;
+
+    Ok(rocket::serde::json::Json(()))
+}
+
+#[post("/api/v1/notifications/requests/<request_id>/dismiss")]
+pub async fn dismiss_notification_request(
+    db: crate::DbConn, user: super::oauth::TokenClaims, request_id: String,
+    localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<()>, super::Error> {
+    if !user.has_scope("write:notifications") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    let account = super::accounts::get_account(&db, &localizer, &user).await?;
+    let request = get_notification_request(&request_id, &account, &db, &localizer).await?;
+
+    crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        c.transaction(|| {
+            diesel::delete(crate::schema::notifications::dsl::notifications.filter(
+                crate::schema::notifications::dsl::account.eq(request.account)
+            ).filter(
+                crate::schema::notifications::dsl::cause.eq(request.from_account)
+            ).filter(
+                crate::schema::notifications::dsl::filtered.eq(true)
+            )).execute(c)?;
+            diesel::delete(crate::schema::notification_requests::dsl::notification_requests.find(request.id))
+                .execute(c)?;
+            Ok(())
+        })
+    }).await?;
 
     Ok(rocket::serde::json::Json(()))
-}
\ No newline at end of file
+}
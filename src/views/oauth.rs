@@ -12,12 +12,15 @@ pub struct TokenClaims {
     pub not_before: i64,
     pub issued_at: i64,
     pub json_web_token_id: uuid::Uuid,
-    pub scopes: Vec<String>
+    pub scopes: Vec<String>,
+    pub account_id: Option<uuid::Uuid>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 struct CustomTokenClaims {
-    pub scopes: Vec<String>
+    pub scopes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account: Option<uuid::Uuid>,
 }
 
 impl TokenClaims {
@@ -36,12 +39,7 @@ impl TokenClaims {
     }
 
     pub async fn get_account(&self, db: &crate::DbConn, localizer: &crate::i18n::Localizer) -> Result<crate::models::Account, super::Error> {
-        let sub = self.subject.clone();
-        crate::db_run(db, localizer, move |c| -> QueryResult<_> {
-            crate::schema::accounts::dsl::accounts.filter(
-                crate::schema::accounts::dsl::owned_by.eq(sub)
-            ).get_result::<crate::models::Account>(c)
-        }).await
+        super::accounts::get_account(db, localizer, self).await
     }
 
     fn sign(&self, key: &jwt_simple::algorithms::HS512Key) -> String {
@@ -57,7 +55,8 @@ impl TokenClaims {
             nonce: None,
             jwt_id: Some(self.json_web_token_id.to_string()),
             custom: CustomTokenClaims {
-                scopes: self.scopes.clone()
+                scopes: self.scopes.clone(),
+                account: self.account_id,
             }
         }).unwrap()
     }
@@ -85,7 +84,8 @@ impl TokenClaims {
             not_before: claims.invalid_before.unwrap().as_secs() as i64,
             issued_at: claims.issued_at.unwrap().as_secs() as i64,
             json_web_token_id: uuid::Uuid::parse_str(&claims.jwt_id.unwrap()).unwrap(),
-            scopes: claims.custom.scopes
+            scopes: claims.custom.scopes,
+            account_id: claims.custom.account,
         })
     }
 }
@@ -124,7 +124,7 @@ impl<'r> rocket::request::FromRequest<'r> for TokenClaims {
             return rocket::request::Outcome::Failure((rocket::http::Status::Unauthorized, ()));
         }
 
-        let claims = match TokenClaims::verify(authorization, &config) {
+        let mut claims = match TokenClaims::verify(authorization, &config) {
             Ok(c) => c,
             Err(_) => return rocket::request::Outcome::Failure((rocket::http::Status::Unauthorized, ())),
         };
@@ -141,6 +141,9 @@ impl<'r> rocket::request::FromRequest<'r> for TokenClaims {
             return rocket::request::Outcome::Failure((rocket::http::Status::Unauthorized, ()));
         }
 
+        // The token record is authoritative for which account the token acts as
+        claims.account_id = token_obj.account_id;
+
         rocket::request::Outcome::Success(claims)
     }
 }
@@ -263,6 +266,30 @@ const API_SCOPES: phf::Map<&'static str, APIScope> = phf::phf_map! {
     "push" => APIScope {
         description: "scope-push",
         parent: &[]
+    },
+    "admin:read" => APIScope {
+        description: "scope-admin-read",
+        parent: &[]
+    },
+    "admin:read:accounts" => APIScope {
+        description: "scope-admin-read-accounts",
+        parent: &["admin:read"]
+    },
+    "admin:read:reports" => APIScope {
+        description: "scope-admin-read-reports",
+        parent: &["admin:read"]
+    },
+    "admin:write" => APIScope {
+        description: "scope-admin-write",
+        parent: &[]
+    },
+    "admin:write:accounts" => APIScope {
+        description: "scope-admin-write-accounts",
+        parent: &["admin:write"]
+    },
+    "admin:write:reports" => APIScope {
+        description: "scope-admin-write-reports",
+        parent: &["admin:write"]
     }
 };
 
@@ -391,9 +418,59 @@ pub async fn _api_apps(
 struct OAuthConsentState {
     client_id: uuid::Uuid,
     scopes: Vec<String>,
-    return_to: String,
     redirect_uri: url::Url,
-    state: Option<String>,
+    code_redirect_uri: String,
+    needs_consent: bool,
+}
+
+#[derive(Serialize)]
+struct OAuthConsentAccount {
+    id: String,
+    username: String,
+    display_name: String,
+}
+
+async fn issue_code(
+    db: &crate::DbConn, localizer: &crate::i18n::Localizer, state: OAuthConsentState, user_id: String,
+    account_id: uuid::Uuid,
+) -> rocket::response::Redirect {
+    let mut redirect_uri = state.redirect_uri;
+    let client_id = state.client_id;
+    let code_redirect_uri = state.code_redirect_uri;
+    let scopes = state.scopes;
+    match crate::db_run(db, localizer, move |c| -> QueryResult<_> {
+        c.transaction(|| {
+            let id = uuid::Uuid::new_v4();
+            diesel::insert_into(crate::schema::oauth_codes::dsl::oauth_codes)
+                .values(&crate::models::OAuthCodes {
+                    id: id.clone(),
+                    client_id,
+                    user_id,
+                    time: Utc::now().naive_utc(),
+                    redirect_uri: code_redirect_uri,
+                    account_id: Some(account_id),
+                })
+                .execute(c)?;
+
+            for scope in scopes {
+                diesel::insert_into(crate::schema::oauth_code_scopes::dsl::oauth_code_scopes)
+                    .values(crate::models::OAuthCodeScopes {
+                        code_id: id.clone(),
+                        scope: scope.to_string(),
+                    })
+                    .execute(c)?;
+            }
+            Ok(id)
+        })
+    }).await {
+        Ok(id) => {
+            redirect_uri.query_pairs_mut().append_pair("code", &id.to_string());
+        }
+        Err(_) => {
+            redirect_uri.query_pairs_mut().append_pair("error", "server_error");
+        }
+    }
+    rocket::response::Redirect::to(redirect_uri.to_string())
 }
 
 #[derive(Responder)]
@@ -526,15 +603,26 @@ pub async fn oauth_authorize(
             None => true
         };
 
-        if should_consent {
+        let accounts = super::accounts::owned_accounts(&db, &localizer, &user_id).await?;
+        if accounts.is_empty() {
+            return Ok(OAuthAuthorizeResponse::Template(Template::render("oauth-error", context! {
+                message: fl!(localizer, "oauth-no-accounts"),
+                lang: localizer
+            })));
+        }
+
+        let consent_state = OAuthConsentState {
+            scopes: scopes.clone(),
+            client_id: app.id,
+            redirect_uri,
+            code_redirect_uri: c_redirect_uri,
+            needs_consent: should_consent,
+        };
+
+        // With more than one account under the identity the user has to pick which one the app acts as
+        if should_consent || accounts.len() > 1 {
             cookies.add_private(
-                rocket::http::Cookie::build("oauth_consent", serde_json::to_string(&OAuthConsentState {
-                    return_to: origin.to_string(),
-                    scopes: scopes.clone(),
-                    client_id: app.id,
-                    redirect_uri,
-                    state: state.map(|s| s.to_string()),
-                }).unwrap())
+                rocket::http::Cookie::build("oauth_consent", serde_json::to_string(&consent_state).unwrap())
                     .http_only(true)
                     .secure(true)
                     .same_site(rocket::http::SameSite::Strict)
@@ -546,43 +634,18 @@ pub async fn oauth_authorize(
                 name: app.name,
                 website: app.website,
                 scopes: scopes.into_iter().filter_map(|s| API_SCOPES.get(&s)).collect::<Vec<_>>(),
+                accounts: accounts.into_iter().map(|a| OAuthConsentAccount {
+                    id: a.id.to_string(),
+                    username: a.username,
+                    display_name: a.display_name,
+                }).collect::<Vec<_>>(),
                 csrf_token: csrf_token.to_string(),
                 lang: localizer
             })))
         } else {
-            return Ok(match crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
-                c.transaction(|| {
-                    let id = uuid::Uuid::new_v4();
-                    diesel::insert_into(crate::schema::oauth_codes::dsl::oauth_codes)
-                        .values(&crate::models::OAuthCodes {
-                            id: id.clone(),
-                            client_id: app.id,
-                            user_id,
-                            time: Utc::now().naive_utc(),
-                            redirect_uri: c_redirect_uri,
-                        })
-                        .execute(c)?;
-
-                    for scope in scopes {
-                        diesel::insert_into(crate::schema::oauth_code_scopes::dsl::oauth_code_scopes)
-                            .values(crate::models::OAuthCodeScopes {
-                                code_id: id.clone(),
-                                scope: scope.to_string(),
-                            })
-                            .execute(c)?;
-                    }
-                    Ok(id)
-                })
-            }).await {
-                Ok(id) => {
-                    redirect_uri.query_pairs_mut().append_pair("code", &id.to_string());
-                    OAuthAuthorizeResponse::RocketRedirect(rocket::response::Redirect::to(redirect_uri.to_string()))
-                }
-                Err(_) => {
-                    redirect_uri.query_pairs_mut().append_pair("error", "server_error");
-                    OAuthAuthorizeResponse::RocketRedirect(rocket::response::Redirect::to(redirect_uri.to_string()))
-                }
-            });
+            Ok(OAuthAuthorizeResponse::RocketRedirect(
+                issue_code(&db, &localizer, consent_state, user_id, accounts[0].id).await
+            ))
         }
     }
 }
@@ -599,6 +662,7 @@ pub enum OAuthConsentResponse {
 pub struct OAuthConsentForm<'r> {
     csrf_token: &'r str,
     consent: &'r str,
+    account: Option<&'r str>,
 }
 
 #[post("/oauth/consent", data = "<form>")]
@@ -636,37 +700,60 @@ pub async fn oauth_consent(
             OAuthConsentResponse::Redirect(rocket::response::Redirect::to(state_obj.redirect_uri.to_string()))
         }
         "yes" => {
-            match crate::db_run(&db, &localizer, move |c| -> diesel::result::QueryResult<_> {
-                c.transaction(|| {
-                    let id = uuid::Uuid::new_v4();
-                    diesel::insert_into(crate::schema::oauth_consents::dsl::oauth_consents)
-                        .values(&crate::models::OAuthConsents {
-                            id: id.clone(),
-                            app_id: state_obj.client_id,
-                            user_id: oidc_user.claims.subject().to_string(),
-                            time: Utc::now().naive_utc(),
-                        })
-                        .execute(c)?;
+            let user_id = oidc_user.claims.subject().to_string();
+            let accounts = match super::accounts::owned_accounts(&db, &localizer, &user_id).await {
+                Ok(a) => a,
+                Err(_) => {
+                    state_obj.redirect_uri.query_pairs_mut().append_pair("error", "server_error");
+                    return OAuthConsentResponse::Redirect(rocket::response::Redirect::to(state_obj.redirect_uri.to_string()));
+                }
+            };
+            let account = match form.account {
+                Some(a) => accounts.iter().find(|acc| acc.id.to_string() == a),
+                None if accounts.len() == 1 => accounts.first(),
+                None => None
+            };
+            let account_id = match account {
+                Some(a) => a.id,
+                None => return OAuthConsentResponse::Template(Template::render("oauth-error", context! {
+                    message: fl!(localizer, "oauth-invalid-account"),
+                    lang: localizer
+                }))
+            };
 
-                    for scope in state_obj.scopes {
-                        diesel::insert_into(crate::schema::oauth_consent_scopes::dsl::oauth_consent_scopes)
-                            .values(crate::models::OAuthConsentScopes {
-                                consent_id: id.clone(),
-                                scope: scope.to_string(),
+            if state_obj.needs_consent {
+                let client_id = state_obj.client_id;
+                let scopes = state_obj.scopes.clone();
+                let c_user_id = user_id.clone();
+                if crate::db_run(&db, &localizer, move |c| -> diesel::result::QueryResult<_> {
+                    c.transaction(|| {
+                        let id = uuid::Uuid::new_v4();
+                        diesel::insert_into(crate::schema::oauth_consents::dsl::oauth_consents)
+                            .values(&crate::models::OAuthConsents {
+                                id: id.clone(),
+                                app_id: client_id,
+                                user_id: c_user_id,
+                                time: Utc::now().naive_utc(),
                             })
                             .execute(c)?;
-                    }
-                    Ok(())
-                })
-            }).await {
-                Ok(_) => {
-                    OAuthConsentResponse::Redirect(rocket::response::Redirect::to(state_obj.return_to))
-                }
-                Err(_) => {
+
+                        for scope in scopes {
+                            diesel::insert_into(crate::schema::oauth_consent_scopes::dsl::oauth_consent_scopes)
+                                .values(crate::models::OAuthConsentScopes {
+                                    consent_id: id.clone(),
+                                    scope: scope.to_string(),
+                                })
+                                .execute(c)?;
+                        }
+                        Ok(())
+                    })
+                }).await.is_err() {
                     state_obj.redirect_uri.query_pairs_mut().append_pair("error", "server_error");
-                    OAuthConsentResponse::Redirect(rocket::response::Redirect::to(state_obj.redirect_uri.to_string()))
+                    return OAuthConsentResponse::Redirect(rocket::response::Redirect::to(state_obj.redirect_uri.to_string()));
                 }
             }
+
+            OAuthConsentResponse::Redirect(issue_code(&db, &localizer, state_obj, user_id, account_id).await)
         }
         _ => OAuthConsentResponse::Template(Template::render("oauth-error", context! {
             message: fl!(localizer, "invalid-consent"),
@@ -894,6 +981,7 @@ pub async fn _oauth_token(
                     user_id: code_obj.user_id,
                     time: Utc::now().naive_utc(),
                     revoked: false,
+                    account_id: code_obj.account_id,
                 };
 
                 c.transaction(|| -> QueryResult<_> {
@@ -931,6 +1019,7 @@ pub async fn _oauth_token(
                 not_before: now.timestamp(),
                 json_web_token_id: new_token.id,
                 scopes: scopes.clone(),
+                account_id: new_token.account_id,
             };
 
             Ok(rocket::serde::json::Json(OAuthToken {
@@ -117,6 +117,13 @@ pub struct Instance {
     pub invites_enabled: bool,
 }
 
+#[derive(Serialize)]
+pub struct ExtendedDescription {
+    #[serde(serialize_with = "serialize_timestamp")]
+    pub updated_at: DateTime<Utc>,
+    pub content: String,
+}
+
 #[derive(Serialize)]
 pub struct InstanceV2 {
     pub domain: String,
@@ -269,6 +276,33 @@ pub struct Notification {
     pub report: Option<Report>
 }
 
+#[derive(Serialize)]
+pub struct NotificationPolicy {
+    pub for_not_following: String,
+    pub for_not_followers: String,
+    pub for_new_accounts: String,
+    pub for_private_mentions: String,
+    pub summary: NotificationPolicySummary,
+}
+
+#[derive(Serialize)]
+pub struct NotificationPolicySummary {
+    pub pending_requests_count: u64,
+    pub pending_notifications_count: u64,
+}
+
+#[derive(Serialize)]
+pub struct NotificationRequest {
+    pub id: String,
+    #[serde(serialize_with = "serialize_timestamp")]
+    pub created_at: DateTime<Utc>,
+    #[serde(serialize_with = "serialize_timestamp")]
+    pub updated_at: DateTime<Utc>,
+    pub account: Account,
+    pub notifications_count: String,
+    pub last_status: Option<Status>,
+}
+
 #[derive(Serialize)]
 pub struct Status {
     pub id: String,
@@ -353,7 +387,100 @@ pub struct Context {
 }
 
 #[derive(Serialize)]
-pub struct Report {}
+pub struct Report {
+    pub id: String,
+    pub action_taken: bool,
+    #[serde(serialize_with = "serialize_timestamp_opt")]
+    pub action_taken_at: Option<DateTime<Utc>>,
+    pub category: String,
+    pub comment: String,
+    pub forwarded: bool,
+    #[serde(serialize_with = "serialize_timestamp")]
+    pub created_at: DateTime<Utc>,
+    pub status_ids: Option<Vec<String>>,
+    pub rule_ids: Option<Vec<String>>,
+    pub target_account: Account,
+}
+
+#[derive(Serialize)]
+pub struct AdminAccount {
+    pub id: String,
+    pub username: String,
+    pub domain: Option<String>,
+    #[serde(serialize_with = "serialize_timestamp")]
+    pub created_at: DateTime<Utc>,
+    pub email: Option<String>,
+    pub ip: Option<String>,
+    pub ips: Vec<String>,
+    pub locale: Option<String>,
+    pub invite_request: Option<String>,
+    pub role: String,
+    pub confirmed: bool,
+    pub approved: bool,
+    pub disabled: bool,
+    pub silenced: bool,
+    pub suspended: bool,
+    pub sensitized: bool,
+    pub account: Account,
+}
+
+#[derive(Serialize)]
+pub struct Import {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub import_type: String,
+    pub mode: String,
+    pub state: String,
+    pub total_items: u64,
+    pub imported_items: u64,
+    pub failed_items: u64,
+    #[serde(serialize_with = "serialize_timestamp")]
+    pub created_at: DateTime<Utc>,
+    #[serde(serialize_with = "serialize_timestamp_opt")]
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+pub struct Archive {
+    pub id: String,
+    pub state: String,
+    pub size: Option<u64>,
+    pub url: Option<String>,
+    #[serde(serialize_with = "serialize_timestamp")]
+    pub created_at: DateTime<Utc>,
+    #[serde(serialize_with = "serialize_timestamp_opt")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+pub struct GroupPost {
+    pub id: String,
+    pub state: String,
+    pub status: Status,
+    #[serde(serialize_with = "serialize_timestamp")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+pub struct AdminReport {
+    pub id: String,
+    pub action_taken: bool,
+    #[serde(serialize_with = "serialize_timestamp_opt")]
+    pub action_taken_at: Option<DateTime<Utc>>,
+    pub category: String,
+    pub comment: String,
+    pub forwarded: bool,
+    #[serde(serialize_with = "serialize_timestamp")]
+    pub created_at: DateTime<Utc>,
+    #[serde(serialize_with = "serialize_timestamp")]
+    pub updated_at: DateTime<Utc>,
+    pub account: AdminAccount,
+    pub target_account: AdminAccount,
+    pub assigned_account: Option<AdminAccount>,
+    pub action_taken_by_account: Option<AdminAccount>,
+    pub statuses: Vec<Status>,
+    pub rules: Vec<Rule>,
+}
 
 #[derive(Serialize)]
 pub struct WebPushSubscription {
@@ -434,6 +561,16 @@ pub struct Tag {
     pub following: Option<bool>
 }
 
+#[derive(Serialize)]
+pub struct FeaturedTag {
+    pub id: String,
+    pub name: String,
+    pub url: String,
+    pub statuses_count: u64,
+    #[serde(serialize_with = "serialize_date_opt")]
+    pub last_status_at: Option<NaiveDate>,
+}
+
 #[derive(Serialize)]
 pub struct TagHistory {
     pub day: String,
@@ -138,6 +138,12 @@ impl<'r> rocket::request::FromRequest<'r> for OIDCUser {
                             }
                         }
 
+                        let sub = id_claims.subject().to_string();
+                        let is_moderator = oidc_app.is_moderator(&id_claims);
+                        if update_moderator(&db, &localizer, sub, is_moderator).await.is_err() {
+                            return rocket::request::Outcome::Failure((rocket::http::Status::InternalServerError, ()));
+                        }
+
                         rocket::request::Outcome::Success(OIDCUser {
                             access_token: auth_res.access_token().clone(),
                             claims: id_claims,
@@ -153,6 +159,34 @@ impl<'r> rocket::request::FromRequest<'r> for OIDCUser {
     }
 }
 
+/// Records the moderator role from fresh OIDC claims, revoking any admin tokens
+/// held by a user who no longer has it.
+async fn update_moderator(
+    db: &crate::DbConn, localizer: &crate::i18n::Localizer, sub: String, is_moderator: bool
+) -> Result<(), super::Error> {
+    crate::db_run(db, localizer, move |c| -> diesel::result::QueryResult<_> {
+        c.transaction(|| -> diesel::result::QueryResult<_> {
+            diesel::update(crate::schema::accounts::dsl::accounts.filter(
+                crate::schema::accounts::dsl::owned_by.eq(&sub)
+            )).set(crate::schema::accounts::dsl::moderator.eq(is_moderator)).execute(c)?;
+
+            if !is_moderator {
+                diesel::update(crate::schema::oauth_token::dsl::oauth_token.filter(
+                    crate::schema::oauth_token::dsl::user_id.eq(&sub)
+                ).filter(
+                    crate::schema::oauth_token::dsl::id.eq_any(
+                        crate::schema::oauth_token_scopes::dsl::oauth_token_scopes.filter(
+                            crate::schema::oauth_token_scopes::dsl::scope.like("admin:%")
+                        ).select(crate::schema::oauth_token_scopes::dsl::token_id)
+                    )
+                )).set(crate::schema::oauth_token::dsl::revoked.eq(true)).execute(c)?;
+            }
+
+            Ok(())
+        })
+    }).await
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct ClientRoles {
     roles: Vec<String>
@@ -189,7 +223,8 @@ impl openidconnect::AdditionalClaims for AdditionalClaims {}
 pub struct OIDCApplication {
     client: OIDCClient,
     client_id: String,
-    required_role: Option<String>
+    required_role: Option<String>,
+    moderator_role: Option<String>,
 }
 
 impl OIDCApplication {
@@ -198,6 +233,7 @@ impl OIDCApplication {
         client_id: &str,
         client_secret: &str,
         required_role: Option<&str>,
+        moderator_role: Option<&str>,
     ) -> Result<Self, String> {
         let provider_metadata = openidconnect::core::CoreProviderMetadata::discover_async(
             openidconnect::IssuerUrl::new(issuer.to_string())
@@ -219,7 +255,8 @@ impl OIDCApplication {
         Ok(Self {
             client,
             client_id: client_id.to_string(),
-            required_role: required_role.map(|r| r.to_string())
+            required_role: required_role.map(|r| r.to_string()),
+            moderator_role: moderator_role.map(|r| r.to_string()),
         })
     }
 
@@ -227,6 +264,13 @@ impl OIDCApplication {
         &self.client_id
     }
 
+    pub fn is_moderator(&self, claims: &OIDCIdTokenClaims) -> bool {
+        match &self.moderator_role {
+            Some(role) => claims.additional_claims().has_role(&self.client_id, role),
+            None => false
+        }
+    }
+
     pub fn authorize(&self, return_uri: &str, external_uri: &str) -> Result<OIDCAuthorizeRedirect, String> {
         let redirect_uri = openidconnect::RedirectUrl::new(format!("{}/oidc/redirect", external_uri))
             .map_err(|err| format!("Invalid redirect URI: {}", err))?;
@@ -414,7 +458,7 @@ pub async fn oidc_redirect(
             .finish()
     );
 
-    match super::accounts::init_account(db, &id_claims, &lang, &localizer).await {
+    match super::accounts::init_account(&db, &id_claims, &lang, &localizer).await {
         Ok(_) => {},
         Err(_) => {
             return OIDCRedirectResponse::InternalServerError(Template::render("oauth-error", context! {
@@ -424,5 +468,17 @@ pub async fn oidc_redirect(
         }
     }
 
+    let sub = id_claims.subject().to_string();
+    let is_moderator = oidc_app.is_moderator(&id_claims);
+    match update_moderator(&db, &localizer, sub, is_moderator).await {
+        Ok(_) => {},
+        Err(_) => {
+            return OIDCRedirectResponse::InternalServerError(Template::render("oauth-error", context! {
+                    message: fl!(localizer, "internal-server-error"),
+                    lang: localizer
+                }))
+        }
+    }
+
     OIDCRedirectResponse::Redirect(Redirect::temporary(state_obj.return_uri))
 }
\ No newline at end of file
@@ -0,0 +1,204 @@
+use diesel::prelude::*;
+use chrono::prelude::*;
+use crate::models;
+
+const REPORT_CATEGORIES: [&str; 4] = ["spam", "legal", "violation", "other"];
+const MAX_COMMENT_LENGTH: usize = 1000;
+
+pub async fn render_report(
+    db: &crate::DbConn, config: &crate::AppConfig, report: models::Report,
+    localizer: &crate::i18n::Localizer
+) -> Result<super::objs::Report, super::Error> {
+    let target_account_id = report.target_account;
+    let status_ids = report.status_ids.clone();
+    let (target_account, status_iids) = crate::db_run(db, localizer, move |c| -> QueryResult<_> {
+        let a = crate::schema::accounts::dsl::accounts.find(target_account_id)
+            .get_result::<models::Account>(c)?;
+        let s = crate::schema::statuses::dsl::statuses.filter(
+            crate::schema::statuses::dsl::id.eq_any(status_ids)
+        ).select(crate::schema::statuses::dsl::iid).get_results::<i64>(c)?;
+        Ok((a, s))
+    }).await?;
+
+    Ok(super::objs::Report {
+        id: report.iid.to_string(),
+        action_taken: report.action_taken_at.is_some(),
+        action_taken_at: report.action_taken_at.map(|t| Utc.from_utc_datetime(&t)),
+        category: report.category,
+        comment: report.comment,
+        forwarded: report.forwarded,
+        created_at: Utc.from_utc_datetime(&report.created_at),
+        status_ids: if status_iids.is_empty() {
+            None
+        } else {
+            Some(status_iids.into_iter().map(|i| i.to_string()).collect())
+        },
+        rule_ids: None,
+        target_account: super::accounts::render_account(config, db, localizer, target_account).await?,
+    })
+}
+
+#[derive(FromForm)]
+pub struct ReportForm<'a> {
+    account_id: &'a str,
+    status_ids: Option<Vec<&'a str>>,
+    comment: Option<&'a str>,
+    forward: Option<&'a str>,
+    category: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+pub struct ReportJson<'a> {
+    account_id: &'a str,
+    #[serde(default)]
+    status_ids: Option<Vec<&'a str>>,
+    #[serde(default)]
+    comment: Option<&'a str>,
+    #[serde(default)]
+    forward: Option<bool>,
+    #[serde(default)]
+    category: Option<&'a str>,
+}
+
+pub struct CreateReport<'a> {
+    account_id: &'a str,
+    status_ids: Vec<&'a str>,
+    comment: &'a str,
+    forward: bool,
+    category: &'a str,
+}
+
+impl<'a> CreateReport<'a> {
+    fn from_form(value: ReportForm<'a>, localizer: &crate::i18n::Localizer) -> Result<Self, super::Error> {
+        Ok(CreateReport {
+            account_id: value.account_id,
+            status_ids: value.status_ids.unwrap_or_default(),
+            comment: value.comment.unwrap_or_default(),
+            forward: super::parse_bool(value.forward, false, localizer)?,
+            category: value.category.unwrap_or("other"),
+        })
+    }
+
+    fn from_json(value: ReportJson<'a>) -> Self {
+        CreateReport {
+            account_id: value.account_id,
+            status_ids: value.status_ids.unwrap_or_default(),
+            comment: value.comment.unwrap_or_default(),
+            forward: value.forward.unwrap_or(false),
+            category: value.category.unwrap_or("other"),
+        }
+    }
+}
+
+#[post("/api/v1/reports", data = "<form>", rank = 1)]
+pub async fn create_report_form(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    form: rocket::form::Form<ReportForm<'_>>, celery: &rocket::State<crate::CeleryApp>,
+    localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::Report>, super::Error> {
+    let form = CreateReport::from_form(form.into_inner(), &localizer)?;
+    _create_report(db, config, user, form, celery, localizer).await
+}
+
+#[post("/api/v1/reports", data = "<form>", rank = 2)]
+pub async fn create_report_json(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    form: rocket::serde::json::Json<ReportJson<'_>>, celery: &rocket::State<crate::CeleryApp>,
+    localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::Report>, super::Error> {
+    _create_report(db, config, user, CreateReport::from_json(form.into_inner()), celery, localizer).await
+}
+
+async fn _create_report(
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
+    form: CreateReport<'_>, celery: &rocket::State<crate::CeleryApp>, localizer: crate::i18n::Localizer
+) -> Result<rocket::serde::json::Json<super::objs::Report>, super::Error> {
+    if !user.has_scope("write:reports") {
+        return Err(super::Error {
+            code: rocket::http::Status::Forbidden,
+            error: fl!(localizer, "error-no-permission")
+        });
+    }
+
+    if !REPORT_CATEGORIES.contains(&form.category) {
+        return Err(super::Error {
+            code: rocket::http::Status::UnprocessableEntity,
+            error: fl!(localizer, "error-invalid-report-category")
+        });
+    }
+
+    if form.comment.chars().count() > MAX_COMMENT_LENGTH {
+        return Err(super::Error {
+            code: rocket::http::Status::UnprocessableEntity,
+            error: fl!(localizer, "error-report-comment-too-long")
+        });
+    }
+
+    let account = super::accounts::get_account(&db, &localizer, &user).await?;
+    let target_account = super::accounts::get_account_from_db(form.account_id, &db, &localizer).await?;
+
+    if target_account.id == account.id {
+        return Err(super::Error {
+            code: rocket::http::Status::UnprocessableEntity,
+            error: fl!(localizer, "error-cannot-report-self")
+        });
+    }
+
+    let mut status_iids = form.status_ids.iter()
+        .map(|s| s.parse::<i64>())
+        .collect::<Result<Vec<_>, _>>().map_err(|_| super::Error {
+            code: rocket::http::Status::UnprocessableEntity,
+            error: fl!(localizer, "error-status-not-found")
+        })?;
+    status_iids.sort_unstable();
+    status_iids.dedup();
+    let status_count = status_iids.len();
+    let target_account_id = target_account.id;
+    let status_ids = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        crate::schema::statuses::dsl::statuses.filter(
+            crate::schema::statuses::dsl::iid.eq_any(status_iids)
+        ).filter(
+            crate::schema::statuses::dsl::account_id.eq(target_account_id)
+        ).select(crate::schema::statuses::dsl::id).get_results::<uuid::Uuid>(c)
+    }).await?;
+    if status_ids.len() != status_count {
+        return Err(super::Error {
+            code: rocket::http::Status::UnprocessableEntity,
+            error: fl!(localizer, "error-status-not-found")
+        });
+    }
+
+    let now = Utc::now().naive_utc();
+    let new_report = models::NewReport {
+        id: uuid::Uuid::new_v4(),
+        account: account.id,
+        target_account: target_account.id,
+        status_ids,
+        category: form.category.to_string(),
+        comment: form.comment.to_string(),
+        forwarded: form.forward && !target_account.local,
+        url: None,
+        created_at: now,
+        updated_at: now,
+    };
+    let report: models::Report = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        diesel::insert_into(crate::schema::reports::table)
+            .values(new_report)
+            .get_result(c)
+    }).await?;
+
+    match celery.send_task(
+        crate::tasks::reports::process_report::new(report.clone())
+    ).await {
+        Ok(_) => {}
+        Err(e) => {
+            error!("Failed to submit celery task: {:?}", e);
+            return Err(super::Error {
+                code: rocket::http::Status::InternalServerError,
+                error: fl!(localizer, "internal-server-error")
+            });
+        }
+    };
+
+    Ok(rocket::serde::json::Json(render_report(&db, config, report, &localizer).await?))
+}
@@ -82,6 +82,16 @@ pub async fn render_status(
         ).get_results(c)
     }).await?;
 
+    let tags: Vec<String> = crate::db_run(db, localizer, move |c| -> QueryResult<_> {
+        crate::schema::status_tags::dsl::status_tags.filter(
+            crate::schema::status_tags::dsl::status.eq(status.id)
+        ).inner_join(
+            crate::schema::tags::table.on(
+                crate::schema::tags::dsl::id.eq(crate::schema::status_tags::dsl::tag)
+            )
+        ).select(crate::schema::tags::dsl::name).get_results(c)
+    }).await?;
+
     let boosted = match req_account_id {
         Some(account) => {
             if status.account_id == account && status.boost_of_id.is_some() {
@@ -137,6 +147,8 @@ pub async fn render_status(
         None => None
     };
 
+    let sensitive = status.sensitive || account.sensitized_at.is_some();
+
     Ok(super::objs::Status {
         id: status.iid.to_string(),
         uri: status.url(&config.uri),
@@ -144,12 +156,15 @@ pub async fn render_status(
         account: super::accounts::render_account(config, db, localizer, account).await?,
         content: status.text,
         visibility,
-        sensitive: status.sensitive,
+        sensitive,
         spoiler_text: status.spoiler_text,
         media_attachments: media_attachments.into_iter()
             .map(|(_, m)| super::media::render_media_attachment(m, config, localizer)).collect::<Result<Vec<_>, _>>()?,
         mentions: vec![],
-        tags: vec![],
+        tags: tags.into_iter().map(|t| super::objs::StatusTag {
+            url: format!("https://{}/tags/{}", config.uri, t),
+            name: t,
+        }).collect(),
         emojis: vec![],
         reblogs_count: boost_count as u64,
         favourites_count: like_count as u64,
@@ -233,6 +248,12 @@ pub async fn get_status_and_check_visibility(
     let status = match crate::db_run(db, localizer, move |c| -> QueryResult<_> {
         crate::schema::statuses::dsl::statuses.filter(
             crate::schema::statuses::dsl::iid.eq(status_id)
+        ).filter(
+            crate::schema::statuses::dsl::account_id.ne_all(
+                crate::schema::accounts::dsl::accounts.filter(
+                    crate::schema::accounts::dsl::suspended_at.is_not_null()
+                ).select(crate::schema::accounts::dsl::id)
+            )
         ).get_result::<models::Status>(c).optional()
     }).await? {
         Some(m) => m,
@@ -484,6 +505,7 @@ pub async fn _create_status(
         status: new_status.id,
         media: m.id
     }).collect::<Vec<_>>();
+    let new_status_tags = crate::tasks::statuses::extract_hashtags(status_source);
 
     let s = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
         c.transaction::<_, diesel::result::Error, _>(|| {
@@ -496,6 +518,7 @@ pub async fn _create_status(
             diesel::insert_into(crate::schema::media_attachments::dsl::media_attachments)
                 .values(new_status_media)
                 .execute(c)?;
+            crate::tasks::statuses::set_status_tags(c, s.id, &new_status_tags)?;
             Ok(s)
         })
     }).await?;
@@ -586,7 +609,7 @@ pub async fn delete_status(
 
 #[get("/api/v1/statuses/<status_id>/context")]
 pub async fn status_context(
-    db: crate::DbConn, config: &rocket::State<crate::AppConfig>,
+    db: crate::DbConn, config: &rocket::State<crate::AppConfig>, celery: &rocket::State<crate::CeleryApp>,
     user: Option<super::oauth::TokenClaims>, status_id: String, localizer: crate::i18n::Localizer
 ) -> Result<rocket::serde::json::Json<super::objs::Context>, super::Error> {
     if let Some(user) = &user {
@@ -640,6 +663,44 @@ pub async fn status_context(
         Ok((ancestors, descendants))
     }).await?;
 
+    // Remote threads are rarely complete, so fetch the rest in the background for next time.
+    // Every status in a thread leads to the same crawl, so they share a rate limit keyed on the root.
+    if !status.local {
+        let backfill_status_id = ancestors.last().map(|s| s.id).unwrap_or(status.id);
+        let should_backfill = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+            c.transaction(|| {
+                let now = Utc::now().naive_utc();
+                let last_crawled = crate::schema::thread_backfills::dsl::thread_backfills.find(backfill_status_id)
+                    .select(crate::schema::thread_backfills::dsl::crawled_at)
+                    .for_update()
+                    .get_result::<chrono::NaiveDateTime>(c).optional()?;
+                if let Some(last_crawled) = last_crawled {
+                    if now - last_crawled < chrono::Duration::minutes(crate::tasks::statuses::THREAD_BACKFILL_INTERVAL_MINUTES) {
+                        return Ok(false);
+                    }
+                }
+                diesel::insert_into(crate::schema::thread_backfills::table)
+                    .values(models::ThreadBackfill {
+                        status_id: backfill_status_id,
+                        crawled_at: now,
+                    })
+                    .on_conflict(crate::schema::thread_backfills::dsl::status_id)
+                    .do_update()
+                    .set(crate::schema::thread_backfills::dsl::crawled_at.eq(now))
+                    .execute(c)?;
+                Ok(true)
+            })
+        }).await?;
+
+        if should_backfill {
+            if let Err(err) = celery.send_task(
+                super::super::tasks::statuses::backfill_thread::new(status.clone())
+            ).await {
+                error!("Failed to submit celery task: {:?}", err);
+            }
+        }
+    }
+
     Ok(rocket::serde::json::Json(super::objs::Context {
         ancestors: futures::stream::iter(ancestors).map(|status| {
             render_status(config, &db, status, &localizer, account.as_ref())
@@ -970,7 +1031,7 @@ pub async fn unboost_status(
 #[post("/api/v1/statuses/<status_id>/pin")]
 pub async fn pin_status(
     db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
-    status_id: String, localizer: crate::i18n::Localizer
+    status_id: String, celery: &rocket::State<crate::CeleryApp>, localizer: crate::i18n::Localizer
 ) -> Result<rocket::serde::json::Json<super::objs::Status>, super::Error> {
     if !user.has_scope("write:accounts") {
         return Err(super::Error {
@@ -1003,13 +1064,29 @@ pub async fn pin_status(
             .execute(c)
     }).await?;
 
+    // Only an account's own statuses are part of its featured collection
+    if status.account_id == account.id {
+        match celery.send_task(
+            super::super::tasks::statuses::deliver_pin::new(status.clone(), account.clone())
+        ).await {
+            Ok(_) => {}
+            Err(err) => {
+                error!("Failed to submit celery task: {:?}", err);
+                return Err(super::Error {
+                    code: rocket::http::Status::InternalServerError,
+                    error: fl!(localizer, "internal-server-error")
+                });
+            }
+        };
+    }
+
     Ok(rocket::serde::json::Json(render_status(config, &db, status, &localizer, Some(&account)).await?))
 }
 
 #[post("/api/v1/statuses/<status_id>/unpin")]
 pub async fn unpin_status(
     db: crate::DbConn, config: &rocket::State<crate::AppConfig>, user: super::oauth::TokenClaims,
-    status_id: String, localizer: crate::i18n::Localizer
+    status_id: String, celery: &rocket::State<crate::CeleryApp>, localizer: crate::i18n::Localizer
 ) -> Result<rocket::serde::json::Json<super::objs::Status>, super::Error> {
     if !user.has_scope("write:accounts") {
         return Err(super::Error {
@@ -1021,7 +1098,7 @@ pub async fn unpin_status(
     let account = super::accounts::get_account(&db, &localizer, &user).await?;
     let status = get_status_and_check_visibility(&status_id, Some(&account), &db, &localizer).await?;
 
-    crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+    let deleted = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
         diesel::delete(crate::schema::pins::dsl::pins.filter(
             crate::schema::pins::dsl::status.eq(status.id)
         ).filter(
@@ -1029,5 +1106,20 @@ pub async fn unpin_status(
         )).execute(c)
     }).await?;
 
+    if deleted > 0 && status.account_id == account.id {
+        match celery.send_task(
+            super::super::tasks::statuses::deliver_unpin::new(status.clone(), account.clone())
+        ).await {
+            Ok(_) => {}
+            Err(err) => {
+                error!("Failed to submit celery task: {:?}", err);
+                return Err(super::Error {
+                    code: rocket::http::Status::InternalServerError,
+                    error: fl!(localizer, "internal-server-error")
+                });
+            }
+        };
+    }
+
     Ok(rocket::serde::json::Json(render_status(config, &db, status, &localizer, Some(&account)).await?))
 }
\ No newline at end of file
@@ -130,6 +130,14 @@ pub async fn timeline_public(
                 crate::schema::statuses::dsl::deleted_at.is_null()
             ).filter(
                 crate::schema::statuses::dsl::boost_of_url.is_null()
+            ).filter(
+                crate::schema::statuses::dsl::account_id.ne_all(
+                    crate::schema::accounts::dsl::accounts.filter(
+                        crate::schema::accounts::dsl::silenced_at.is_not_null().or(
+                            crate::schema::accounts::dsl::suspended_at.is_not_null()
+                        )
+                    ).select(crate::schema::accounts::dsl::id)
+                )
             ).limit(limit as i64).inner_join(crate::schema::statuses::table.on(
                 crate::schema::statuses::dsl::id.eq(crate::schema::public_timeline::dsl::status_id)
             )).into_boxed();
@@ -0,0 +1,183 @@
+use diesel::prelude::*;
+use chrono::prelude::*;
+use futures::StreamExt;
+use crate::models;
+
+/// Daily uses of each tag over the trend window, newest first, counted in a single query
+pub(crate) fn tag_histories(
+    c: &diesel::PgConnection, tag_ids: &[uuid::Uuid]
+) -> QueryResult<std::collections::HashMap<uuid::Uuid, Vec<super::objs::TagHistory>>> {
+    let today = Utc::now().date_naive();
+    let since = today - chrono::Duration::days(crate::tasks::trends::TREND_WINDOW_DAYS - 1);
+
+    let counts: Vec<(uuid::Uuid, chrono::NaiveDate, i64, i64)> = crate::schema::status_tags::dsl::status_tags
+        .filter(crate::schema::status_tags::dsl::tag.eq_any(tag_ids))
+        .inner_join(crate::schema::statuses::table.on(
+            crate::schema::statuses::dsl::id.eq(crate::schema::status_tags::dsl::status)
+        ))
+        .filter(crate::schema::statuses::dsl::public.eq(true))
+        .filter(crate::schema::statuses::dsl::deleted_at.is_null())
+        .filter(crate::schema::statuses::dsl::created_at.ge(since.and_hms_opt(0, 0, 0).unwrap()))
+        .group_by((
+            crate::schema::status_tags::dsl::tag,
+            diesel::dsl::sql::<diesel::sql_types::Date>("statuses.created_at::date"),
+        ))
+        .select((
+            crate::schema::status_tags::dsl::tag,
+            diesel::dsl::sql::<diesel::sql_types::Date>("statuses.created_at::date"),
+            diesel::dsl::sql::<diesel::sql_types::BigInt>("COUNT(*)"),
+            diesel::dsl::sql::<diesel::sql_types::BigInt>("COUNT(DISTINCT statuses.account_id)"),
+        ))
+        .get_results(c)?;
+
+    Ok(tag_ids.iter().map(|tag_id| {
+        let history = (0..crate::tasks::trends::TREND_WINDOW_DAYS).map(|i| {
+            let day = today - chrono::Duration::days(i);
+            let (uses, accounts) = counts.iter()
+                .find(|(t, d, _, _)| t == tag_id && *d == day)
+                .map(|(_, _, u, a)| (*u, *a))
+                .unwrap_or((0, 0));
+            super::objs::TagHistory {
+                day: day.and_hms_opt(0, 0, 0).unwrap().timestamp().to_string(),
+                uses: uses.to_string(),
+                accounts: accounts.to_string(),
+            }
+        }).collect();
+        (*tag_id, history)
+    }).collect())
+}
+
+fn page_links(path: &str, host: &rocket::http::uri::Host<'_>, limit: u64, offset: u64, count: usize) -> Vec<super::Link> {
+    let mut links = vec![];
+    if count as u64 >= limit {
+        links.push(super::Link {
+            rel: "next".to_string(),
+            href: format!("https://{}{}?offset={}", host, path, offset + limit)
+        });
+    }
+    if offset > 0 {
+        links.push(super::Link {
+            rel: "prev".to_string(),
+            href: format!("https://{}{}?offset={}", host, path, offset.saturating_sub(limit))
+        });
+    }
+    links
+}
+
+#[get("/api/v1/trends/tags?<limit>&<offset>")]
+pub async fn trends_tags(
+    config: &rocket::State<crate::AppConfig>, db: crate::DbConn, limit: Option<u64>, offset: Option<u64>,
+    host: &rocket::http::uri::Host<'_>, localizer: crate::i18n::Localizer
+) -> Result<super::LinkedResponse<rocket::serde::json::Json<Vec<super::objs::Tag>>>, super::Error> {
+    let limit = limit.unwrap_or(10);
+    if limit > 20 {
+        return Err(super::Error {
+            code: rocket::http::Status::BadRequest,
+            error: fl!(localizer, "limit-too-large")
+        });
+    }
+    let offset = offset.unwrap_or(0);
+    let require_review = config.trends_require_review;
+
+    let tags: Vec<(models::Tag, Vec<super::objs::TagHistory>)> = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        let mut sel = crate::schema::trending_tags::dsl::trending_tags.inner_join(
+            crate::schema::tags::table.on(
+                crate::schema::tags::dsl::id.eq(crate::schema::trending_tags::dsl::tag)
+            )
+        ).order_by(
+            crate::schema::trending_tags::dsl::score.desc()
+        ).select(crate::schema::tags::all_columns).limit(limit as i64).offset(offset as i64).into_boxed();
+        if require_review {
+            sel = sel.filter(crate::schema::tags::dsl::trendable.eq(true));
+        } else {
+            sel = sel.filter(crate::schema::tags::dsl::trendable.is_distinct_from(false));
+        }
+        let tags = sel.get_results::<models::Tag>(c)?;
+        let mut histories = tag_histories(c, &tags.iter().map(|t| t.id).collect::<Vec<_>>())?;
+        Ok(tags.into_iter().map(|t| {
+            let history = histories.remove(&t.id).unwrap_or_default();
+            (t, history)
+        }).collect())
+    }).await?;
+
+    let links = page_links("/api/v1/trends/tags", host, limit, offset, tags.len());
+
+    Ok(super::LinkedResponse {
+        inner: rocket::serde::json::Json(tags.into_iter().map(|(t, history)| super::objs::Tag {
+            url: format!("https://{}/tags/{}", config.uri, t.name),
+            name: t.name,
+            history,
+            following: None,
+        }).collect()),
+        links
+    })
+}
+
+#[get("/api/v1/trends?<limit>&<offset>")]
+pub async fn trends(
+    config: &rocket::State<crate::AppConfig>, db: crate::DbConn, limit: Option<u64>, offset: Option<u64>,
+    host: &rocket::http::uri::Host<'_>, localizer: crate::i18n::Localizer
+) -> Result<super::LinkedResponse<rocket::serde::json::Json<Vec<super::objs::Tag>>>, super::Error> {
+    trends_tags(config, db, limit, offset, host, localizer).await
+}
+
+#[get("/api/v1/trends/statuses?<limit>&<offset>")]
+pub async fn trends_statuses(
+    config: &rocket::State<crate::AppConfig>, db: crate::DbConn, user: Option<super::oauth::TokenClaims>,
+    limit: Option<u64>, offset: Option<u64>, host: &rocket::http::uri::Host<'_>,
+    localizer: crate::i18n::Localizer
+) -> Result<super::LinkedResponse<rocket::serde::json::Json<Vec<super::objs::Status>>>, super::Error> {
+    if let Some(user) = &user {
+        if !user.has_scope("read:statuses") {
+            return Err(super::Error {
+                code: rocket::http::Status::Forbidden,
+                error: fl!(localizer, "error-no-permission")
+            });
+        }
+    }
+
+    let limit = limit.unwrap_or(20);
+    if limit > 40 {
+        return Err(super::Error {
+            code: rocket::http::Status::BadRequest,
+            error: fl!(localizer, "limit-too-large")
+        });
+    }
+    let offset = offset.unwrap_or(0);
+    let require_review = config.trends_require_review;
+
+    let account = match &user {
+        Some(u) => Some(super::accounts::get_account(&db, &localizer, u).await?),
+        None => None
+    };
+
+    let statuses: Vec<models::Status> = crate::db_run(&db, &localizer, move |c| -> QueryResult<_> {
+        let mut sel = crate::schema::trending_statuses::dsl::trending_statuses.inner_join(
+            crate::schema::statuses::table.on(
+                crate::schema::statuses::dsl::id.eq(crate::schema::trending_statuses::dsl::status)
+            )
+        ).filter(
+            crate::schema::statuses::dsl::deleted_at.is_null()
+        ).order_by(
+            crate::schema::trending_statuses::dsl::score.desc()
+        ).select(crate::schema::statuses::all_columns).limit(limit as i64).offset(offset as i64).into_boxed();
+        if require_review {
+            sel = sel.filter(crate::schema::statuses::dsl::trendable.eq(true));
+        } else {
+            sel = sel.filter(crate::schema::statuses::dsl::trendable.is_distinct_from(false));
+        }
+        sel.get_results(c)
+    }).await?;
+
+    let links = page_links("/api/v1/trends/statuses", host, limit, offset, statuses.len());
+
+    Ok(super::LinkedResponse {
+        inner: rocket::serde::json::Json(
+            futures::stream::iter(statuses).map(|status| {
+                super::statuses::render_status(config, &db, status, &localizer, account.as_ref())
+            }).buffered(10).collect::<Vec<_>>().await
+                .into_iter().collect::<Result<Vec<_>, _>>()?
+        ),
+        links
+    })
+}